@@ -0,0 +1,25 @@
+pub mod nn_attention;
+pub mod nn_augmentation;
+pub mod nn_autograd;
+pub mod nn_clipping;
+pub mod nn_csv;
+pub mod nn_dataset;
+pub mod nn_embedding;
+pub mod nn_emnist;
+pub mod nn_gradient_check;
+pub mod nn_graph;
+pub mod nn_gzip;
+pub mod nn_idx;
+pub mod nn_layer;
+pub mod nn_loss;
+pub mod nn_matrix;
+pub mod nn_metrics;
+pub mod nn_mmap;
+pub mod nn_network;
+pub mod nn_normalization;
+pub mod nn_prefetch;
+pub mod nn_preprocessing;
+pub mod nn_recurrent;
+pub mod nn_regularization;
+pub mod nn_trainer;
+pub mod nn_vision;
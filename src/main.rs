@@ -1,56 +1,81 @@
-mod nn_emnist;
-mod nn_layer;
-mod nn_matrix;
-mod nn_network;
 use std::collections::VecDeque;
+use std::sync::Arc;
 use std::time::Instant;
 
-use nn_layer::Layer;
+use neural_network::nn_layer::{Layer, NetworkLayer};
 use std::env;
 
-use crate::nn_layer::{Gradient, Relu, Sigmoid, Softmax};
-use crate::nn_matrix::Matrix;
+use neural_network::nn_augmentation::{Augmentation, Transform};
+use neural_network::nn_dataset::{DataLoader, Dataset};
+use neural_network::nn_emnist::{InMemoryEmnist, LabelMapping, Split};
+use neural_network::nn_layer::{Gradient, Sigmoid, Softmax};
+use neural_network::nn_matrix::Matrix;
+use neural_network::nn_prefetch::Prefetcher;
+use neural_network::nn_preprocessing::OneHotEncoder;
+use neural_network::nn_trainer::Sample;
+use neural_network::{nn_matrix, nn_metrics, nn_network};
 
 
-const EMNINST_TRAIN_IMAGES:&str = "emnist/emnist-digits-train-images-idx3-ubyte";
-const EMNINST_TRAIN_LABELS:&str = "emnist/emnist-digits-train-labels-idx1-ubyte";
-const EMNINST_TEST_IMAGES:&str = "emnist/emnist-digits-test-labels-idx1-ubyte";
-const EMNINST_TEST_LABELS:&str = "emnist/emnist-digits-test-images-idx3-ubyte";
+const EMNIST_DIR: &str = "emnist";
 
-fn label_to_vec(label: u8) -> Vec<f64> {
-    let mut v: [f64; 10] = [0.0; 10];
-    v[label as usize] = 1.0;
-    v.to_vec()
+fn normalize_cast_f64(original: Vec<u8>) -> Vec<f64>{
+    original.iter().map(|f| (*f as f64) / 255.0).collect()
+} 
+
+fn no_augmentation(sample: Sample) -> Sample {
+    sample
 }
 
-fn vec_to_label(vec: &Vec<f64>) -> u8 {
-    vec.iter()
-        .enumerate()
-        .reduce(|(acc_i, acc_v), (i, v)| if v > acc_v { (i, v) } else { (acc_i, acc_v) })
-        .unwrap()
-        .0 as u8
+//Desloca a imagem 28x28 de 2 a 5 pixels em uma direção aleatória
+fn translate_randomly(image_matrix: &mut Matrix) {
+    let amount = rand::random_range(2..6);
+    match rand::random_range(0..4) {
+        0 => image_matrix.mut_translate_down(amount),
+        1 => image_matrix.mut_translate_up(amount),
+        2 => image_matrix.mut_translate_right(amount),
+        3 => image_matrix.mut_translate_left(amount),
+        _ => print!("How?"),
+    }
 }
 
-fn normalize_cast_f64(original: Vec<u8>) -> Vec<f64>{
-    original.iter().map(|f| (*f as f64) / 255.0).collect()
-} 
+//Transformações afins quase sempre, e as demais com menor frequência
+fn emnist_augmentation() -> Augmentation {
+    let mut augmentation = Augmentation::new();
+    augmentation.add(0.9, Transform::Affine {
+        rotation: (-12.0, 12.0),
+        scale: (0.9, 1.1),
+        shear: (-10.0, 10.0),
+        translation: (-3.0, 3.0),
+    });
+    augmentation.add(0.3, Transform::Elastic { alpha: (30.0, 36.0), sigma: 4.0 });
+    augmentation.add(0.3, Transform::BrightnessContrast {
+        brightness: (-0.1, 0.1),
+        contrast: (0.8, 1.2),
+    });
+    augmentation.add(0.2, Transform::GaussianNoise { std_dev: (0.0, 0.05) });
+    augmentation.add(0.2, Transform::RandomErasing {
+        area: (0.02, 0.1),
+        aspect_ratio: (0.3, 3.3),
+        value: 0.0,
+    });
+    augmentation
+}
 
-fn train_emnist(network: &mut nn_network::NeuralNetwork, max_samples: u32, mixing_f: fn(Vec<u8>)->Vec<f64>) {
-    let mut parser = nn_emnist::Parser::setup(
-        EMINST_TRAIN_LABELS,
-        EMINST_TRAIN_IMAGES,
+fn train_emnist(network: &mut nn_network::NeuralNetwork, data: &Arc<InMemoryEmnist>, max_samples: u32, augment: impl Fn(Sample) -> Sample + Send + Sync + 'static) {
+    let num_samples = data.len().min(max_samples as usize);
+    //Leitura, normalização e aumento de dados em outras threads, enquanto a rede treina
+    let mut loader = DataLoader::new((0..num_samples).collect(), 1);
+    let batches = Prefetcher::from_loader(
+        &mut loader,
+        Arc::clone(data),
+        augment,
+        Prefetcher::default_workers(),
+        256,
     );
     println!("Iniciando treinamento...");
     let start = Instant::now();
     let mut samples = 0;
-    while parser.has_more() && samples < max_samples {
-        let (img, label) = parser.read_next();
-        //Normaliza o valor dos pixels para 0..1 dividindo por 255
-        let vec64 = mixing_f(img);
-
-        let input = nn_matrix::Matrix::from_vec(784, 1, vec64);
-        let expected = nn_matrix::Matrix::from_vec(10, 1, label_to_vec(label));
-
+    for (input, expected) in batches {
         network.train(input, expected);
         samples += 1;
         // print!(".");
@@ -66,24 +91,24 @@ fn train_emnist(network: &mut nn_network::NeuralNetwork, max_samples: u32, mixin
     println!("Total Training Time is: {:?}", duration);
 }
 
-fn batch_train_emnist(network: &mut nn_network::NeuralNetwork, max_samples: u32) {
-    let mut parser = nn_emnist::Parser::setup(
-        EMINST_TRAIN_LABELS,
-        EMINST_TRAIN_IMAGES,
-    );
+//Alternativa a train_emnist, habilitada pelas chamadas comentadas em main
+#[allow(dead_code)]
+fn batch_train_emnist(network: &mut nn_network::NeuralNetwork, data: &InMemoryEmnist, split: Split, max_samples: u32) {
+    let encoder = OneHotEncoder::new(split.num_classes());
 
     println!("Iniciando treinamento...");
     let start = Instant::now();
     let mut samples = 0;
     let mut gradients: VecDeque<Gradient> = VecDeque::with_capacity(network.num_layers());
 
-    while parser.has_more() && samples < max_samples {
-        let (img, label) = parser.read_next();
+    while (samples as usize) < data.len() && samples < max_samples {
+        let index = samples as usize;
+        let (img, label) = (data.image(index).to_vec(), data.raw_label(index));
         //Normaliza o valor dos pixels para 0..1 dividindo por 255
-        let vec64 = img.iter().map(|f| (*f as f64 / 255.0)).collect();
+        let vec64 = img.iter().map(|f| *f as f64 / 255.0).collect();
 
         let input = nn_matrix::Matrix::from_vec(784, 1, vec64);
-        let expected = nn_matrix::Matrix::from_vec(10, 1, label_to_vec(label));
+        let expected = encoder.encode(split.class_index(label));
 
         network.train_batch(input, expected, &mut gradients);
         samples += 1;
@@ -119,23 +144,21 @@ fn batch_train_emnist(network: &mut nn_network::NeuralNetwork, max_samples: u32)
     println!("Total Training Time is: {:?}", duration);
 }
 
-fn test_emnist_on_training(network: &mut nn_network::NeuralNetwork, max_samples: u32, mixing_f: fn(Vec<u8>)->Vec<f64>) {
-    let mut test_parser = nn_emnist::Parser::setup(
-        EMINST_TRAIN_LABELS,
-        EMINST_TRAIN_IMAGES,
-    );
+fn test_emnist_on_training(network: &mut nn_network::NeuralNetwork, test_data: &InMemoryEmnist, split: Split, max_samples: u32, mixing_f: fn(Vec<u8>)->Vec<f64>) {
+    let encoder = OneHotEncoder::new(split.num_classes());
     let mut right_classification = 0;
     let mut test_samples = 0;
-    while test_parser.has_more() && test_samples < max_samples {
-        let (img, label) = test_parser.read_next();
+    while (test_samples as usize) < test_data.len() && test_samples < max_samples {
+        let index = test_samples as usize;
+        let (img, label) = (test_data.image(index).to_vec(), test_data.raw_label(index));
         //Normaliza o valor dos pixels para 0..1 dividindo por 255
         let vec64 = mixing_f(img); 
         
         let input = nn_matrix::Matrix::from_vec(784, 1, vec64);
 
         let output = network.classify(&input);
-        let out_label = vec_to_label(output.data());
-        if out_label == label {
+        let out_label = encoder.decode(output);
+        if out_label == split.class_index(label) {
             right_classification += 1;
         }
         //println!("Label: {}. Found: {} Output:{}", label, out_label, output);
@@ -149,21 +172,21 @@ fn test_emnist_on_training(network: &mut nn_network::NeuralNetwork, max_samples:
     );
 }
 
-fn test_emnist(network: &mut nn_network::NeuralNetwork, max_samples: u32, mixing_f: fn(Vec<u8>)->Vec<f64>) {
-    let mut test_parser = nn_emnist::Parser::setup(
-        EMINST_TEST_LABELS,
-        EMINST_TEST_IMAGES,
-    );
+fn test_emnist(network: &mut nn_network::NeuralNetwork, test_data: &InMemoryEmnist, split: Split, max_samples: u32, mixing_f: fn(Vec<u8>)->Vec<f64>) {
+    let encoder = OneHotEncoder::new(split.num_classes());
     let mut right_classification = 0;
     let mut test_samples = 0;
-    while test_parser.has_more() && test_samples < max_samples {
-        let (img, label) = test_parser.read_next();
+    let mut metrics = nn_metrics::ClassificationMetrics::new(split.num_classes());
+    while (test_samples as usize) < test_data.len() && test_samples < max_samples {
+        let index = test_samples as usize;
+        let (img, label) = (test_data.image(index).to_vec(), test_data.raw_label(index));
         let vec64 = mixing_f(img);
         let input = nn_matrix::Matrix::from_vec(784, 1, vec64);
 
         let output = network.classify(&input);
-        let out_label = vec_to_label(output.data());
-        if out_label == label {
+        metrics.add(output, split.class_index(label));
+        let out_label = encoder.decode(output);
+        if out_label == split.class_index(label) {
             right_classification += 1;
         }
         //println!("Label: {}. Found: {} Output:{}", label, out_label, output);
@@ -175,6 +198,7 @@ fn test_emnist(network: &mut nn_network::NeuralNetwork, max_samples: u32, mixing
         "Total Samples: {}. Right Classifications:{}. Accuracy: {}%",
         test_samples, right_classification, accuracy
     );
+    println!("{}", metrics.report());
 }
 
 fn main() {
@@ -194,6 +218,30 @@ fn main() {
     } else {
         (280000, 280000)
     };
+    //Terceiro argumento: divisão do EMNIST (byclass, bymerge, balanced, letters, digits ou mnist)
+    let split = match args.get(3) {
+        Some(name) => Split::from_name(name).expect("Divisao do EMNIST desconhecida"),
+        None => Split::Digits,
+    };
+    println!("Divisao: {}. Classes: {}.", split.name(), split.num_classes());
+    match LabelMapping::read_file(&split.mapping_file(EMNIST_DIR)) {
+        Ok(mapping) => {
+            assert!(mapping.num_classes() == split.num_classes());
+            let classes: Vec<String> = (0..split.num_classes())
+                .map(|class| {
+                    let characters = mapping.characters(split.label(class)).unwrap_or("?");
+                    format!("{}={}", class, characters)
+                })
+                .collect();
+            println!("Classes: {}", classes.join(" "));
+        }
+        Err(error) => println!("Mapeamento dos rotulos indisponivel: {}", error),
+    }
+
+    //Cada arquivo é lido uma única vez; o conjunto de treino é compartilhado com as threads de leitura
+    println!("Carregando EMNIST...");
+    let train_data = Arc::new(InMemoryEmnist::open(EMNIST_DIR, split, true).unwrap());
+    let test_data = InMemoryEmnist::open(EMNIST_DIR, split, false).unwrap();
 
     let temp = vec![0.0];
 
@@ -203,7 +251,7 @@ fn main() {
         cached_sum: 0.0,
     };
 
-    let _closure = move |val: f64, z: &Vec<f64>| cacheable_softmax.activate(val, z);
+    let _closure = move |val: f64, z: &[f64]| cacheable_softmax.activate(val, z);
 
     let mut network = nn_network::NeuralNetwork::new(4, 0.4);
 
@@ -212,12 +260,12 @@ fn main() {
     let hidden_layer1 = Layer::new::<Sigmoid>(784, 128);
     let hidden_layer2 = Layer::new::<Sigmoid>(128, 128);
     let hidden_layer3 = Layer::new::<Sigmoid>(128, 128);
-    let output_layer = Layer::new::<Sigmoid>(128, 10);
+    let output_layer = Layer::new::<Sigmoid>(128, split.num_classes());
     //Não implementei a derivada da Softmax por preguiça. Como é uma função matricial, precisaria estudar jacobianas para lembrar como isso é feito.
     //Para que a função softmax funcionasse com cache, seria necessário criar a camada com a closure.
     // let output_layer = Layer::new_with_function(128, 10, _closure, Softmax::derivative_with_xentropy);
 
-    let mut last_epoch_weights = hidden_layer3.weights().clone();
+    let last_epoch_weights = hidden_layer3.weights().clone();
 
     //network.add_layer(input_layer);
     network.add_layer(hidden_layer1);
@@ -228,41 +276,37 @@ fn main() {
     //while epoch < max_epochs {
     println!("Epoch {}.", epoch);
     println!("Training on EMNIST DataSet...");
-    train_emnist(&mut network, training_samples, normalize_cast_f64);
-    //batch_train_emnist(&mut network, training_samples);
+    train_emnist(&mut network, &train_data, training_samples, no_augmentation);
+    //batch_train_emnist(&mut network, &train_data, split, training_samples);
     //println!("HL3 weights: {}", network.borrow_layer(3).weights());
     println!(
         "HL3 layer wieghts changed? {}",
         last_epoch_weights != *network.borrow_layer(3).weights()
     );
-    last_epoch_weights = network.borrow_layer(3).weights().clone();
+    //Necessário para repetir a comparação na segunda época (comentada abaixo)
+    // last_epoch_weights = network.borrow_layer(3).weights().clone();
 
     println!("Test on Training samples...");
-    test_emnist_on_training(&mut network, test_samples, normalize_cast_f64);
+    test_emnist_on_training(&mut network, &train_data, split, test_samples, normalize_cast_f64);
     println!("Testing neural net...");
-    test_emnist(&mut network, test_samples, normalize_cast_f64);
+    test_emnist(&mut network, &test_data, split, test_samples, normalize_cast_f64);
     epoch += 1;
 
 
     let randomize_translation = |img:Vec<u8>|{
         
         let mut image_matrix = Matrix::from_vec(28, 28, normalize_cast_f64(img));
-        
-        let amount = rand::random_range(2..6); 
-        match rand::random_range(0..4){
-            0 => image_matrix.mut_translate_down(amount),    
-            1 => image_matrix.mut_translate_up(amount),    
-            2 => image_matrix.mut_translate_right(amount),    
-            3 => image_matrix.mut_translate_left(amount),
-            _ => print!("How?")    
-        }
+        translate_randomly(&mut image_matrix);
         image_matrix.data_mov()
     };
 
     println!("Epoch {}.", epoch);
-    println!("Training on EMNIST DataSet With Translation...");
-    train_emnist(&mut network, training_samples, randomize_translation);
-    //batch_train_emnist(&mut network, training_samples);
+    println!("Training on EMNIST DataSet With Augmentation...");
+    let augmentation = emnist_augmentation();
+    train_emnist(&mut network, &train_data, training_samples, move |sample| {
+        augmentation.apply_sample(sample, 28, 28)
+    });
+    //batch_train_emnist(&mut network, &train_data, split, training_samples);
     //println!("HL3 weights: {}", network.borrow_layer(3).weights());
     // println!(
     //     "HL3 layer wieghts changed? {}",
@@ -271,8 +315,8 @@ fn main() {
     // last_epoch_weights = network.borrow_layer(3).weights().clone();
 
     println!("Test on Training samples...");
-    test_emnist_on_training(&mut network, test_samples, randomize_translation);
+    test_emnist_on_training(&mut network, &train_data, split, test_samples, randomize_translation);
     println!("Testing neural net...");
-    test_emnist(&mut network, test_samples, randomize_translation);
+    test_emnist(&mut network, &test_data, split, test_samples, randomize_translation);
     //}
 }
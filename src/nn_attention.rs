@@ -0,0 +1,531 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://arxiv.org/abs/1706.03762 (Attention Is All You Need)
+//https://arxiv.org/abs/2010.11929 (ViT: imagens como sequências de patches)
+use crate::nn_layer::{Gradient, NetworkLayer};
+use crate::nn_matrix::Matrix;
+
+/**
+ * Atenção multi-cabeça (self-attention).
+ * A entrada é uma sequência de tokens, um por coluna (dimensão do modelo x tokens), e a saída tem o mesmo formato.
+ *
+ * Q = W_q * X + b_q, K = W_k * X + b_k, V = W_v * X + b_v
+ * Para cada cabeça h (bloco de linhas de Q, K e V com dimensão d_k = d / cabeças):
+ *   A_h = softmax_linhas(Q_h^T * K_h / sqrt(d_k))   -> A_h[i][j]: atenção do token i ao token j
+ *   O_h = V_h * A_h^T
+ * Y = W_o * [O_1; ...; O_n] + b_o
+ *
+ * Com a máscara causal, o token i só atende aos tokens j <= i.
+ * Pesos: [W_q; W_k; W_v; W_o] empilhados (4d x d). Viéses: [b_q; b_k; b_v; b_o] (4d x 1).
+ */
+pub struct MultiHeadAttention {
+    model_dim: usize,
+    num_heads: usize,
+    causal: bool,
+    weights: Matrix,
+    biases: Matrix,
+    neurons: Matrix,
+    //Valores guardados para a retropropagação
+    queries: Matrix,
+    keys: Matrix,
+    values: Matrix,
+    attention: Vec<Matrix>, //Uma matriz (tokens x tokens) por cabeça
+    heads_output: Matrix,
+}
+
+impl MultiHeadAttention {
+    pub fn new(model_dim: usize, num_heads: usize) -> MultiHeadAttention {
+        assert!(num_heads > 0 && model_dim.is_multiple_of(num_heads));
+        //Cada projeção é inicializada separadamente e as 4 são empilhadas
+        let data: Vec<f64> = (0..4)
+            .flat_map(|_| Matrix::new_random_glorot(model_dim, model_dim).data_mov())
+            .collect();
+        MultiHeadAttention {
+            model_dim,
+            num_heads,
+            causal: false,
+            weights: Matrix::from_vec(4 * model_dim, model_dim, data),
+            biases: Matrix::new(4 * model_dim, 1),
+            neurons: Matrix::new(model_dim, 1),
+            queries: Matrix::new(model_dim, 1),
+            keys: Matrix::new(model_dim, 1),
+            values: Matrix::new(model_dim, 1),
+            attention: Vec::new(),
+            heads_output: Matrix::new(model_dim, 1),
+        }
+    }
+
+    pub fn set_causal(&mut self, causal: bool) {
+        self.causal = causal;
+    }
+
+    /**
+     * Pesos de atenção calculados na última propagação, um por cabeça (tokens x tokens).
+     */
+    pub fn attention(&self) -> &Vec<Matrix> {
+        &self.attention
+    }
+
+    fn head_dim(&self) -> usize {
+        self.model_dim / self.num_heads
+    }
+
+    //Projeção k (0: Q, 1: K, 2: V, 3: saída) aplicada à entrada
+    fn project(&self, projection: usize, input: &Matrix) -> Matrix {
+        let weights = self
+            .weights
+            .rows_range(projection * self.model_dim, self.model_dim);
+        let mut result = &weights * input;
+        result.mut_add_column(
+            &self
+                .biases
+                .rows_range(projection * self.model_dim, self.model_dim),
+        );
+        result
+    }
+}
+
+impl NetworkLayer for MultiHeadAttention {
+    fn propagate(&mut self, input: &Matrix) {
+        assert!(input.rows() == self.model_dim);
+        let tokens = input.cols();
+        let head_dim = self.head_dim();
+        let scale = 1.0 / (head_dim as f64).sqrt();
+
+        self.queries = self.project(0, input);
+        self.keys = self.project(1, input);
+        self.values = self.project(2, input);
+        self.heads_output = Matrix::new(self.model_dim, tokens);
+        self.attention = Vec::with_capacity(self.num_heads);
+
+        for head in 0..self.num_heads {
+            let first = head * head_dim;
+            let q = self.queries.rows_range(first, head_dim);
+            let k = self.keys.rows_range(first, head_dim);
+            let v = self.values.rows_range(first, head_dim);
+
+            let mut scores = (&q.transpose() * &k).scalar_product(scale);
+            if self.causal {
+                for i in 0..tokens {
+                    for j in i + 1..tokens {
+                        scores[i][j] = f64::NEG_INFINITY;
+                    }
+                }
+            }
+            let attention = scores.softmax_rows();
+            self.heads_output
+                .set_rows(first, &(&v * &attention.transpose()));
+            self.attention.push(attention);
+        }
+        self.neurons = self.project(3, &self.heads_output);
+    }
+
+    fn neurons(&self) -> &Matrix {
+        &self.neurons
+    }
+    fn weights(&self) -> &Matrix {
+        &self.weights
+    }
+    fn biases(&self) -> &Matrix {
+        &self.biases
+    }
+    fn fix_weights(&mut self, weights: Matrix) {
+        assert!(weights.rows() == self.weights.rows() && weights.cols() == self.weights.cols());
+        self.weights = weights;
+    }
+    fn fix_bias(&mut self, biases: Matrix) {
+        assert!(biases.rows() == self.biases.rows() && biases.cols() == self.biases.cols());
+        self.biases = biases;
+    }
+
+    /**
+     * ∂C/∂W_o = ∂C/∂Y * O^T, ∂C/∂O = W_o^T * ∂C/∂Y
+     * Para cada cabeça:
+     *   ∂C/∂V_h = ∂C/∂O_h * A_h, ∂C/∂A_h = ∂C/∂O_h^T * V_h
+     *   Softmax por linha: ∂C/∂S[i][j] = A[i][j] * (∂C/∂A[i][j] - sum_k(∂C/∂A[i][k] * A[i][k]))
+     *   ∂C/∂Q_h = K_h * ∂C/∂S^T / sqrt(d_k), ∂C/∂K_h = Q_h * ∂C/∂S / sqrt(d_k)
+     * Posições mascaradas têm A = 0 e, portanto, gradiente nulo.
+     * Por fim, cada projeção P = W * X + b contribui com ∂C/∂W = ∂C/∂P * X^T e ∂C/∂X += W^T * ∂C/∂P.
+     */
+    fn backpropagate(
+        &mut self,
+        output_gradient: &Matrix,
+        prev_activations: &Matrix,
+    ) -> (Gradient, Matrix) {
+        let d = self.model_dim;
+        let tokens = prev_activations.cols();
+        let head_dim = self.head_dim();
+        let scale = 1.0 / (head_dim as f64).sqrt();
+        assert!(output_gradient.rows() == d && output_gradient.cols() == tokens);
+
+        let mut weight_gradient = Matrix::new(4 * d, d);
+        let mut bias_gradient = Matrix::new(4 * d, 1);
+
+        let output_weights = self.weights.rows_range(3 * d, d);
+        weight_gradient.set_rows(3 * d, &(output_gradient * &self.heads_output.transpose()));
+        bias_gradient.set_rows(3 * d, &output_gradient.sum_columns());
+        let heads_gradient = &output_weights.transpose() * output_gradient;
+
+        let mut queries_gradient = Matrix::new(d, tokens);
+        let mut keys_gradient = Matrix::new(d, tokens);
+        let mut values_gradient = Matrix::new(d, tokens);
+        for head in 0..self.num_heads {
+            let first = head * head_dim;
+            let q = self.queries.rows_range(first, head_dim);
+            let k = self.keys.rows_range(first, head_dim);
+            let v = self.values.rows_range(first, head_dim);
+            let attention = &self.attention[head];
+            let head_gradient = heads_gradient.rows_range(first, head_dim);
+
+            values_gradient.set_rows(first, &(&head_gradient * attention));
+            let attention_gradient = &head_gradient.transpose() * &v;
+
+            let mut scores_gradient = Matrix::new(tokens, tokens);
+            for i in 0..tokens {
+                let weighted_sum: f64 = (0..tokens)
+                    .map(|j| attention_gradient[i][j] * attention[i][j])
+                    .sum();
+                for j in 0..tokens {
+                    scores_gradient[i][j] =
+                        attention[i][j] * (attention_gradient[i][j] - weighted_sum) * scale;
+                }
+            }
+            queries_gradient.set_rows(first, &(&k * &scores_gradient.transpose()));
+            keys_gradient.set_rows(first, &(&q * &scores_gradient));
+        }
+
+        let input_transpose = prev_activations.transpose();
+        let mut input_gradient = Matrix::new(d, tokens);
+        for (projection, gradient) in [queries_gradient, keys_gradient, values_gradient]
+            .iter()
+            .enumerate()
+        {
+            let weights = self.weights.rows_range(projection * d, d);
+            weight_gradient.set_rows(projection * d, &(gradient * &input_transpose));
+            bias_gradient.set_rows(projection * d, &gradient.sum_columns());
+            input_gradient += &(&weights.transpose() * gradient);
+        }
+        (
+            Gradient {
+                weight: weight_gradient,
+                delta: bias_gradient,
+                rows: None,
+            },
+            input_gradient,
+        )
+    }
+
+    fn adjust_parameters(&mut self, gradients: &mut Gradient, learning_rate: f64) {
+        self.weights -= gradients.weight.mut_scalar_product(learning_rate);
+        self.biases -= gradients.delta.mut_scalar_product(learning_rate);
+    }
+}
+
+/**
+ * Codificação posicional senoidal (d x tokens):
+ * PE[2i][pos] = sin(pos / 10000^(2i/d)), PE[2i+1][pos] = cos(pos / 10000^(2i/d))
+ */
+pub fn positional_encoding(model_dim: usize, tokens: usize) -> Matrix {
+    let mut encoding = Matrix::new(model_dim, tokens);
+    for i in 0..model_dim {
+        let frequency = 1.0 / 10000f64.powf((i - i % 2) as f64 / model_dim as f64);
+        for pos in 0..tokens {
+            let angle = pos as f64 * frequency;
+            encoding[i][pos] = if i.is_multiple_of(2) {
+                angle.sin()
+            } else {
+                angle.cos()
+            };
+        }
+    }
+    encoding
+}
+
+/**
+ * Soma a codificação posicional aos tokens de entrada. Não possui parâmetros treináveis:
+ * os pesos e viéses são matrizes vazias e o gradiente passa inalterado.
+ */
+pub struct PositionalEncoding {
+    model_dim: usize,
+    neurons: Matrix,
+    empty: Matrix,
+}
+
+impl PositionalEncoding {
+    pub fn new(model_dim: usize) -> PositionalEncoding {
+        PositionalEncoding {
+            model_dim,
+            neurons: Matrix::new(model_dim, 1),
+            empty: Matrix::new(0, 0),
+        }
+    }
+}
+
+impl NetworkLayer for PositionalEncoding {
+    fn propagate(&mut self, input: &Matrix) {
+        assert!(input.rows() == self.model_dim);
+        self.neurons = input.clone() + &positional_encoding(self.model_dim, input.cols());
+    }
+    fn neurons(&self) -> &Matrix {
+        &self.neurons
+    }
+    fn weights(&self) -> &Matrix {
+        &self.empty
+    }
+    fn biases(&self) -> &Matrix {
+        &self.empty
+    }
+    fn fix_weights(&mut self, weights: Matrix) {
+        assert!(weights.num_elements() == 0);
+    }
+    fn fix_bias(&mut self, biases: Matrix) {
+        assert!(biases.num_elements() == 0);
+    }
+    fn backpropagate(
+        &mut self,
+        output_gradient: &Matrix,
+        _prev_activations: &Matrix,
+    ) -> (Gradient, Matrix) {
+        (
+            Gradient {
+                weight: Matrix::new(0, 0),
+                delta: Matrix::new(0, 0),
+                rows: None,
+            },
+            output_gradient.clone(),
+        )
+    }
+    fn adjust_parameters(&mut self, _gradients: &mut Gradient, _learning_rate: f64) {}
+}
+
+/**
+ * Média dos tokens (d x tokens -> d x 1). Resume a sequência em um único vetor
+ * para que uma camada densa de saída possa classificá-la.
+ */
+pub struct TokenMeanPool {
+    neurons: Matrix,
+    empty: Matrix,
+}
+
+impl TokenMeanPool {
+    pub fn new() -> TokenMeanPool {
+        TokenMeanPool {
+            neurons: Matrix::new(0, 1),
+            empty: Matrix::new(0, 0),
+        }
+    }
+}
+
+impl Default for TokenMeanPool {
+    fn default() -> Self {
+        TokenMeanPool::new()
+    }
+}
+
+impl NetworkLayer for TokenMeanPool {
+    fn propagate(&mut self, input: &Matrix) {
+        self.neurons = input
+            .sum_columns()
+            .scalar_product(1.0 / input.cols() as f64);
+    }
+    fn neurons(&self) -> &Matrix {
+        &self.neurons
+    }
+    fn weights(&self) -> &Matrix {
+        &self.empty
+    }
+    fn biases(&self) -> &Matrix {
+        &self.empty
+    }
+    fn fix_weights(&mut self, weights: Matrix) {
+        assert!(weights.num_elements() == 0);
+    }
+    fn fix_bias(&mut self, biases: Matrix) {
+        assert!(biases.num_elements() == 0);
+    }
+    //Cada token recebe 1/n do gradiente da média
+    fn backpropagate(
+        &mut self,
+        output_gradient: &Matrix,
+        prev_activations: &Matrix,
+    ) -> (Gradient, Matrix) {
+        let tokens = prev_activations.cols();
+        let mut input_gradient = Matrix::new(prev_activations.rows(), tokens);
+        for j in 0..tokens {
+            input_gradient.set_column(j, output_gradient);
+        }
+        input_gradient.mut_scalar_product(1.0 / tokens as f64);
+        (
+            Gradient {
+                weight: Matrix::new(0, 0),
+                delta: Matrix::new(0, 0),
+                rows: None,
+            },
+            input_gradient,
+        )
+    }
+    fn adjust_parameters(&mut self, _gradients: &mut Gradient, _learning_rate: f64) {}
+}
+
+/**
+ * Divide uma imagem em patches quadrados, um por coluna (patch_size² x número de patches),
+ * percorridos linha a linha. Uma camada densa com ativação Identity aplicada ao resultado
+ * projeta cada patch em um token.
+ */
+pub fn image_patches(image: &Matrix, patch_size: usize) -> Matrix {
+    assert!(image.rows().is_multiple_of(patch_size) && image.cols().is_multiple_of(patch_size));
+    let patches_per_row = image.cols() / patch_size;
+    let num_patches = (image.rows() / patch_size) * patches_per_row;
+    let mut patches = Matrix::new(patch_size * patch_size, num_patches);
+    for p in 0..num_patches {
+        let top = (p / patches_per_row) * patch_size;
+        let left = (p % patches_per_row) * patch_size;
+        for i in 0..patch_size {
+            for j in 0..patch_size {
+                patches[i * patch_size + j][p] = image[top + i][left + j];
+            }
+        }
+    }
+    patches
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_gradient_check::check_layer_gradients;
+    use crate::nn_layer::{Identity, Layer, Sigmoid};
+    use crate::nn_network::NeuralNetwork;
+
+    fn check_attention_gradients(attention: &mut MultiHeadAttention) {
+        let input = Matrix::from_vec(4, 3, (0..12).map(|i| (i as f64 * 0.7).cos()).collect());
+        let cost_weights =
+            Matrix::from_vec(4, 3, (0..12).map(|i| (i as f64 * 0.37).sin()).collect());
+        check_layer_gradients(attention, &input, &cost_weights, 1e-6);
+    }
+
+    #[test]
+    fn test_attention_gradients() {
+        let mut attention = MultiHeadAttention::new(4, 2);
+        attention.fix_bias(Matrix::from_vec(
+            16,
+            1,
+            (0..16).map(|i| i as f64 * 0.01).collect(),
+        ));
+        check_attention_gradients(&mut attention);
+
+        attention.set_causal(true);
+        check_attention_gradients(&mut attention);
+    }
+
+    #[test]
+    fn test_causal_mask() {
+        let mut attention = MultiHeadAttention::new(4, 2);
+        attention.set_causal(true);
+        let input = Matrix::from_vec(4, 3, (0..12).map(|i| (i as f64 * 0.7).cos()).collect());
+        attention.propagate(&input);
+        let first_token = attention.neurons().column(0);
+        for head in attention.attention() {
+            println!("Attention: {}", head);
+            assert!(head[0][1] == 0.0 && head[0][2] == 0.0 && head[1][2] == 0.0);
+            assert!((head[1][0] + head[1][1] - 1.0).abs() < 1e-12);
+        }
+
+        //Alterar os últimos tokens não altera a saída do primeiro
+        let mut changed = input.clone();
+        changed[1][2] += 5.0;
+        changed[3][1] -= 2.0;
+        attention.propagate(&changed);
+        assert!(attention.neurons().column(0) == first_token);
+    }
+
+    #[test]
+    fn test_positional_encoding() {
+        let encoding = positional_encoding(4, 3);
+        print!("Encoding: {}", encoding);
+        //Posição 0: sin(0) = 0, cos(0) = 1
+        assert!(encoding.column(0) == Matrix::from_vec(4, 1, vec![0.0, 1.0, 0.0, 1.0]));
+        assert!((encoding[0][1] - 1f64.sin()).abs() < 1e-12);
+        assert!((encoding[2][2] - (2.0 / 100.0f64).sin()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_image_patches() {
+        let image = Matrix::from_vec(4, 4, (0..16).map(|i| i as f64).collect());
+        let patches = image_patches(&image, 2);
+        print!("Patches: {}", patches);
+        assert!(patches.column(0) == Matrix::from_vec(4, 1, vec![0.0, 1.0, 4.0, 5.0]));
+        assert!(patches.column(3) == Matrix::from_vec(4, 1, vec![10.0, 11.0, 14.0, 15.0]));
+    }
+
+    #[test]
+    fn test_patch_transformer() {
+        //Imagens 4x4 divididas em 4 patches 2x2, projetados em tokens de dimensão 4
+        let mut network = NeuralNetwork::new(5, 0.1);
+        network.add_layer(Layer::new::<Identity>(4, 4));
+        network.add_layer(PositionalEncoding::new(4));
+        network.add_layer(MultiHeadAttention::new(4, 2));
+        network.add_layer(TokenMeanPool::new());
+        network.add_layer(Layer::new::<Sigmoid>(4, 2));
+
+        let vertical = Matrix::from_vec(
+            4,
+            4,
+            (0..16)
+                .map(|i| if i % 4 == 1 { 1.0 } else { 0.0 })
+                .collect(),
+        );
+        let horizontal = Matrix::from_vec(
+            4,
+            4,
+            (0..16)
+                .map(|i| if i / 4 == 2 { 1.0 } else { 0.0 })
+                .collect(),
+        );
+        let samples = [
+            (
+                image_patches(&vertical, 2),
+                Matrix::from_vec(2, 1, vec![1.0, 0.0]),
+            ),
+            (
+                image_patches(&horizontal, 2),
+                Matrix::from_vec(2, 1, vec![0.0, 1.0]),
+            ),
+        ];
+        let total_error = |network: &mut NeuralNetwork| -> f64 {
+            samples
+                .iter()
+                .map(|(input, expected)| {
+                    let output = network.classify(input);
+                    (0..2)
+                        .map(|i| (output[i][0] - expected[i][0]).powi(2))
+                        .sum::<f64>()
+                })
+                .sum()
+        };
+
+        let initial_error = total_error(&mut network);
+        for _ in 0..200 {
+            for (input, expected) in &samples {
+                network.train(input.clone(), expected.clone());
+            }
+        }
+        let final_error = total_error(&mut network);
+        println!("Error: {} -> {}", initial_error, final_error);
+        assert!(final_error < initial_error);
+    }
+}
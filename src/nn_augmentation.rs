@@ -0,0 +1,408 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://cognitivemedium.com/assets/rmnist/Simard.pdf (distorções elásticas)
+//https://arxiv.org/abs/1708.04896 (random erasing)
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+
+use crate::nn_matrix::Matrix;
+use crate::nn_trainer::Sample;
+
+/**
+ * Transformações aleatórias de imagens (matrizes com uma linha por linha da imagem e pixels entre 0 e 1).
+ * Os parâmetros são intervalos (mínimo, máximo), sorteados a cada aplicação.
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum Transform {
+    /**
+     * Rotação e cisalhamento em graus, escala e translação em pixels (colunas, linhas), em torno do centro.
+     * A imagem resultante é interpolada (bilinear).
+     */
+    Affine {
+        rotation: (f64, f64),
+        scale: (f64, f64),
+        shear: (f64, f64),
+        translation: (f64, f64),
+    },
+    /**
+     * Deslocamento de cada pixel por um campo aleatório suavizado por um filtro gaussiano de desvio sigma
+     * e multiplicado por alpha (Simard et al., 2003)
+     */
+    Elastic {
+        alpha: (f64, f64),
+        sigma: f64,
+    },
+    GaussianNoise {
+        std_dev: (f64, f64),
+    },
+    /**
+     * Preenche com value um retângulo com área (fração da imagem) e proporção largura/altura sorteadas
+     */
+    RandomErasing {
+        area: (f64, f64),
+        aspect_ratio: (f64, f64),
+        value: f64,
+    },
+    /**
+     * Soma brightness e multiplica a distância de cada pixel à média por contrast
+     */
+    BrightnessContrast {
+        brightness: (f64, f64),
+        contrast: (f64, f64),
+    },
+}
+
+impl Transform {
+    pub fn apply(&self, image: &mut Matrix, rng: &mut impl Rng) {
+        match *self {
+            Transform::Affine {
+                rotation,
+                scale,
+                shear,
+                translation,
+            } => {
+                let (sin, cos) = uniform(rng, rotation).to_radians().sin_cos();
+                let shear = uniform(rng, shear).to_radians().tan();
+                let scale = uniform(rng, scale);
+                let offset = (uniform(rng, translation), uniform(rng, translation));
+                //Rotação * cisalhamento horizontal * escala
+                let transform = [
+                    [cos * scale, (cos * shear - sin) * scale],
+                    [sin * scale, (sin * shear + cos) * scale],
+                ];
+                *image = image.affine(transform, offset);
+            }
+            Transform::Elastic { alpha, sigma } => {
+                let alpha = uniform(rng, alpha);
+                let dx = displacement_field(image.rows(), image.cols(), alpha, sigma, rng);
+                let dy = displacement_field(image.rows(), image.cols(), alpha, sigma, rng);
+                let mut distorted = Matrix::new(image.rows(), image.cols());
+                for i in 0..image.rows() {
+                    for j in 0..image.cols() {
+                        distorted[i][j] = image.bilinear(i as f64 + dy[i][j], j as f64 + dx[i][j]);
+                    }
+                }
+                *image = distorted;
+            }
+            Transform::GaussianNoise { std_dev } => {
+                let noise = Normal::new(0.0, uniform(rng, std_dev)).unwrap();
+                for i in 0..image.rows() {
+                    for j in 0..image.cols() {
+                        image[i][j] = (image[i][j] + noise.sample(rng)).clamp(0.0, 1.0);
+                    }
+                }
+            }
+            Transform::RandomErasing {
+                area,
+                aspect_ratio,
+                value,
+            } => {
+                let (rows, cols) = (image.rows(), image.cols());
+                //Como no artigo, sorteia até caber na imagem
+                for _ in 0..10 {
+                    let target_area = uniform(rng, area) * (rows * cols) as f64;
+                    let ratio = uniform(rng, aspect_ratio);
+                    let height = (target_area / ratio).sqrt().round() as usize;
+                    let width = (target_area * ratio).sqrt().round() as usize;
+                    if height == 0 || width == 0 || height > rows || width > cols {
+                        continue;
+                    }
+                    let top = rng.random_range(0..=rows - height);
+                    let left = rng.random_range(0..=cols - width);
+                    for i in top..top + height {
+                        for j in left..left + width {
+                            image[i][j] = value;
+                        }
+                    }
+                    break;
+                }
+            }
+            Transform::BrightnessContrast {
+                brightness,
+                contrast,
+            } => {
+                let brightness = uniform(rng, brightness);
+                let contrast = uniform(rng, contrast);
+                let mean = image.data().iter().sum::<f64>() / image.num_elements() as f64;
+                for i in 0..image.rows() {
+                    for j in 0..image.cols() {
+                        let value = (image[i][j] - mean) * contrast + mean + brightness;
+                        image[i][j] = value.clamp(0.0, 1.0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn uniform(rng: &mut impl Rng, (min, max): (f64, f64)) -> f64 {
+    assert!(min <= max, "Intervalo inválido: ({}, {})", min, max);
+    rng.random_range(min..=max)
+}
+
+//Campo de deslocamentos uniformes em -1..1, suavizado e multiplicado por alpha
+fn displacement_field(
+    rows: usize,
+    cols: usize,
+    alpha: f64,
+    sigma: f64,
+    rng: &mut impl Rng,
+) -> Matrix {
+    let data = (0..rows * cols)
+        .map(|_| rng.random_range(-1.0..=1.0))
+        .collect();
+    gaussian_blur(&Matrix::from_vec(rows, cols, data), sigma).scalar_product(alpha)
+}
+
+//Filtro gaussiano separável (linhas e depois colunas), com as bordas completadas com 0
+fn gaussian_blur(matrix: &Matrix, sigma: f64) -> Matrix {
+    assert!(sigma > 0.0);
+    let radius = (3.0 * sigma).ceil() as isize;
+    let kernel: Vec<f64> = (-radius..=radius)
+        .map(|k| (-(k * k) as f64 / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f64 = kernel.iter().sum();
+    let kernel: Vec<f64> = kernel.iter().map(|k| k / total).collect();
+
+    let (rows, cols) = (matrix.rows() as isize, matrix.cols() as isize);
+    let mut horizontal = Matrix::new(matrix.rows(), matrix.cols());
+    for i in 0..rows {
+        for j in 0..cols {
+            horizontal[i as usize][j as usize] = (-radius..=radius)
+                .filter(|k| (0..cols).contains(&(j + k)))
+                .map(|k| kernel[(k + radius) as usize] * matrix[i as usize][(j + k) as usize])
+                .sum();
+        }
+    }
+    let mut blurred = Matrix::new(matrix.rows(), matrix.cols());
+    for i in 0..rows {
+        for j in 0..cols {
+            blurred[i as usize][j as usize] = (-radius..=radius)
+                .filter(|k| (0..rows).contains(&(i + k)))
+                .map(|k| kernel[(k + radius) as usize] * horizontal[(i + k) as usize][j as usize])
+                .sum();
+        }
+    }
+    blurred
+}
+
+/**
+ * Sequência de transformações, cada uma aplicada com sua probabilidade, na ordem em que foram adicionadas
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Augmentation {
+    steps: Vec<(f64, Transform)>,
+}
+
+impl Augmentation {
+    pub fn new() -> Augmentation {
+        Augmentation { steps: Vec::new() }
+    }
+
+    pub fn add(&mut self, probability: f64, transform: Transform) {
+        assert!((0.0..=1.0).contains(&probability));
+        self.steps.push((probability, transform));
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn apply(&self, image: &mut Matrix, rng: &mut impl Rng) {
+        for (probability, transform) in &self.steps {
+            if rng.random_bool(*probability) {
+                transform.apply(image, rng);
+            }
+        }
+    }
+
+    /**
+     * Aplica à entrada de uma amostra (matriz coluna com a imagem rows x cols linha a linha),
+     * mantendo a saída esperada. Usa o gerador aleatório da thread, então pode ser usada nas threads do Prefetcher.
+     */
+    pub fn apply_sample(&self, (input, expected): Sample, rows: usize, cols: usize) -> Sample {
+        assert!(input.rows() == rows * cols && input.cols() == 1);
+        let mut image = Matrix::from_vec(rows, cols, input.data().clone());
+        self.apply(&mut image, &mut rand::rng());
+        (
+            Matrix::from_vec(rows * cols, 1, image.data().clone()),
+            expected,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    //Quadrado 2x2 claro no centro de uma imagem 6x6
+    fn square() -> Matrix {
+        let mut image = Matrix::new(6, 6);
+        for i in 2..4 {
+            for j in 2..4 {
+                image[i][j] = 1.0;
+            }
+        }
+        image
+    }
+
+    fn affine(rotation: f64, scale: f64, shear: f64, translation: f64) -> Transform {
+        Transform::Affine {
+            rotation: (rotation, rotation),
+            scale: (scale, scale),
+            shear: (shear, shear),
+            translation: (translation, translation),
+        }
+    }
+
+    #[test]
+    fn test_affine() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut image = square();
+        affine(0.0, 1.0, 0.0, 0.0).apply(&mut image, &mut rng);
+        assert!(image == square());
+
+        //Um pixel para a direita e para baixo
+        affine(0.0, 1.0, 0.0, 1.0).apply(&mut image, &mut rng);
+        assert!(image[3][3] == 1.0 && image[4][4] == 1.0 && image[2][2] == 0.0);
+
+        //O quadrado centralizado é simétrico para rotações de 90 graus
+        let mut image = square();
+        affine(90.0, 1.0, 0.0, 0.0).apply(&mut image, &mut rng);
+        assert!(image == square());
+
+        //Escala 2: a área (soma dos pixels) é multiplicada por 4
+        let mut image = square();
+        affine(0.0, 2.0, 0.0, 0.0).apply(&mut image, &mut rng);
+        let total: f64 = image.data().iter().sum();
+        assert!((total - 16.0).abs() < 1e-9 && image[1][1] > 0.0 && image[1][1] < 1.0);
+    }
+
+    #[test]
+    fn test_elastic() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut image = square();
+        let elastic = Transform::Elastic {
+            alpha: (0.0, 0.0),
+            sigma: 1.0,
+        };
+        elastic.apply(&mut image, &mut rng);
+        assert!(image == square());
+
+        let elastic = Transform::Elastic {
+            alpha: (3.0, 3.0),
+            sigma: 1.0,
+        };
+        elastic.apply(&mut image, &mut rng);
+        assert!(image != square());
+        assert!(image.data().iter().all(|&v| (0.0..=1.0).contains(&v)));
+
+        //O filtro preserva uma matriz constante longe das bordas
+        let blurred = gaussian_blur(&Matrix::from_vec(9, 9, vec![1.0; 81]), 0.5);
+        assert!((blurred[4][4] - 1.0).abs() < 1e-12 && blurred[0][0] < 1.0);
+    }
+
+    #[test]
+    fn test_noise_and_jitter() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut image = Matrix::from_vec(50, 50, vec![0.5; 2500]);
+        let noise = Transform::GaussianNoise {
+            std_dev: (0.1, 0.1),
+        };
+        noise.apply(&mut image, &mut rng);
+        let mean = image.data().iter().sum::<f64>() / 2500.0;
+        let variance = image.data().iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 2500.0;
+        assert!((mean - 0.5).abs() < 0.01 && (variance.sqrt() - 0.1).abs() < 0.01);
+
+        let mut image = square();
+        let jitter = Transform::BrightnessContrast {
+            brightness: (0.5, 0.5),
+            contrast: (1.0, 1.0),
+        };
+        jitter.apply(&mut image, &mut rng);
+        assert!(image[0][0] == 0.5 && image[2][2] == 1.0);
+
+        //Contraste 0 deixa a imagem com a média
+        let mut image = square();
+        let jitter = Transform::BrightnessContrast {
+            brightness: (0.0, 0.0),
+            contrast: (0.0, 0.0),
+        };
+        jitter.apply(&mut image, &mut rng);
+        assert!(image.data().iter().all(|&v| (v - 4.0 / 36.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_random_erasing() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..20 {
+            let mut image = Matrix::from_vec(10, 10, vec![1.0; 100]);
+            let erasing = Transform::RandomErasing {
+                area: (0.16, 0.16),
+                aspect_ratio: (1.0, 1.0),
+                value: 0.0,
+            };
+            erasing.apply(&mut image, &mut rng);
+            //Quadrado 4x4
+            assert!(image.data().iter().filter(|&&v| v == 0.0).count() == 16);
+        }
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let mut augmentation = Augmentation::new();
+        augmentation.add(0.0, affine(0.0, 1.0, 0.0, 2.0));
+        let mut image = square();
+        augmentation.apply(&mut image, &mut StdRng::seed_from_u64(5));
+        assert!(image == square());
+
+        augmentation.add(1.0, affine(0.0, 1.0, 0.0, 1.0));
+        augmentation.add(
+            1.0,
+            Transform::GaussianNoise {
+                std_dev: (0.05, 0.2),
+            },
+        );
+        assert!(augmentation.len() == 3);
+        let run = |seed: u64| {
+            let mut image = square();
+            augmentation.apply(&mut image, &mut StdRng::seed_from_u64(seed));
+            image
+        };
+        assert!(run(6) == run(6) && run(6) != run(7));
+
+        let input = Matrix::from_vec(36, 1, square().data().clone());
+        let expected = Matrix::from_vec(2, 1, vec![0.0, 1.0]);
+        let (augmented, target) =
+            augmentation.apply_sample((input.clone(), expected.clone()), 6, 6);
+        assert!(augmented.rows() == 36 && augmented.cols() == 1);
+        assert!(augmented != input && target == expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_probability() {
+        Augmentation::new().add(1.5, affine(0.0, 1.0, 0.0, 0.0));
+    }
+}
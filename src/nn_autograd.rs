@@ -0,0 +1,642 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://en.wikipedia.org/wiki/Automatic_differentiation#Reverse_accumulation
+use crate::nn_layer::{ActivationFunction, Gradient, NetworkLayer};
+use crate::nn_loss::Loss;
+use crate::nn_matrix::Matrix;
+
+/**
+ * Referência para um valor registrado na fita
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Var(usize);
+
+#[derive(Clone, Copy)]
+enum Operation {
+    Leaf,
+    Add(Var, Var),
+    Sub(Var, Var),
+    Hadamard(Var, Var),
+    MatMul(Var, Var),
+    Scale(Var, f64),
+    AddColumn(Var, Var),
+    Map(Var, fn(f64) -> f64), //Guarda a derivada, avaliada na entrada da operação
+    Transpose(Var),
+    Sum(Var),
+    SumColumns(Var),
+}
+
+struct TapeNode {
+    value: Matrix,
+    operation: Operation,
+}
+
+/**
+ * Fita para diferenciação automática em modo reverso. Cada operação calcula o resultado
+ * imediatamente e registra de onde ele veio; backward percorre a fita de trás para frente
+ * aplicando a regra da cadeia, de forma que só a propagação precisa ser escrita.
+ * Como uma operação só usa valores já registrados, a ordem da fita é uma ordem topológica.
+ */
+pub struct Tape {
+    nodes: Vec<TapeNode>,
+}
+
+/**
+ * Resultado de Tape::backward: ∂C/∂v para cada valor v que influencia a saída
+ */
+pub struct Gradients {
+    gradients: Vec<Option<Matrix>>,
+}
+
+impl Gradients {
+    /**
+     * None se o valor não influencia a saída
+     */
+    pub fn grad(&self, var: Var) -> Option<&Matrix> {
+        self.gradients
+            .get(var.0)
+            .and_then(|gradient| gradient.as_ref())
+    }
+}
+
+impl Default for Tape {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tape {
+    pub fn new() -> Tape {
+        Tape { nodes: Vec::new() }
+    }
+
+    /**
+     * Registra um valor de entrada (dados ou parâmetros)
+     */
+    pub fn variable(&mut self, value: Matrix) -> Var {
+        self.push(value, Operation::Leaf)
+    }
+
+    pub fn value(&self, var: Var) -> &Matrix {
+        &self.nodes[var.0].value
+    }
+
+    fn push(&mut self, value: Matrix, operation: Operation) -> Var {
+        self.nodes.push(TapeNode { value, operation });
+        Var(self.nodes.len() - 1)
+    }
+
+    fn assert_same_shape(&self, a: Var, b: Var) {
+        let (a, b) = (self.value(a), self.value(b));
+        assert!(a.rows() == b.rows() && a.cols() == b.cols());
+    }
+
+    pub fn add(&mut self, a: Var, b: Var) -> Var {
+        self.assert_same_shape(a, b);
+        let value = self.value(a).clone() + self.value(b);
+        self.push(value, Operation::Add(a, b))
+    }
+
+    pub fn sub(&mut self, a: Var, b: Var) -> Var {
+        self.assert_same_shape(a, b);
+        let mut value = self.value(a).clone();
+        value -= self.value(b);
+        self.push(value, Operation::Sub(a, b))
+    }
+
+    pub fn hadamard(&mut self, a: Var, b: Var) -> Var {
+        self.assert_same_shape(a, b);
+        let value = self.value(a).hadamard_product(self.value(b));
+        self.push(value, Operation::Hadamard(a, b))
+    }
+
+    pub fn matmul(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a) * self.value(b);
+        self.push(value, Operation::MatMul(a, b))
+    }
+
+    pub fn scale(&mut self, a: Var, scalar: f64) -> Var {
+        let value = self.value(a).clone().scalar_product(scalar);
+        self.push(value, Operation::Scale(a, scalar))
+    }
+
+    /**
+     * Soma a coluna (linhas x 1) a todas as colunas de a (ex: viéses em um lote de amostras)
+     */
+    pub fn add_column(&mut self, a: Var, column: Var) -> Var {
+        let mut value = self.value(a).clone();
+        value.mut_add_column(self.value(column));
+        self.push(value, Operation::AddColumn(a, column))
+    }
+
+    /**
+     * Aplica f a cada elemento. derivative é a derivada de f, avaliada na entrada
+     */
+    pub fn map(&mut self, a: Var, f: fn(f64) -> f64, derivative: fn(f64) -> f64) -> Var {
+        let value = self.value(a).clone().map(f);
+        self.push(value, Operation::Map(a, derivative))
+    }
+
+    /**
+     * Funções de ativação elemento a elemento (não serve para a Softmax, que depende da coluna toda)
+     */
+    pub fn activation<F: ActivationFunction>(&mut self, a: Var) -> Var {
+        self.map(a, |val| F::activate(val, &Vec::new()), F::derivative)
+    }
+
+    pub fn transpose(&mut self, a: Var) -> Var {
+        let value = self.value(a).transpose();
+        self.push(value, Operation::Transpose(a))
+    }
+
+    /**
+     * Soma de todos os elementos (resultado 1 x 1)
+     */
+    pub fn sum(&mut self, a: Var) -> Var {
+        let total = self.value(a).data().iter().sum();
+        self.push(Matrix::from_vec(1, 1, vec![total]), Operation::Sum(a))
+    }
+
+    /**
+     * Soma das colunas (resultado linhas x 1)
+     */
+    pub fn sum_columns(&mut self, a: Var) -> Var {
+        let value = self.value(a).sum_columns();
+        self.push(value, Operation::SumColumns(a))
+    }
+
+    /**
+     * Gradientes de uma saída escalar (1 x 1) em relação a todos os valores da fita
+     */
+    pub fn backward(&self, output: Var) -> Gradients {
+        let value = self.value(output);
+        assert!(value.rows() == 1 && value.cols() == 1);
+        self.backward_with(output, Matrix::from_vec(1, 1, vec![1.0]))
+    }
+
+    /**
+     * Retropropagação a partir de ∂C/∂saída conhecido (ex: gradiente vindo da camada seguinte)
+     */
+    pub fn backward_with(&self, output: Var, output_gradient: Matrix) -> Gradients {
+        let value = self.value(output);
+        assert!(value.rows() == output_gradient.rows() && value.cols() == output_gradient.cols());
+        let mut gradients: Vec<Option<Matrix>> = vec![None; output.0 + 1];
+        gradients[output.0] = Some(output_gradient);
+
+        for i in (0..=output.0).rev() {
+            let Some(gradient) = gradients[i].take() else {
+                continue;
+            };
+            match self.nodes[i].operation {
+                Operation::Leaf => {}
+                Operation::Add(a, b) => {
+                    accumulate(&mut gradients[a.0], gradient.clone());
+                    accumulate(&mut gradients[b.0], gradient.clone());
+                }
+                Operation::Sub(a, b) => {
+                    accumulate(&mut gradients[a.0], gradient.clone());
+                    accumulate(&mut gradients[b.0], gradient.clone().scalar_product(-1.0));
+                }
+                Operation::Hadamard(a, b) => {
+                    accumulate(
+                        &mut gradients[a.0],
+                        gradient.hadamard_product(self.value(b)),
+                    );
+                    accumulate(
+                        &mut gradients[b.0],
+                        gradient.hadamard_product(self.value(a)),
+                    );
+                }
+                //C = A * B: ∂C/∂A = G * B^T, ∂C/∂B = A^T * G
+                Operation::MatMul(a, b) => {
+                    accumulate(&mut gradients[a.0], &gradient * &self.value(b).transpose());
+                    accumulate(&mut gradients[b.0], &self.value(a).transpose() * &gradient);
+                }
+                Operation::Scale(a, scalar) => {
+                    accumulate(&mut gradients[a.0], gradient.clone().scalar_product(scalar));
+                }
+                Operation::AddColumn(a, column) => {
+                    accumulate(&mut gradients[column.0], gradient.sum_columns());
+                    accumulate(&mut gradients[a.0], gradient.clone());
+                }
+                Operation::Map(a, derivative) => {
+                    let mut input_gradient = self.value(a).clone().map(derivative);
+                    input_gradient.mut_hadamard_product(&gradient);
+                    accumulate(&mut gradients[a.0], input_gradient);
+                }
+                Operation::Transpose(a) => {
+                    accumulate(&mut gradients[a.0], gradient.transpose());
+                }
+                Operation::Sum(a) => {
+                    let input = self.value(a);
+                    let input_gradient = Matrix::from_vec(
+                        input.rows(),
+                        input.cols(),
+                        vec![gradient[0][0]; input.num_elements()],
+                    );
+                    accumulate(&mut gradients[a.0], input_gradient);
+                }
+                Operation::SumColumns(a) => {
+                    let input = self.value(a);
+                    let mut input_gradient = Matrix::new(input.rows(), input.cols());
+                    input_gradient.mut_add_column(&gradient);
+                    accumulate(&mut gradients[a.0], input_gradient);
+                }
+            }
+            gradients[i] = Some(gradient);
+        }
+        Gradients { gradients }
+    }
+}
+
+fn accumulate(total: &mut Option<Matrix>, gradient: Matrix) {
+    match total {
+        Some(total) => *total += &gradient,
+        None => *total = Some(gradient),
+    }
+}
+
+/**
+ * Propagação de uma camada escrita com operações da fita: recebe a fita, a entrada, os pesos e os viéses
+ */
+pub type AutogradForward = fn(&mut Tape, Var, Var, Var) -> Var;
+
+/**
+ * Camada definida apenas pela propagação: a retropropagação é obtida pela fita.
+ * Os pesos e viéses têm formato livre, definido por quem escreve a função de propagação.
+ */
+pub struct AutogradLayer {
+    weights: Matrix,
+    biases: Matrix,
+    neurons: Matrix,
+    forward: AutogradForward,
+    tape: Tape,
+    vars: Option<(Var, Var, Var, Var)>, //Entrada, pesos, viéses e saída da última propagação
+}
+
+impl AutogradLayer {
+    pub fn new(weights: Matrix, biases: Matrix, forward: AutogradForward) -> AutogradLayer {
+        AutogradLayer {
+            weights,
+            biases,
+            neurons: Matrix::new(0, 0),
+            forward,
+            tape: Tape::new(),
+            vars: None,
+        }
+    }
+}
+
+impl NetworkLayer for AutogradLayer {
+    fn propagate(&mut self, input: &Matrix) {
+        //Uma fita nova a cada propagação, para não acumular operações de amostras anteriores
+        self.tape = Tape::new();
+        let input = self.tape.variable(input.clone());
+        let weights = self.tape.variable(self.weights.clone());
+        let biases = self.tape.variable(self.biases.clone());
+        let output = (self.forward)(&mut self.tape, input, weights, biases);
+        self.neurons = self.tape.value(output).clone();
+        self.vars = Some((input, weights, biases, output));
+    }
+
+    fn neurons(&self) -> &Matrix {
+        &self.neurons
+    }
+    fn weights(&self) -> &Matrix {
+        &self.weights
+    }
+    fn biases(&self) -> &Matrix {
+        &self.biases
+    }
+    fn fix_weights(&mut self, weights: Matrix) {
+        assert!(weights.rows() == self.weights.rows() && weights.cols() == self.weights.cols());
+        self.weights = weights;
+    }
+    fn fix_bias(&mut self, biases: Matrix) {
+        assert!(biases.rows() == self.biases.rows() && biases.cols() == self.biases.cols());
+        self.biases = biases;
+    }
+
+    fn backpropagate(
+        &mut self,
+        output_gradient: &Matrix,
+        prev_activations: &Matrix,
+    ) -> (Gradient, Matrix) {
+        let (input, weights, biases, output) = self.vars.expect("Camada não propagada");
+        let gradients = self.tape.backward_with(output, output_gradient.clone());
+        let gradient_or_zero = |var: Var, shape: &Matrix| {
+            gradients
+                .grad(var)
+                .cloned()
+                .unwrap_or_else(|| Matrix::new(shape.rows(), shape.cols()))
+        };
+        (
+            Gradient {
+                weight: gradient_or_zero(weights, &self.weights),
+                delta: gradient_or_zero(biases, &self.biases),
+                rows: None,
+            },
+            gradient_or_zero(input, prev_activations),
+        )
+    }
+
+    fn adjust_parameters(&mut self, gradients: &mut Gradient, learning_rate: f64) {
+        self.weights -= gradients.weight.mut_scalar_product(learning_rate);
+        self.biases -= gradients.delta.mut_scalar_product(learning_rate);
+    }
+}
+
+/**
+ * Custo escrito com operações da fita: recebe a fita, a saída da rede e a saída esperada,
+ * e devolve a soma (1 x 1) dos custos das amostras do lote
+ */
+pub type AutogradCost = Box<dyn Fn(&mut Tape, Var, Var) -> Var>;
+
+/**
+ * Função de custo definida apenas pelo cálculo do custo: ∂C/∂a é obtido pela fita.
+ * Permite custos que não são elemento a elemento (ex: que combinam as saídas de uma amostra).
+ * Como nas demais funções de custo, o custo do lote é dividido pelo número de amostras.
+ */
+pub struct AutogradLoss {
+    cost: AutogradCost,
+}
+
+impl AutogradLoss {
+    pub fn new(cost: impl Fn(&mut Tape, Var, Var) -> Var + 'static) -> AutogradLoss {
+        AutogradLoss {
+            cost: Box::new(cost),
+        }
+    }
+
+    /**
+     * Registra o custo médio do lote na fita, devolvendo a variável da saída e a do custo
+     */
+    fn record(&self, tape: &mut Tape, output: &Matrix, expected_output: &Matrix) -> (Var, Var) {
+        assert!(output.rows() == expected_output.rows() && output.cols() == expected_output.cols());
+        let output_var = tape.variable(output.clone());
+        let expected_var = tape.variable(expected_output.clone());
+        let total = (self.cost)(tape, output_var, expected_var);
+        let cost = tape.scale(total, 1.0 / output.cols() as f64);
+        (output_var, cost)
+    }
+}
+
+impl Loss for AutogradLoss {
+    fn element_cost(&self, _output: f64, _expected: f64) -> f64 {
+        panic!("AutogradLoss não é elemento a elemento; use cost")
+    }
+    fn element_derivative(&self, _output: f64, _expected: f64) -> f64 {
+        panic!("AutogradLoss não é elemento a elemento; use gradient")
+    }
+
+    fn cost(&self, output: &Matrix, expected_output: &Matrix) -> f64 {
+        let mut tape = Tape::new();
+        let (_, cost) = self.record(&mut tape, output, expected_output);
+        tape.value(cost)[0][0]
+    }
+
+    fn gradient(&self, output: &Matrix, expected_output: &Matrix) -> Matrix {
+        let mut tape = Tape::new();
+        let (output_var, cost) = self.record(&mut tape, output, expected_output);
+        tape.backward(cost)
+            .grad(output_var)
+            .cloned()
+            .unwrap_or_else(|| Matrix::new(output.rows(), output.cols()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_layer::{Layer, Sigmoid, Tanh};
+    use crate::nn_loss::MeanSquaredError;
+    use crate::nn_network::NeuralNetwork;
+
+    #[test]
+    fn test_gradients() {
+        //C = soma((3 * tanh(W * x + b))²) - soma(W^T)
+        let build = |tape: &mut Tape, w: &Matrix, b: &Matrix, x: &Matrix| {
+            let (w, b, x) = (
+                tape.variable(w.clone()),
+                tape.variable(b.clone()),
+                tape.variable(x.clone()),
+            );
+            let z = tape.matmul(w, x);
+            let z = tape.add_column(z, b);
+            let a = tape.activation::<Tanh>(z);
+            let a = tape.scale(a, 3.0);
+            let wt = tape.transpose(w);
+            let wt_sum = tape.sum_columns(wt);
+            let product = tape.hadamard(a, a);
+            let sum = tape.sum(product);
+            let penalty = tape.sum(wt_sum);
+            let cost = tape.sub(sum, penalty);
+            (w, b, x, cost)
+        };
+        let w = Matrix::from_vec(2, 3, vec![0.1, -0.4, 0.3, 0.5, 0.2, -0.1]);
+        let b = Matrix::from_vec(2, 1, vec![0.05, -0.2]);
+        let x = Matrix::from_vec(3, 2, vec![0.7, -0.3, 0.2, 0.9, -0.5, 0.4]);
+
+        let mut tape = Tape::new();
+        let (w_var, b_var, x_var, cost) = build(&mut tape, &w, &b, &x);
+        let gradients = tape.backward(cost);
+
+        let epsilon = 1e-6;
+        let numeric_cost = |w: &Matrix, b: &Matrix, x: &Matrix| {
+            let mut tape = Tape::new();
+            let (_, _, _, cost) = build(&mut tape, w, b, x);
+            tape.value(cost)[0][0]
+        };
+        for (index, var) in [(0, w_var), (1, b_var), (2, x_var)] {
+            let analytic = gradients.grad(var).unwrap();
+            let original = [&w, &b, &x][index];
+            for i in 0..original.rows() {
+                for j in 0..original.cols() {
+                    let mut params = [w.clone(), b.clone(), x.clone()];
+                    params[index][i][j] += epsilon;
+                    let cost_plus = numeric_cost(&params[0], &params[1], &params[2]);
+                    params[index][i][j] -= 2.0 * epsilon;
+                    let cost_minus = numeric_cost(&params[0], &params[1], &params[2]);
+                    let numeric = (cost_plus - cost_minus) / (2.0 * epsilon);
+                    assert!(
+                        (numeric - analytic[i][j]).abs() < 1e-6,
+                        "Variável {} ({}, {}): numérico {} analítico {}",
+                        index,
+                        i,
+                        j,
+                        numeric,
+                        analytic[i][j]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unused_variable() {
+        let mut tape = Tape::new();
+        let a = tape.variable(Matrix::from_vec(1, 2, vec![1.0, 2.0]));
+        let unused = tape.variable(Matrix::from_vec(1, 1, vec![5.0]));
+        let sum = tape.sum(a);
+        let gradients = tape.backward(sum);
+        assert!(gradients.grad(unused).is_none());
+        assert!(*gradients.grad(a).unwrap() == Matrix::from_vec(1, 2, vec![1.0, 1.0]));
+    }
+
+    #[test]
+    fn test_mse_loss() {
+        //Custo definido só pela propagação: soma((a - y)²) / amostras
+        let output = Matrix::from_vec(2, 2, vec![0.3, 0.8, 0.6, 0.1]);
+        let expected = Matrix::from_vec(2, 2, vec![1.0, 0.0, 0.0, 1.0]);
+        let mut tape = Tape::new();
+        let a = tape.variable(output.clone());
+        let y = tape.variable(expected.clone());
+        let error = tape.sub(a, y);
+        let squared = tape.hadamard(error, error);
+        let total = tape.sum(squared);
+        let cost = tape.scale(total, 1.0 / output.cols() as f64);
+        let gradients = tape.backward(cost);
+
+        let analytic = gradients.grad(a).unwrap();
+        for i in 0..2 {
+            for j in 0..2 {
+                let expected_derivative =
+                    NeuralNetwork::cost_derivative_mse(output[i][j], expected[i][j]) / 2.0;
+                assert!((analytic[i][j] - expected_derivative).abs() < 1e-12);
+            }
+        }
+    }
+
+    fn squared_error(tape: &mut Tape, output: Var, expected: Var) -> Var {
+        let error = tape.sub(output, expected);
+        let squared = tape.hadamard(error, error);
+        tape.sum(squared)
+    }
+
+    #[test]
+    fn test_autograd_loss() {
+        let output = Matrix::from_vec(2, 3, vec![0.3, 0.8, -0.2, 0.6, 0.1, 1.4]);
+        let expected = Matrix::from_vec(2, 3, vec![1.0, 0.0, 0.0, 0.0, 1.0, 1.0]);
+        let loss = AutogradLoss::new(squared_error);
+        let mse = MeanSquaredError {};
+        assert!((loss.cost(&output, &expected) - mse.cost(&output, &expected)).abs() < 1e-12);
+
+        //Gradiente da fita contra o do MSE e contra diferenças finitas centrais
+        let gradient = loss.gradient(&output, &expected);
+        let mse_gradient = mse.gradient(&output, &expected);
+        let epsilon = 1e-6;
+        for i in 0..output.rows() {
+            for j in 0..output.cols() {
+                assert!((gradient[i][j] - mse_gradient[i][j]).abs() < 1e-12);
+                let mut perturbed = output.clone();
+                perturbed[i][j] += epsilon;
+                let cost_plus = loss.cost(&perturbed, &expected);
+                perturbed[i][j] -= 2.0 * epsilon;
+                let cost_minus = loss.cost(&perturbed, &expected);
+                let numeric = (cost_plus - cost_minus) / (2.0 * epsilon);
+                assert!(
+                    (numeric - gradient[i][j]).abs() < 1e-6,
+                    "({}, {}): numérico {} analítico {}",
+                    i,
+                    j,
+                    numeric,
+                    gradient[i][j]
+                );
+            }
+        }
+
+        //Treinamento com o custo da fita
+        let mut network = NeuralNetwork::new(2, 0.5);
+        network.add_layer(Layer::new::<Sigmoid>(2, 1));
+        network.set_loss(AutogradLoss::new(squared_error));
+        let input = Matrix::from_vec(2, 2, vec![0.0, 1.0, 1.0, 0.0]);
+        let expected = Matrix::from_vec(1, 2, vec![1.0, 0.0]);
+        let initial_cost = mse.cost(network.classify(&input), &expected);
+        for _ in 0..100 {
+            network.train(input.clone(), expected.clone());
+        }
+        assert!(mse.cost(network.classify(&input), &expected) < initial_cost);
+    }
+
+    fn dense_sigmoid(tape: &mut Tape, input: Var, weights: Var, biases: Var) -> Var {
+        let z = tape.matmul(weights, input);
+        let z = tape.add_column(z, biases);
+        tape.activation::<Sigmoid>(z)
+    }
+
+    #[test]
+    fn test_layer_matches_dense() {
+        let mut dense = Layer::new::<Sigmoid>(3, 2);
+        let mut autograd = AutogradLayer::new(
+            dense.weights().clone(),
+            Matrix::from_vec(2, 1, vec![0.1, -0.3]),
+            dense_sigmoid,
+        );
+        dense.fix_bias(Matrix::from_vec(2, 1, vec![0.1, -0.3]));
+
+        let input = Matrix::from_vec(3, 2, vec![0.2, 0.9, -0.4, 0.3, 0.6, -0.1]);
+        let output_gradient = Matrix::from_vec(2, 2, vec![0.5, -1.0, 0.25, 0.75]);
+        dense.propagate(&input);
+        autograd.propagate(&input);
+        assert!(dense.neurons() == autograd.neurons());
+
+        let (dense_gradient, dense_input_gradient) = dense.backpropagate(&output_gradient, &input);
+        let (autograd_gradient, autograd_input_gradient) =
+            autograd.backpropagate(&output_gradient, &input);
+        assert!(dense_gradient.weight == autograd_gradient.weight);
+        assert!(dense_gradient.delta == autograd_gradient.delta);
+        assert!(dense_input_gradient == autograd_input_gradient);
+    }
+
+    #[test]
+    fn test_train_autograd_network() {
+        let mut network = NeuralNetwork::new(2, 0.5);
+        network.add_layer(AutogradLayer::new(
+            Matrix::new_random_glorot(4, 2),
+            Matrix::new(4, 1),
+            |tape, input, weights, biases| {
+                let z = tape.matmul(weights, input);
+                let z = tape.add_column(z, biases);
+                tape.activation::<Tanh>(z)
+            },
+        ));
+        network.add_layer(AutogradLayer::new(
+            Matrix::new_random_glorot(1, 4),
+            Matrix::new(1, 1),
+            dense_sigmoid,
+        ));
+        //XOR, em lote
+        let input = Matrix::from_vec(2, 4, vec![0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0]);
+        let expected = Matrix::from_vec(1, 4, vec![0.0, 1.0, 1.0, 0.0]);
+        let squared_error = |output: &Matrix| -> f64 {
+            output
+                .data()
+                .iter()
+                .zip(expected.data())
+                .map(|(a, y)| (a - y).powi(2))
+                .sum()
+        };
+        let initial_error = squared_error(network.classify(&input));
+        for _ in 0..500 {
+            network.train(input.clone(), expected.clone());
+        }
+        let final_error = squared_error(network.classify(&input));
+        println!("Error: {} -> {}", initial_error, final_error);
+        assert!(final_error < initial_error);
+    }
+}
@@ -0,0 +1,155 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://arxiv.org/abs/1211.5063 (On the difficulty of training Recurrent Neural Networks)
+use crate::nn_layer::Gradient;
+use crate::nn_matrix::Matrix;
+use std::collections::VecDeque;
+
+/**
+ * Limitação dos gradientes antes do ajuste dos parâmetros, contra a explosão de gradientes.
+ * As normas consideram pesos e viéses juntos.
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum GradientClipping {
+    Value(f64),      //Cada elemento limitado a [-limite, limite]
+    LayerNorm(f64),  //Gradiente de cada camada reescalado se sua norma passar do limite
+    GlobalNorm(f64), //Todos os gradientes reescalados pelo mesmo fator se a norma global passar do limite
+}
+
+impl GradientClipping {
+    /**
+     * Aplica a limitação e devolve a norma global dos gradientes antes dela
+     */
+    pub fn clip(&self, gradients: &mut VecDeque<Gradient>) -> f64 {
+        let norm = global_norm(gradients);
+        match *self {
+            GradientClipping::Value(limit) => {
+                assert!(limit > 0.0);
+                for gradient in gradients.iter_mut() {
+                    clamp(&mut gradient.weight, limit);
+                    clamp(&mut gradient.delta, limit);
+                }
+            }
+            GradientClipping::LayerNorm(max_norm) => {
+                assert!(max_norm > 0.0);
+                for gradient in gradients.iter_mut() {
+                    let layer_norm = squared_norm(gradient).sqrt();
+                    if layer_norm > max_norm {
+                        scale(gradient, max_norm / layer_norm);
+                    }
+                }
+            }
+            GradientClipping::GlobalNorm(max_norm) => {
+                assert!(max_norm > 0.0);
+                if norm > max_norm {
+                    gradients
+                        .iter_mut()
+                        .for_each(|gradient| scale(gradient, max_norm / norm));
+                }
+            }
+        }
+        norm
+    }
+}
+
+/**
+ * Norma euclidiana de todos os gradientes (pesos e viéses de todas as camadas)
+ */
+pub fn global_norm(gradients: &VecDeque<Gradient>) -> f64 {
+    gradients.iter().map(squared_norm).sum::<f64>().sqrt()
+}
+
+fn squared_norm(gradient: &Gradient) -> f64 {
+    gradient
+        .weight
+        .data()
+        .iter()
+        .chain(gradient.delta.data())
+        .map(|value| value * value)
+        .sum()
+}
+
+fn scale(gradient: &mut Gradient, factor: f64) {
+    gradient.weight.mut_scalar_product(factor);
+    gradient.delta.mut_scalar_product(factor);
+}
+
+//mut_map recebe um ponteiro de função, que não pode capturar o limite
+fn clamp(matrix: &mut Matrix, limit: f64) {
+    for i in 0..matrix.rows() {
+        for j in 0..matrix.cols() {
+            matrix[i][j] = matrix[i][j].clamp(-limit, limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+
+    //Norma da primeira camada 5, da segunda 12: norma global 13
+    fn gradients() -> VecDeque<Gradient> {
+        VecDeque::from(vec![
+            Gradient {
+                weight: Matrix::from_vec(1, 2, vec![3.0, 0.0]),
+                delta: Matrix::from_vec(1, 1, vec![-4.0]),
+                rows: None,
+            },
+            Gradient {
+                weight: Matrix::from_vec(1, 1, vec![12.0]),
+                delta: Matrix::new(0, 0),
+                rows: None,
+            },
+        ])
+    }
+
+    #[test]
+    fn test_global_norm() {
+        let mut gradients = gradients();
+        assert!(global_norm(&gradients) == 13.0);
+        assert!(GradientClipping::GlobalNorm(6.5).clip(&mut gradients) == 13.0);
+        assert!(gradients[0].weight == Matrix::from_vec(1, 2, vec![1.5, 0.0]));
+        assert!(gradients[0].delta == Matrix::from_vec(1, 1, vec![-2.0]));
+        assert!(gradients[1].weight == Matrix::from_vec(1, 1, vec![6.0]));
+        assert!((global_norm(&gradients) - 6.5).abs() < 1e-12);
+
+        //Abaixo do limite nada muda
+        let mut gradients = self::gradients();
+        GradientClipping::GlobalNorm(20.0).clip(&mut gradients);
+        assert!(gradients[1].weight == Matrix::from_vec(1, 1, vec![12.0]));
+    }
+
+    #[test]
+    fn test_layer_norm() {
+        let mut gradients = gradients();
+        assert!(GradientClipping::LayerNorm(6.0).clip(&mut gradients) == 13.0);
+        //Primeira camada (norma 5) intacta, segunda reescalada para norma 6
+        assert!(gradients[0].weight == Matrix::from_vec(1, 2, vec![3.0, 0.0]));
+        assert!(gradients[0].delta == Matrix::from_vec(1, 1, vec![-4.0]));
+        assert!(gradients[1].weight == Matrix::from_vec(1, 1, vec![6.0]));
+    }
+
+    #[test]
+    fn test_value() {
+        let mut gradients = gradients();
+        assert!(GradientClipping::Value(3.5).clip(&mut gradients) == 13.0);
+        assert!(gradients[0].weight == Matrix::from_vec(1, 2, vec![3.0, 0.0]));
+        assert!(gradients[0].delta == Matrix::from_vec(1, 1, vec![-3.5]));
+        assert!(gradients[1].weight == Matrix::from_vec(1, 1, vec![3.5]));
+    }
+}
@@ -0,0 +1,726 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://www.rfc-editor.org/rfc/rfc4180
+use crate::nn_dataset::{Dataset, SharedDataset};
+use crate::nn_matrix::Matrix;
+use crate::nn_preprocessing::LabelEncoder;
+use crate::nn_trainer::Sample;
+
+//Valores considerados ausentes, além do campo vazio
+const MISSING_MARKERS: [&str; 5] = ["NA", "N/A", "NaN", "?", "null"];
+
+/**
+ * Coluna do arquivo, pela posição (a partir de 0), pelo nome no cabeçalho ou a última do registro
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+    Last,
+}
+
+/**
+ * Tratamento de valores ausentes nas colunas de features.
+ * Em colunas categóricas, Mean e Fill deixam todas as posições da codificação em 0.
+ * Rótulos ausentes são sempre erro, exceto com DropRow.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MissingValues {
+    Error,
+    DropRow,
+    Mean,
+    Fill(f64),
+}
+
+/**
+ * Tipo da saída esperada: classes (uma posição por rótulo) ou valor numérico
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Classification,
+    Regression,
+}
+
+/**
+ * Codificação aprendida em um arquivo (normalmente o de treino):
+ * categorias de cada coluna categórica, médias para valores ausentes e rótulos das classes.
+ */
+#[derive(Debug, Clone)]
+pub struct CsvEncoding {
+    columns: Vec<String>,
+    categories: Vec<Option<LabelEncoder>>, //Uma por coluna de feature; None nas numéricas
+    means: Vec<f64>,
+    label_encoder: Option<LabelEncoder>,
+}
+
+/**
+ * Leitura de arquivos CSV de dados tabulares. Cada linha vira uma amostra com as features em uma matriz coluna
+ * (colunas categóricas codificadas com uma posição por categoria) e a saída esperada da coluna de rótulo.
+ */
+#[derive(Debug, Clone)]
+pub struct CsvReader {
+    delimiter: char,
+    header: Option<bool>,
+    label_column: Column,
+    target: Target,
+    missing_values: MissingValues,
+    ignored_columns: Vec<Column>,
+    categorical_columns: Vec<Column>,
+    encoding: Option<CsvEncoding>,
+}
+
+impl Default for CsvReader {
+    fn default() -> Self {
+        CsvReader::new()
+    }
+}
+
+impl CsvReader {
+    /**
+     * Padrões: vírgula, cabeçalho detectado, rótulo na última coluna, classificação e erro em valores ausentes
+     */
+    pub fn new() -> CsvReader {
+        CsvReader {
+            delimiter: ',',
+            header: None,
+            label_column: Column::Last,
+            target: Target::Classification,
+            missing_values: MissingValues::Error,
+            ignored_columns: Vec::new(),
+            categorical_columns: Vec::new(),
+            encoding: None,
+        }
+    }
+
+    pub fn set_delimiter(&mut self, delimiter: char) {
+        assert!(delimiter != '"' && delimiter != '\n' && delimiter != '\r');
+        self.delimiter = delimiter;
+    }
+
+    /**
+     * Some(true/false) força a presença ou ausência do cabeçalho; None detecta pela primeira linha
+     */
+    pub fn set_header(&mut self, header: Option<bool>) {
+        self.header = header;
+    }
+
+    pub fn set_label_column(&mut self, column: Column) {
+        self.label_column = column;
+    }
+
+    pub fn set_target(&mut self, target: Target) {
+        self.target = target;
+    }
+
+    pub fn set_missing_values(&mut self, missing_values: MissingValues) {
+        self.missing_values = missing_values;
+    }
+
+    /**
+     * Colunas que não são usadas como features (ex: identificadores)
+     */
+    pub fn set_ignored_columns(&mut self, columns: Vec<Column>) {
+        self.ignored_columns = columns;
+    }
+
+    /**
+     * Colunas tratadas como categóricas mesmo que sejam numéricas (ex: códigos).
+     * Colunas com algum valor não numérico são sempre categóricas.
+     */
+    pub fn set_categorical_columns(&mut self, columns: Vec<Column>) {
+        self.categorical_columns = columns;
+    }
+
+    /**
+     * Usa a codificação de outro arquivo (categorias, médias e rótulos), para que treino e teste
+     * gerem as mesmas features. Categorias desconhecidas ficam com todas as posições em 0.
+     */
+    pub fn set_encoding(&mut self, encoding: Option<CsvEncoding>) {
+        self.encoding = encoding;
+    }
+
+    pub fn read_file(&self, file_name: &str) -> std::io::Result<CsvDataset> {
+        self.parse(&std::fs::read_to_string(file_name)?)
+    }
+
+    pub fn parse(&self, text: &str) -> std::io::Result<CsvDataset> {
+        let mut records = parse_records(text, self.delimiter)?;
+        if records.is_empty() {
+            return Err(invalid("Arquivo CSV vazio".to_string()));
+        }
+        let num_columns = records[0].1.len();
+        if let Some((line, record)) = records
+            .iter()
+            .find(|(_, record)| record.len() != num_columns)
+        {
+            return Err(invalid(format!(
+                "Linha {}: {} campos, esperados {}",
+                line,
+                record.len(),
+                num_columns
+            )));
+        }
+        let has_header = self.header.unwrap_or_else(|| detect_header(&records));
+        let names: Vec<String> = if has_header {
+            records.remove(0).1
+        } else {
+            (0..num_columns).map(|i| format!("coluna{}", i)).collect()
+        };
+
+        let label = self.resolve(&self.label_column, &names)?;
+        let mut ignored = Vec::with_capacity(self.ignored_columns.len());
+        for column in &self.ignored_columns {
+            ignored.push(self.resolve(column, &names)?);
+        }
+        let feature_columns: Vec<usize> = (0..num_columns)
+            .filter(|i| *i != label && !ignored.contains(i))
+            .collect();
+
+        //Linhas com valores ausentes
+        if self.missing_values == MissingValues::DropRow {
+            records.retain(|(_, record)| {
+                !is_missing(&record[label])
+                    && feature_columns.iter().all(|&i| !is_missing(&record[i]))
+            });
+        }
+        for (line, record) in &records {
+            if is_missing(&record[label]) {
+                return Err(invalid(format!("Linha {}: rótulo ausente", line)));
+            }
+            if self.missing_values == MissingValues::Error
+                && let Some(&i) = feature_columns.iter().find(|&&i| is_missing(&record[i]))
+            {
+                return Err(invalid(format!(
+                    "Linha {}: valor ausente em {}",
+                    line, names[i]
+                )));
+            }
+        }
+        if records.is_empty() {
+            return Err(invalid("Nenhuma linha de dados no CSV".to_string()));
+        }
+
+        let encoding = match &self.encoding {
+            Some(encoding) => {
+                let expected: Vec<&String> = feature_columns.iter().map(|&i| &names[i]).collect();
+                if has_header && encoding.columns.iter().collect::<Vec<_>>() != expected {
+                    return Err(invalid(
+                        "Colunas do CSV diferem das da codificação".to_string(),
+                    ));
+                }
+                if encoding.columns.len() != feature_columns.len() {
+                    return Err(invalid(
+                        "Número de colunas do CSV difere do da codificação".to_string(),
+                    ));
+                }
+                encoding.clone()
+            }
+            None => self.fit_encoding(&records, &names, &feature_columns, label)?,
+        };
+
+        let mut feature_names = Vec::new();
+        for (k, &i) in feature_columns.iter().enumerate() {
+            match &encoding.categories[k] {
+                Some(categories) => feature_names.extend(
+                    categories
+                        .labels()
+                        .iter()
+                        .map(|category| format!("{}={}", names[i], category)),
+                ),
+                None => feature_names.push(names[i].clone()),
+            }
+        }
+
+        let mut samples = Vec::with_capacity(records.len());
+        for (line, record) in &records {
+            let mut features = Vec::with_capacity(feature_names.len());
+            for (k, &i) in feature_columns.iter().enumerate() {
+                let field = &record[i];
+                match &encoding.categories[k] {
+                    Some(categories) => {
+                        let mut one_hot = vec![0.0; categories.num_classes()];
+                        if let Some(category) = categories.encode(field) {
+                            one_hot[category] = 1.0;
+                        }
+                        features.extend(one_hot);
+                    }
+                    None if is_missing(field) => features.push(match self.missing_values {
+                        MissingValues::Fill(value) => value,
+                        _ => encoding.means[k],
+                    }),
+                    None => features.push(parse_number(field).ok_or_else(|| {
+                        invalid(format!(
+                            "Linha {}: valor não numérico em {}: {}",
+                            line, names[i], field
+                        ))
+                    })?),
+                }
+            }
+            let target = match &encoding.label_encoder {
+                Some(label_encoder) => {
+                    let class = label_encoder.encode(&record[label]).ok_or_else(|| {
+                        invalid(format!(
+                            "Linha {}: rótulo desconhecido: {}",
+                            line, record[label]
+                        ))
+                    })?;
+                    label_encoder.one_hot().encode(class)
+                }
+                None => {
+                    let value = parse_number(&record[label]).ok_or_else(|| {
+                        invalid(format!(
+                            "Linha {}: rótulo não numérico: {}",
+                            line, record[label]
+                        ))
+                    })?;
+                    Matrix::from_vec(1, 1, vec![value])
+                }
+            };
+            samples.push((Matrix::from_vec(features.len(), 1, features), target));
+        }
+
+        Ok(CsvDataset {
+            feature_names,
+            samples,
+            encoding,
+        })
+    }
+
+    fn resolve(&self, column: &Column, names: &[String]) -> std::io::Result<usize> {
+        match column {
+            Column::Last => Ok(names.len() - 1),
+            Column::Index(index) if *index < names.len() => Ok(*index),
+            Column::Index(index) => Err(invalid(format!("Coluna {} inexistente", index))),
+            Column::Name(name) => names
+                .iter()
+                .position(|column_name| column_name == name)
+                .ok_or_else(|| invalid(format!("Coluna {} inexistente", name))),
+        }
+    }
+
+    fn fit_encoding(
+        &self,
+        records: &[(usize, Vec<String>)],
+        names: &[String],
+        feature_columns: &[usize],
+        label: usize,
+    ) -> std::io::Result<CsvEncoding> {
+        let mut forced = Vec::with_capacity(self.categorical_columns.len());
+        for column in &self.categorical_columns {
+            forced.push(self.resolve(column, names)?);
+        }
+        let mut categories = Vec::with_capacity(feature_columns.len());
+        let mut means = Vec::with_capacity(feature_columns.len());
+        for &i in feature_columns {
+            let present: Vec<&str> = records
+                .iter()
+                .map(|(_, record)| record[i].as_str())
+                .filter(|field| !is_missing(field))
+                .collect();
+            let numbers: Option<Vec<f64>> =
+                present.iter().map(|field| parse_number(field)).collect();
+            match numbers {
+                Some(numbers) if !forced.contains(&i) => {
+                    categories.push(None);
+                    let mean = numbers.iter().sum::<f64>() / numbers.len().max(1) as f64;
+                    means.push(mean);
+                }
+                _ => {
+                    categories.push(Some(LabelEncoder::fit(&present)));
+                    means.push(0.0);
+                }
+            }
+        }
+        let label_encoder = match self.target {
+            Target::Classification => {
+                let labels: Vec<&str> = records
+                    .iter()
+                    .map(|(_, record)| record[label].as_str())
+                    .collect();
+                Some(LabelEncoder::fit(&labels))
+            }
+            Target::Regression => None,
+        };
+        Ok(CsvEncoding {
+            columns: feature_columns.iter().map(|&i| names[i].clone()).collect(),
+            categories,
+            means,
+            label_encoder,
+        })
+    }
+}
+
+/**
+ * Amostras lidas de um CSV, com os nomes das features geradas e a codificação usada
+ */
+#[derive(Debug, Clone)]
+pub struct CsvDataset {
+    feature_names: Vec<String>,
+    samples: Vec<Sample>,
+    encoding: CsvEncoding,
+}
+
+impl CsvDataset {
+    pub fn num_features(&self) -> usize {
+        self.feature_names.len()
+    }
+
+    /**
+     * Nomes das features; colunas categóricas geram uma por categoria ("coluna=categoria")
+     */
+    pub fn feature_names(&self) -> &[String] {
+        &self.feature_names
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    pub fn into_samples(self) -> Vec<Sample> {
+        self.samples
+    }
+
+    /**
+     * Codificação a ser reaproveitada na leitura de outros arquivos (ver CsvReader::set_encoding)
+     */
+    pub fn encoding(&self) -> &CsvEncoding {
+        &self.encoding
+    }
+
+    /**
+     * Rótulos das classes (apenas em classificação)
+     */
+    pub fn label_encoder(&self) -> Option<&LabelEncoder> {
+        self.encoding.label_encoder.as_ref()
+    }
+
+    /**
+     * Todas as features em uma matriz, uma amostra por coluna
+     */
+    pub fn features(&self) -> Matrix {
+        let mut features = Matrix::new(self.num_features(), self.samples.len());
+        for (j, (input, _)) in self.samples.iter().enumerate() {
+            features.set_column(j, input);
+        }
+        features
+    }
+
+    /**
+     * Todas as saídas esperadas em uma matriz, uma amostra por coluna
+     */
+    pub fn targets(&self) -> Matrix {
+        let mut targets = Matrix::new(self.samples[0].1.rows(), self.samples.len());
+        for (j, (_, target)) in self.samples.iter().enumerate() {
+            targets.set_column(j, target);
+        }
+        targets
+    }
+}
+
+impl Dataset for CsvDataset {
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn get(&mut self, index: usize) -> Sample {
+        self.sample(index)
+    }
+}
+
+impl SharedDataset for CsvDataset {
+    fn sample(&self, index: usize) -> Sample {
+        self.samples[index].clone()
+    }
+}
+
+fn invalid(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+fn is_missing(field: &str) -> bool {
+    field.is_empty() || MISSING_MARKERS.contains(&field)
+}
+
+fn parse_number(field: &str) -> Option<f64> {
+    field.parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
+//Termina o campo atual; espaços em volta são removidos apenas de campos sem aspas
+fn end_field(field: &mut String, quoted: &mut bool, record: &mut Vec<String>) {
+    let value = if *quoted {
+        std::mem::take(field)
+    } else {
+        field.trim().to_string()
+    };
+    field.clear();
+    *quoted = false;
+    record.push(value);
+}
+
+/**
+ * Separa o texto em registros (com a linha onde começam), tratando campos entre aspas:
+ * podem conter o delimitador e quebras de linha, e "" representa uma aspa.
+ * Espaços em volta de campos sem aspas são removidos e linhas vazias são ignoradas.
+ * O BOM do UTF-8 no início do texto (gravado por planilhas) é descartado.
+ */
+fn parse_records(text: &str, delimiter: char) -> std::io::Result<Vec<(usize, Vec<String>)>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false; //Campo começou com aspas
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.trim().is_empty() && !quoted => {
+                field.clear();
+                quoted = true;
+                in_quotes = true;
+            }
+            '"' => {
+                return Err(invalid(format!(
+                    "Linha {}: aspas no meio de um campo",
+                    line
+                )));
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                if !record.is_empty() || quoted || !field.trim().is_empty() {
+                    end_field(&mut field, &mut quoted, &mut record);
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                field.clear();
+                line += 1;
+                record_line = line;
+            }
+            c if c == delimiter => end_field(&mut field, &mut quoted, &mut record),
+            c if quoted => {
+                if !c.is_whitespace() {
+                    return Err(invalid(format!("Linha {}: texto depois das aspas", line)));
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(invalid(format!(
+            "Linha {}: aspas não fechadas",
+            record_line
+        )));
+    }
+    if !record.is_empty() || quoted || !field.trim().is_empty() {
+        end_field(&mut field, &mut quoted, &mut record);
+        records.push((record_line, record));
+    }
+    Ok(records)
+}
+
+/**
+ * A primeira linha é cabeçalho se alguma coluna numérica nas demais linhas tem texto nela,
+ * ou se todos os seus campos são texto que não se repete na coluna.
+ */
+fn detect_header(records: &[(usize, Vec<String>)]) -> bool {
+    if records.len() < 2 {
+        return false;
+    }
+    let first = &records[0].1;
+    let numeric_column = |i: usize| {
+        records[1..]
+            .iter()
+            .map(|(_, record)| record[i].as_str())
+            .filter(|field| !is_missing(field))
+            .all(|field| parse_number(field).is_some())
+    };
+    let text = |field: &str| !is_missing(field) && parse_number(field).is_none();
+    if (0..first.len()).any(|i| text(&first[i]) && numeric_column(i)) {
+        return true;
+    }
+    (0..first.len())
+        .all(|i| text(&first[i]) && records[1..].iter().all(|(_, record)| record[i] != first[i]))
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_layer::{Layer, Sigmoid};
+    use crate::nn_network::NeuralNetwork;
+
+    const IRIS: &str = "sepal_length,sepal_width,color,species
+5.1,3.5,red,setosa
+7.0,3.2,blue,versicolor
+6.3,3.3,red,virginica
+4.9,3.0,green,setosa
+";
+
+    #[test]
+    fn test_parse_records() {
+        let text = "a;\"b;c\";\"d \"\"e\"\"\"\r\n\n 1 ; 2 ;\"multi\nline\"\n";
+        let records = parse_records(text, ';').unwrap();
+        assert!(records.len() == 2);
+        assert!(
+            records[0]
+                == (
+                    1,
+                    vec!["a".to_string(), "b;c".to_string(), "d \"e\"".to_string()]
+                )
+        );
+        assert!(
+            records[1]
+                == (
+                    3,
+                    vec!["1".to_string(), "2".to_string(), "multi\nline".to_string()]
+                )
+        );
+        //Campo vazio no fim
+        assert!(parse_records("1,", ',').unwrap()[0].1 == ["1", ""]);
+        //BOM no início do arquivo
+        assert!(parse_records("\u{feff}a,b\n", ',').unwrap()[0].1 == ["a", "b"]);
+        assert!(parse_records("\"aberto", ',').is_err());
+        assert!(parse_records("a\"b", ',').is_err());
+    }
+
+    #[test]
+    fn test_header_detection() {
+        let records = parse_records(IRIS, ',').unwrap();
+        assert!(detect_header(&records));
+        let records = parse_records("1,2,a\n3,4,b\n", ',').unwrap();
+        assert!(!detect_header(&records));
+        let records = parse_records("cor,forma\nazul,redondo\nverde,quadrado\n", ',').unwrap();
+        assert!(detect_header(&records));
+        let records = parse_records("azul,redondo\nazul,quadrado\n", ',').unwrap();
+        assert!(!detect_header(&records));
+    }
+
+    #[test]
+    fn test_classification() {
+        let dataset = CsvReader::new().parse(IRIS).unwrap();
+        assert!(dataset.len() == 4);
+        assert!(
+            dataset.feature_names()
+                == [
+                    "sepal_length",
+                    "sepal_width",
+                    "color=red",
+                    "color=blue",
+                    "color=green"
+                ]
+        );
+        let (input, target) = &dataset.samples()[1];
+        assert!(*input == Matrix::from_vec(5, 1, vec![7.0, 3.2, 0.0, 1.0, 0.0]));
+        assert!(*target == Matrix::from_vec(3, 1, vec![0.0, 1.0, 0.0]));
+        assert!(dataset.label_encoder().unwrap().decode(2) == "virginica");
+        assert!(dataset.features().cols() == 4 && dataset.targets().rows() == 3);
+
+        let mut network = NeuralNetwork::new(1, 0.1);
+        network.add_layer(Layer::new::<Sigmoid>(dataset.num_features(), 3));
+        for (input, target) in dataset.samples() {
+            network.train(input.clone(), target.clone());
+        }
+        assert!(network.classify(&dataset.samples()[0].0).rows() == 3);
+
+        //Mesma codificação em outro arquivo: categoria nova fica zerada
+        let mut reader = CsvReader::new();
+        reader.set_encoding(Some(dataset.encoding().clone()));
+        let test = reader
+            .parse("sepal_length,sepal_width,color,species\n5.0,3.1,purple,virginica\n")
+            .unwrap();
+        assert!(test.samples()[0].0 == Matrix::from_vec(5, 1, vec![5.0, 3.1, 0.0, 0.0, 0.0]));
+        assert!(test.samples()[0].1[2][0] == 1.0);
+        assert!(
+            reader
+                .parse("sepal_length,sepal_width,color,species\n5.0,3.1,red,rosa\n")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_regression_and_columns() {
+        let text = "id\tprice\tzone\tarea\n1\t100.5\t3\t50\n2\t200\t1\t90\n3\t150\t3\t70\n";
+        let mut reader = CsvReader::new();
+        reader.set_delimiter('\t');
+        reader.set_target(Target::Regression);
+        reader.set_label_column(Column::Name("price".to_string()));
+        reader.set_ignored_columns(vec![Column::Index(0)]);
+        reader.set_categorical_columns(vec![Column::Name("zone".to_string())]);
+        let dataset = reader.parse(text).unwrap();
+        assert!(dataset.feature_names() == ["zone=3", "zone=1", "area"]);
+        assert!(dataset.samples()[0].1 == Matrix::from_vec(1, 1, vec![100.5]));
+        assert!(dataset.samples()[1].0 == Matrix::from_vec(3, 1, vec![0.0, 1.0, 90.0]));
+        assert!(dataset.label_encoder().is_none());
+
+        reader.set_label_column(Column::Name("preço".to_string()));
+        assert!(reader.parse(text).is_err());
+        reader.set_label_column(Column::Index(usize::MAX));
+        assert!(reader.parse(text).is_err());
+        //Nomes da primeira coluna encontrados mesmo com BOM
+        reader.set_label_column(Column::Last);
+        reader.set_ignored_columns(vec![Column::Name("id".to_string())]);
+        let dataset = reader.parse(&format!("\u{feff}{}", text)).unwrap();
+        assert!(dataset.feature_names() == ["price", "zone=3", "zone=1"]);
+        assert!(dataset.samples()[0].1[0][0] == 50.0);
+        //Sem cabeçalho, o rótulo é a última coluna
+        let mut reader = CsvReader::new();
+        reader.set_header(Some(false));
+        reader.set_target(Target::Regression);
+        let dataset = reader.parse("1,2,3\n4,5,6\n").unwrap();
+        assert!(dataset.samples()[1].1[0][0] == 6.0 && dataset.num_features() == 2);
+        assert!(reader.parse("1,2,3\n4,5\n").is_err());
+    }
+
+    #[test]
+    fn test_missing_values() {
+        let text = "a,b,label\n1,,x\n3,4,y\nNA,8,x\n5,6,\n";
+        let mut reader = CsvReader::new();
+        assert!(reader.parse(text).is_err());
+
+        reader.set_missing_values(MissingValues::DropRow);
+        let dataset = reader.parse(text).unwrap();
+        assert!(dataset.len() == 1 && dataset.samples()[0].0[0][0] == 3.0);
+
+        //Rótulo ausente continua sendo erro
+        reader.set_missing_values(MissingValues::Mean);
+        assert!(reader.parse(text).is_err());
+        let text = "a,b,label\n1,,x\n3,4,y\nNA,8,x\n";
+        let dataset = reader.parse(text).unwrap();
+        assert!(dataset.samples()[0].0 == Matrix::from_vec(2, 1, vec![1.0, 6.0]));
+        assert!(dataset.samples()[2].0 == Matrix::from_vec(2, 1, vec![2.0, 8.0]));
+
+        reader.set_missing_values(MissingValues::Fill(-1.0));
+        let dataset = reader.parse(text).unwrap();
+        assert!(dataset.samples()[0].0[1][0] == -1.0);
+    }
+}
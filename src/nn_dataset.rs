@@ -0,0 +1,387 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::Mutex;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use crate::nn_matrix::Matrix;
+use crate::nn_metrics::argmax;
+use crate::nn_trainer::Sample;
+
+/**
+ * Conjunto de amostras com acesso por índice, independente de onde os dados estão
+ * (memória, arquivo, etc). As entradas e saídas esperadas de cada amostra são matrizes coluna.
+ */
+pub trait Dataset {
+    fn len(&self) -> usize;
+    fn get(&mut self, index: usize) -> Sample;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /**
+     * Classe da amostra, usada na divisão estratificada.
+     * O padrão é a maior saída esperada; conjuntos que leem os rótulos separadamente podem evitar ler a entrada.
+     */
+    fn label(&mut self, index: usize) -> usize {
+        argmax(&self.get(index).1)
+    }
+}
+
+/**
+ * Leitura de amostras por várias threads ao mesmo tempo, sem exclusão mútua (ex: Prefetcher).
+ * Implementada pelos conjuntos em memória ou mapeados, em que ler uma amostra não altera o conjunto.
+ * Conjuntos lidos de arquivo (que precisam de &mut self) podem ser compartilhados em um Mutex,
+ * travado durante a leitura de cada amostra.
+ */
+pub trait SharedDataset: Send + Sync {
+    fn sample(&self, index: usize) -> Sample;
+}
+
+impl<D: Dataset + Send> SharedDataset for Mutex<D> {
+    fn sample(&self, index: usize) -> Sample {
+        self.lock().unwrap().get(index)
+    }
+}
+
+impl Dataset for Vec<Sample> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn get(&mut self, index: usize) -> Sample {
+        self.sample(index)
+    }
+}
+
+impl SharedDataset for Vec<Sample> {
+    fn sample(&self, index: usize) -> Sample {
+        self[index].clone()
+    }
+}
+
+/**
+ * Percorre um conjunto (ou parte dele, dada pelos índices) em lotes.
+ * Cada lote é um par de matrizes com uma amostra por coluna, o formato aceito pela rede.
+ */
+pub struct DataLoader {
+    indices: Vec<usize>,
+    batch_size: usize,
+    shuffle: bool,
+    drop_last: bool,
+    rng: StdRng,
+}
+
+impl DataLoader {
+    /**
+     * Lotes com as amostras dos índices, na ordem dada (sem embaralhamento por padrão)
+     */
+    pub fn new(indices: Vec<usize>, batch_size: usize) -> DataLoader {
+        assert!(batch_size > 0);
+        DataLoader {
+            indices,
+            batch_size,
+            shuffle: false,
+            drop_last: false,
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    /**
+     * Lotes com todas as amostras do conjunto
+     */
+    pub fn for_dataset<D: Dataset + ?Sized>(dataset: &D, batch_size: usize) -> DataLoader {
+        DataLoader::new((0..dataset.len()).collect(), batch_size)
+    }
+
+    /**
+     * Embaralha as amostras a cada passagem (época)
+     */
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+    }
+
+    /**
+     * Descarta o último lote se ele tiver menos amostras que batch_size
+     */
+    pub fn set_drop_last(&mut self, drop_last: bool) {
+        self.drop_last = drop_last;
+    }
+
+    /**
+     * Semente do embaralhamento, para que a ordem das amostras seja reproduzível
+     */
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn num_batches(&self) -> usize {
+        if self.drop_last {
+            self.indices.len() / self.batch_size
+        } else {
+            self.indices.len().div_ceil(self.batch_size)
+        }
+    }
+
+    /**
+     * Índices das amostras de cada lote de uma passagem pelo conjunto
+     */
+    pub fn batch_indices(&mut self) -> Vec<Vec<usize>> {
+        let mut order = self.indices.clone();
+        if self.shuffle {
+            order.shuffle(&mut self.rng);
+        }
+        order
+            .chunks(self.batch_size)
+            .take(self.num_batches())
+            .map(|chunk| chunk.to_vec())
+            .collect()
+    }
+
+    /**
+     * Lotes de uma passagem pelo conjunto, lidos sob demanda
+     */
+    pub fn batches<'a, D: Dataset + ?Sized>(&mut self, dataset: &'a mut D) -> Batches<'a, D> {
+        Batches {
+            dataset,
+            batches: self.batch_indices().into_iter(),
+        }
+    }
+}
+
+pub struct Batches<'a, D: Dataset + ?Sized> {
+    dataset: &'a mut D,
+    batches: std::vec::IntoIter<Vec<usize>>,
+}
+
+impl<D: Dataset + ?Sized> Iterator for Batches<'_, D> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        let indices = self.batches.next()?;
+        Some(collate(self.dataset, &indices))
+    }
+}
+
+/**
+ * Une as amostras dos índices em um lote, uma amostra por coluna
+ */
+pub fn collate<D: Dataset + ?Sized>(dataset: &mut D, indices: &[usize]) -> Sample {
+    let samples: Vec<Sample> = indices.iter().map(|&index| dataset.get(index)).collect();
+    stack(&samples)
+}
+
+/**
+ * Une amostras (matrizes coluna) em um lote, na ordem dada.
+ * Uma única amostra é devolvida como está (ex: sequências, com um passo de tempo por coluna).
+ */
+pub fn stack(samples: &[Sample]) -> Sample {
+    assert!(!samples.is_empty());
+    if samples.len() == 1 {
+        return samples[0].clone();
+    }
+    let mut inputs = Matrix::new(samples[0].0.rows(), samples.len());
+    let mut targets = Matrix::new(samples[0].1.rows(), samples.len());
+    for (column, (input, target)) in samples.iter().enumerate() {
+        inputs.set_column(column, input);
+        targets.set_column(column, target);
+    }
+    (inputs, targets)
+}
+
+/**
+ * Divide os índices 0..len em partes com as frações dadas (ex: [0.8, 0.1, 0.1] para treino, validação e teste),
+ * em ordem aleatória determinada pela semente. A última parte recebe as amostras restantes do arredondamento.
+ */
+pub fn split_indices(len: usize, fractions: &[f64], seed: u64) -> Vec<Vec<usize>> {
+    let mut indices: Vec<usize> = (0..len).collect();
+    indices.shuffle(&mut StdRng::seed_from_u64(seed));
+    split_by_fractions(&indices, fractions)
+}
+
+/**
+ * Como split_indices, mas dividindo cada classe separadamente,
+ * de forma que todas as partes mantêm a proporção das classes do conjunto.
+ */
+pub fn stratified_split_indices(
+    dataset: &mut impl Dataset,
+    fractions: &[f64],
+    seed: u64,
+) -> Vec<Vec<usize>> {
+    let mut classes: Vec<Vec<usize>> = Vec::new();
+    for index in 0..dataset.len() {
+        let label = dataset.label(index);
+        if label >= classes.len() {
+            classes.resize(label + 1, Vec::new());
+        }
+        classes[label].push(index);
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut parts = vec![Vec::new(); fractions.len()];
+    for mut class_indices in classes {
+        class_indices.shuffle(&mut rng);
+        for (part, class_part) in parts
+            .iter_mut()
+            .zip(split_by_fractions(&class_indices, fractions))
+        {
+            part.extend(class_part);
+        }
+    }
+    //As partes são embaralhadas para não ficarem ordenadas por classe
+    parts.iter_mut().for_each(|part| part.shuffle(&mut rng));
+    parts
+}
+
+fn split_by_fractions(indices: &[usize], fractions: &[f64]) -> Vec<Vec<usize>> {
+    assert!(!fractions.is_empty() && fractions.iter().all(|&fraction| fraction >= 0.0));
+    let total: f64 = fractions.iter().sum();
+    assert!((total - 1.0).abs() < 1e-9, "As frações devem somar 1");
+    let mut parts = Vec::with_capacity(fractions.len());
+    let mut start = 0;
+    for (i, fraction) in fractions.iter().enumerate() {
+        let end = if i == fractions.len() - 1 {
+            indices.len()
+        } else {
+            (start + (fraction * indices.len() as f64).round() as usize).min(indices.len())
+        };
+        parts.push(indices[start..end].to_vec());
+        start = end;
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+
+    //Amostra i: entrada [i, -i], classe i % 3 (uma saída por classe)
+    fn samples(count: usize) -> Vec<Sample> {
+        (0..count)
+            .map(|i| {
+                let mut target = Matrix::new(3, 1);
+                target[i % 3][0] = 1.0;
+                (Matrix::from_vec(2, 1, vec![i as f64, -(i as f64)]), target)
+            })
+            .collect()
+    }
+
+    fn first_inputs(batch: &Sample) -> Vec<usize> {
+        (0..batch.0.cols())
+            .map(|j| batch.0[0][j] as usize)
+            .collect()
+    }
+
+    #[test]
+    fn test_batches() {
+        let mut dataset = samples(10);
+        let mut loader = DataLoader::for_dataset(&dataset, 4);
+        assert!(loader.num_batches() == 3);
+        let batches: Vec<Sample> = loader.batches(&mut dataset).collect();
+        assert!(batches.len() == 3);
+        assert!(first_inputs(&batches[0]) == [0, 1, 2, 3]);
+        assert!(first_inputs(&batches[2]) == [8, 9]);
+        assert!(batches[0].0.rows() == 2 && batches[0].1.rows() == 3);
+        assert!(batches[1].0[1][0] == -4.0);
+        assert!(batches[1].1[1][0] == 1.0);
+
+        loader.set_drop_last(true);
+        assert!(loader.num_batches() == 2);
+        assert!(
+            loader
+                .batches(&mut dataset)
+                .all(|batch| batch.0.cols() == 4)
+        );
+    }
+
+    #[test]
+    fn test_shuffle_seed() {
+        let mut dataset = samples(20);
+        let order = |seed: u64, dataset: &mut Vec<Sample>| -> Vec<usize> {
+            let mut loader = DataLoader::for_dataset(dataset, 3);
+            loader.set_shuffle(true);
+            loader.set_seed(seed);
+            loader
+                .batches(dataset)
+                .flat_map(|batch| first_inputs(&batch))
+                .collect()
+        };
+        let first = order(42, &mut dataset);
+        assert!(first == order(42, &mut dataset));
+        assert!(first != order(7, &mut dataset));
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert!(sorted == (0..20).collect::<Vec<usize>>());
+
+        //Passagens seguintes do mesmo DataLoader usam outra ordem
+        let mut loader = DataLoader::for_dataset(&dataset, 20);
+        loader.set_shuffle(true);
+        loader.set_seed(1);
+        assert!(loader.batch_indices() != loader.batch_indices());
+    }
+
+    #[test]
+    fn test_split() {
+        let parts = split_indices(10, &[0.6, 0.2, 0.2], 3);
+        assert!(parts.iter().map(|part| part.len()).collect::<Vec<_>>() == [6, 2, 2]);
+        let mut all: Vec<usize> = parts.concat();
+        all.sort();
+        assert!(all == (0..10).collect::<Vec<usize>>());
+        assert!(parts == split_indices(10, &[0.6, 0.2, 0.2], 3));
+
+        //Loader de uma das partes
+        let mut dataset = samples(10);
+        let mut loader = DataLoader::new(parts[1].clone(), 5);
+        let batch = loader.batches(&mut dataset).next().unwrap();
+        assert!(first_inputs(&batch) == parts[1]);
+    }
+
+    #[test]
+    fn test_stratified_split() {
+        //30 amostras, 10 de cada classe
+        let mut dataset = samples(30);
+        let parts = stratified_split_indices(&mut dataset, &[0.8, 0.2], 5);
+        assert!(parts[0].len() == 24 && parts[1].len() == 6);
+        for part in &parts {
+            let mut per_class = [0; 3];
+            for &index in part {
+                per_class[dataset.label(index)] += 1;
+            }
+            assert!(per_class.iter().all(|&count| count == part.len() / 3));
+        }
+        let mut all: Vec<usize> = parts.concat();
+        all.sort();
+        assert!(all == (0..30).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_fractions() {
+        split_indices(10, &[0.5, 0.2], 0);
+    }
+}
@@ -0,0 +1,271 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::nn_layer::{Gradient, NetworkLayer};
+use crate::nn_matrix::Matrix;
+
+/**
+ * Camada de embedding: associa cada token (índice inteiro em 0..vocabulário) a um vetor treinável.
+ * A entrada é uma matriz linha com um índice por coluna (1 x tokens), e a saída tem um vetor por coluna
+ * (dimensão x tokens), podendo alimentar camadas recorrentes, de atenção ou densas.
+ *
+ * Os pesos são a tabela de vetores (vocabulário x dimensão), um token por linha. Não há viéses.
+ * O gradiente é esparso (ver Gradient): contém apenas as linhas dos tokens presentes na entrada,
+ * e adjust_parameters só atualiza essas linhas. Memória e tempo não dependem do tamanho do vocabulário.
+ */
+pub struct Embedding {
+    vocab_size: usize,
+    dim: usize,
+    table: Matrix,
+    neurons: Matrix,
+    empty: Matrix,
+    indices: Vec<usize>,
+}
+
+impl Embedding {
+    pub fn new(vocab_size: usize, dim: usize) -> Embedding {
+        Embedding {
+            vocab_size,
+            dim,
+            table: Matrix::new_random_glorot(vocab_size, dim),
+            neurons: Matrix::new(dim, 1),
+            empty: Matrix::new(0, 0),
+            indices: Vec::new(),
+        }
+    }
+
+    /**
+     * Converte uma sequência de índices no formato de entrada da camada (1 x tokens)
+     */
+    pub fn indices_to_matrix(indices: &[usize]) -> Matrix {
+        Matrix::from_vec(
+            1,
+            indices.len(),
+            indices.iter().map(|&i| i as f64).collect(),
+        )
+    }
+
+    /**
+     * Vetores dos tokens, um por coluna (dimensão x tokens)
+     */
+    pub fn lookup(&self, indices: &[usize]) -> Matrix {
+        let mut vectors = Matrix::new(self.dim, indices.len());
+        for (t, &index) in indices.iter().enumerate() {
+            assert!(index < self.vocab_size);
+            for k in 0..self.dim {
+                vectors[k][t] = self.table[index][k];
+            }
+        }
+        vectors
+    }
+}
+
+impl NetworkLayer for Embedding {
+    fn propagate(&mut self, input: &Matrix) {
+        assert!(input.rows() == 1);
+        self.indices = input
+            .data()
+            .iter()
+            .map(|&value| {
+                assert!(
+                    value >= 0.0 && value.fract() == 0.0,
+                    "Índice inválido: {}",
+                    value
+                );
+                value as usize
+            })
+            .collect();
+        self.neurons = self.lookup(&self.indices);
+    }
+
+    fn neurons(&self) -> &Matrix {
+        &self.neurons
+    }
+    fn weights(&self) -> &Matrix {
+        &self.table
+    }
+    fn biases(&self) -> &Matrix {
+        &self.empty
+    }
+    fn fix_weights(&mut self, weights: Matrix) {
+        assert!(weights.rows() == self.vocab_size && weights.cols() == self.dim);
+        self.table = weights;
+    }
+    fn fix_bias(&mut self, biases: Matrix) {
+        assert!(biases.num_elements() == 0);
+    }
+
+    /**
+     * A coluna t da saída é a linha indices[t] da tabela, logo ∂C/∂tabela[indices[t]] += ∂C/∂y_t.
+     * Tokens repetidos acumulam gradiente na mesma linha. O gradiente tem uma linha por token distinto.
+     * Os índices não são diferenciáveis: o gradiente devolvido para a entrada é nulo.
+     */
+    fn backpropagate(
+        &mut self,
+        output_gradient: &Matrix,
+        prev_activations: &Matrix,
+    ) -> (Gradient, Matrix) {
+        assert!(output_gradient.rows() == self.dim && output_gradient.cols() == self.indices.len());
+        let mut rows = self.indices.clone();
+        rows.sort_unstable();
+        rows.dedup();
+        let mut rows_gradient = Matrix::new(rows.len(), self.dim);
+        for (t, index) in self.indices.iter().enumerate() {
+            let row = rows.binary_search(index).unwrap();
+            for k in 0..self.dim {
+                rows_gradient[row][k] += output_gradient[k][t];
+            }
+        }
+        (
+            Gradient {
+                weight: rows_gradient,
+                delta: Matrix::new(0, 0),
+                rows: Some(rows),
+            },
+            Matrix::new(prev_activations.rows(), prev_activations.cols()),
+        )
+    }
+
+    /**
+     * Gradiente esparso sem nenhuma linha
+     */
+    fn zero_gradient(&self) -> Gradient {
+        Gradient {
+            weight: Matrix::new(0, self.dim),
+            delta: Matrix::new(0, 0),
+            rows: Some(Vec::new()),
+        }
+    }
+
+    fn adjust_parameters(&mut self, gradients: &mut Gradient, learning_rate: f64) {
+        let rows = gradients
+            .rows
+            .as_ref()
+            .expect("Gradiente do embedding deve ser esparso");
+        for (i, &row) in rows.iter().enumerate() {
+            for k in 0..self.dim {
+                self.table[row][k] -= learning_rate * gradients.weight[i][k];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_layer::{Layer, Sigmoid};
+    use crate::nn_network::NeuralNetwork;
+    use crate::nn_recurrent::Lstm;
+
+    #[test]
+    fn test_lookup() {
+        let mut embedding = Embedding::new(4, 2);
+        embedding.fix_weights(Matrix::from_vec(
+            4,
+            2,
+            vec![0.0, 0.1, 1.0, 1.1, 2.0, 2.1, 3.0, 3.1],
+        ));
+        embedding.propagate(&Embedding::indices_to_matrix(&[2, 0, 2]));
+        print!("Embeddings: {}", embedding.neurons());
+        let expected = Matrix::from_vec(2, 3, vec![2.0, 0.0, 2.0, 2.1, 0.1, 2.1]);
+        assert!(*embedding.neurons() == expected);
+    }
+
+    #[test]
+    fn test_sparse_update() {
+        let mut embedding = Embedding::new(5, 2);
+        let original = embedding.weights().clone();
+        let input = Embedding::indices_to_matrix(&[1, 3, 1]);
+        embedding.propagate(&input);
+
+        let output_gradient = Matrix::from_vec(2, 3, vec![1.0, 2.0, 3.0, -1.0, -2.0, -3.0]);
+        let (mut gradient, input_gradient) = embedding.backpropagate(&output_gradient, &input);
+        assert!(input_gradient.is_zero());
+        //Apenas as linhas dos tokens 1 e 3.
+        //Token 1 aparece duas vezes: gradiente acumulado das colunas 0 e 2
+        assert!(gradient.rows == Some(vec![1, 3]));
+        assert!(gradient.weight.rows() == 2);
+        assert!(gradient.weight[0] == [4.0, -4.0]);
+        assert!(gradient.weight[1] == [2.0, -2.0]);
+
+        embedding.adjust_parameters(&mut gradient, 0.5);
+        let table = embedding.weights();
+        for row in [0, 2, 4] {
+            assert!(table[row] == original[row]);
+        }
+        assert!((table[1][0] - (original[1][0] - 2.0)).abs() < 1e-12);
+        assert!((table[3][1] - (original[3][1] + 1.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_accumulate_sparse() {
+        let mut embedding = Embedding::new(1000, 2);
+        let original = embedding.weights().clone();
+        let output_gradient = Matrix::from_vec(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        let first = Embedding::indices_to_matrix(&[7, 500]);
+        embedding.propagate(&first);
+        let (mut gradient, _) = embedding.backpropagate(&output_gradient, &first);
+        let second = Embedding::indices_to_matrix(&[500, 2]);
+        embedding.propagate(&second);
+        gradient.accumulate(&embedding.backpropagate(&output_gradient, &second).0);
+
+        assert!(gradient.rows == Some(vec![2, 7, 500]));
+        assert!(gradient.weight == Matrix::from_vec(3, 2, vec![2.0, 4.0, 1.0, 3.0, 3.0, 7.0]));
+        let dense = gradient.dense_weight(1000);
+        assert!(dense.rows() == 1000 && dense[500] == [3.0, 7.0] && dense[0] == [0.0, 0.0]);
+
+        embedding.adjust_parameters(&mut gradient, 1.0);
+        assert!(embedding.weights()[0] == original[0]);
+        assert!((embedding.weights()[2][1] - (original[2][1] - 4.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_character_sequence_classification() {
+        //Sequências de caracteres (vocabulário de 6): a classe indica se o token 5 aparece na sequência
+        let mut network = NeuralNetwork::new(3, 0.3);
+        network.add_layer(Embedding::new(6, 4));
+        network.add_layer(Lstm::new(4, 6));
+        network.add_layer(Layer::new::<Sigmoid>(6, 1));
+        let sequences: [(&[usize], f64); 4] = [
+            (&[0, 1, 2, 3], 0.0),
+            (&[5, 1, 2, 4], 1.0),
+            (&[3, 2, 5, 0], 1.0),
+            (&[4, 4, 1, 2], 0.0),
+        ];
+        let total_error = |network: &mut NeuralNetwork| -> f64 {
+            sequences
+                .iter()
+                .map(|(tokens, label)| {
+                    (network.classify(&Embedding::indices_to_matrix(tokens))[0][0] - label).powi(2)
+                })
+                .sum()
+        };
+
+        let initial_error = total_error(&mut network);
+        for _ in 0..100 {
+            for (tokens, label) in &sequences {
+                network.train(
+                    Embedding::indices_to_matrix(tokens),
+                    Matrix::from_vec(1, 1, vec![*label]),
+                );
+            }
+        }
+        let final_error = total_error(&mut network);
+        println!("Error: {} -> {}", initial_error, final_error);
+        assert!(final_error < initial_error);
+    }
+}
@@ -16,7 +16,14 @@
  */
 //http://neuralnetworksanddeeplearning.com/chap2.html
 //https://www.3blue1brown.com/lessons/backpropagation-calculus#title
-use std::io::{Read, Seek, SeekFrom};
+use std::io::Read;
+
+use crate::nn_dataset::{Dataset, SharedDataset};
+use crate::nn_gzip::{DataFile, GZIP_MAGIC};
+use crate::nn_idx::{IdxHeader, IdxReader, IdxType};
+use crate::nn_matrix::Matrix;
+use crate::nn_mmap::Mmap;
+use crate::nn_trainer::Sample;
 /**
  * Web Archive do formato usado no dataset MNIST
  *
@@ -25,23 +32,26 @@ use std::io::{Read, Seek, SeekFrom};
 
 #[derive(Debug)]
 pub struct ImageFileHeader {
-    magic_number: u32,
-    num_images: u32,
-    rows: u32,
-    cols: u32,
+    pub magic_number: u32,
+    pub num_images: u32,
+    pub rows: u32,
+    pub cols: u32,
 }
 #[derive(Debug)]
 pub struct LabelFileHeader {
-    magic_number: u32,
-    num_labels: u32,
+    pub magic_number: u32,
+    pub num_labels: u32,
 }
 
+/**
+ * Leitura sequencial dos pares (imagem, rótulo) de um conjunto EMNIST/MNIST no formato IDX
+ */
 pub struct Parser {
-    label_file: std::fs::File,
-    image_file: std::fs::File,
-    cur_index: u64,
-    labels_header: LabelFileHeader,
-    images_header: ImageFileHeader,
+    labels: IdxReader<DataFile>,
+    images: IdxReader<DataFile>,
+    cur_index: usize,
+    image_rows: usize,
+    image_cols: usize,
 }
 
 impl LabelFileHeader {
@@ -49,10 +59,6 @@ impl LabelFileHeader {
 }
 
 impl Parser {
-    const LABEL_OFFSET: u8 = 8;
-    const IMAGE_OFFSET: u8 = 16;
-    const IMAGE_SIZE: u64 = 28 * 28;
-
     pub fn parse_image_header(file_data: &[u8]) -> ImageFileHeader {
         ImageFileHeader {
             magic_number: u32::from_be_bytes(file_data[0..4].try_into().unwrap()),
@@ -69,73 +75,637 @@ impl Parser {
         }
     }
 
-    fn read_label_header(label_file: &mut std::fs::File) -> LabelFileHeader {
-        let mut buffer: [u8; 64] = [0; 64];
-        let _bytes_read = label_file.read(&mut buffer);
-        Parser::parse_label_header(&buffer)
-    }
-    fn read_image_header(image_file: &mut std::fs::File) -> ImageFileHeader {
-        let mut header_buffer: [u8; 32] = [0; 32];
-        let _bytes_read = image_file.read(&mut header_buffer);
-        Parser::parse_image_header(&header_buffer)
-    }
-
+    /**
+     * Abre os arquivos de rótulos (u8, 1 dimensão) e imagens (u8, 3 dimensões).
+     * Os arquivos podem estar comprimidos com gzip, como na distribuição oficial.
+     * O tamanho das imagens vem do cabeçalho; arquivos fora do formato encerram o programa.
+     */
     pub fn setup(label_file_name: &str, image_file_name: &str) -> Parser {
-        let mut label_file = std::fs::File::open(label_file_name).unwrap();
-        let mut image_file = std::fs::File::open(image_file_name).unwrap();
+        let labels = IdxReader::open(label_file_name).unwrap();
+        let images = IdxReader::open(image_file_name).unwrap();
+        check_headers(
+            labels.header(),
+            images.header(),
+            label_file_name,
+            image_file_name,
+        )
+        .unwrap_or_else(|error| panic!("{}", error));
+        let image_rows = images.header().dims()[1];
+        let image_cols = images.header().dims()[2];
         Parser {
+            labels,
+            images,
             cur_index: 0,
-            images_header: Parser::read_image_header(&mut image_file),
-            labels_header: Parser::read_label_header(&mut label_file),
-            label_file: label_file,
-            image_file: image_file,
+            image_rows,
+            image_cols,
         }
     }
 
+    pub fn image_rows(&self) -> usize {
+        self.image_rows
+    }
+
+    pub fn image_cols(&self) -> usize {
+        self.image_cols
+    }
+
+    /**
+     * Indica se algum dos arquivos está comprimido com gzip
+     */
+    pub fn is_gzip(&self) -> bool {
+        self.labels.get_ref().is_gzip() || self.images.get_ref().is_gzip()
+    }
+
     pub fn transpose(image_buffer: &[u8], transposed_buffer: &mut[u8]){
-        for row in 0..28 {
-            for col in 0..28 {
+        Parser::transpose_image(image_buffer, 28, 28, transposed_buffer);
+    }
+
+    /**
+     * Transpõe uma imagem armazenada com rows x cols no arquivo,
+     * gerando uma imagem cols x rows com as linhas de baixo para cima (ordem do BMP)
+     */
+    pub fn transpose_image(
+        image_buffer: &[u8],
+        rows: usize,
+        cols: usize,
+        transposed_buffer: &mut [u8],
+    ) {
+        assert!(image_buffer.len() == rows * cols && transposed_buffer.len() == rows * cols);
+        for row in 0..cols {
+            for col in 0..rows {
                 // Lendo do EMNIST (Topo -> Base), transpondo linhas/colunas
-                let pixel = image_buffer[col * 28 + row];
+                let pixel = image_buffer[col * cols + row];
                 // Escrevendo no BMP (Base -> Topo)
-                // A linha 0 do BMP é a linha 27 da imagem original
-                let bmp_row = 27 - row;
-                let bmp_index = bmp_row * 28 + col;
+                // A linha 0 do BMP é a última linha da imagem original
+                let bmp_row = cols - 1 - row;
+                let bmp_index = bmp_row * rows + col;
                 transposed_buffer[bmp_index] = pixel;
             }
         }
     }
 
     pub fn read_next(&mut self) -> (Vec<u8>, u8) {
-        let next_label_offset = Parser::LABEL_OFFSET as u64 + (self.cur_index);
-        let next_image_offset = Parser::IMAGE_OFFSET as u64 + (Parser::IMAGE_SIZE * self.cur_index);
+        let sample = self.read(self.cur_index);
+        self.cur_index += 1;
+        sample
+    }
 
-        self.label_file
-            .seek(SeekFrom::Start(next_label_offset))
-            .unwrap();
-        let mut label_buffer: [u8; 1] = [0];
-        let _label_bytes: Result<usize, std::io::Error> = self.label_file.read(&mut label_buffer);
+    pub fn len(&self) -> usize {
+        self.labels.num_items()
+    }
 
-        let mut image_buffer: [u8; 28 * 28] = [0; 28 * 28];
-        
-        self.image_file
-            .seek(SeekFrom::Start(next_image_offset))
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn read_label(&mut self, index: usize) -> u8 {
+        let mut label_buffer: [u8; 1] = [0];
+        self.labels
+            .read_item_bytes(index, &mut label_buffer)
             .unwrap();
-        let _img_bytes = self.image_file.read(&mut image_buffer);
+        label_buffer[0]
+    }
 
-        self.cur_index += 1;
+    /**
+     * Leitura da amostra de qualquer posição, sem alterar a posição de read_next
+     */
+    pub fn read(&mut self, index: usize) -> (Vec<u8>, u8) {
+        let label = self.read_label(index);
+
+        let mut image_buffer = vec![0; self.image_rows * self.image_cols];
+        self.images
+            .read_item_bytes(index, &mut image_buffer)
+            .unwrap();
 
-        let mut transposed_buffer: [u8; 28 * 28] = [0; 28 * 28];
-        Parser::transpose(&image_buffer, &mut transposed_buffer);
-        (transposed_buffer.to_vec(), label_buffer[0])
+        let mut transposed_buffer = vec![0; image_buffer.len()];
+        Parser::transpose_image(
+            &image_buffer,
+            self.image_rows,
+            self.image_cols,
+            &mut transposed_buffer,
+        );
+        (transposed_buffer, label)
     }
 
     pub fn has_more(&self)->bool{
-        return self.cur_index < self.labels_header.num_labels as u64
+        self.cur_index < self.labels.num_items()
     }
 
 }
 
+/**
+ * Divisões do dataset EMNIST. Cada uma tem seus arquivos de treino e teste
+ * e um arquivo de mapeamento dos rótulos para caracteres (emnist-<divisão>-mapping.txt).
+ * https://www.nist.gov/itl/products-and-services/emnist-dataset
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Split {
+    ByClass,  //62 classes: dígitos, maiúsculas e minúsculas
+    ByMerge,  //47 classes: minúsculas parecidas com as maiúsculas unidas a elas
+    Balanced, //47 classes, com o mesmo número de amostras por classe
+    Letters,  //26 classes, maiúsculas e minúsculas unidas, rótulos de 1 a 26
+    Digits,   //10 classes
+    Mnist,    //10 classes, mesmo tamanho do MNIST original
+}
+
+impl Split {
+    pub const ALL: [Split; 6] = [
+        Split::ByClass,
+        Split::ByMerge,
+        Split::Balanced,
+        Split::Letters,
+        Split::Digits,
+        Split::Mnist,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Split::ByClass => "byclass",
+            Split::ByMerge => "bymerge",
+            Split::Balanced => "balanced",
+            Split::Letters => "letters",
+            Split::Digits => "digits",
+            Split::Mnist => "mnist",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Split> {
+        Split::ALL.into_iter().find(|split| split.name() == name)
+    }
+
+    pub fn num_classes(&self) -> usize {
+        match self {
+            Split::ByClass => 62,
+            Split::ByMerge | Split::Balanced => 47,
+            Split::Letters => 26,
+            Split::Digits | Split::Mnist => 10,
+        }
+    }
+
+    /**
+     * Índice da classe (0..num_classes) de um rótulo do arquivo.
+     * Os rótulos da divisão letters começam em 1.
+     */
+    pub fn class_index(&self, label: u8) -> usize {
+        let first_label = if *self == Split::Letters { 1 } else { 0 };
+        let index = (label as usize)
+            .checked_sub(first_label)
+            .filter(|&index| index < self.num_classes());
+        index.unwrap_or_else(|| panic!("Rótulo {} inválido em {}", label, self.name()))
+    }
+
+    /**
+     * Rótulo do arquivo correspondente ao índice da classe (inverso de class_index)
+     */
+    pub fn label(&self, class: usize) -> u8 {
+        assert!(class < self.num_classes());
+        let first_label = if *self == Split::Letters { 1 } else { 0 };
+        (class + first_label) as u8
+    }
+
+    fn file_name(&self, dir: &str, suffix: &str) -> String {
+        format!("{}/emnist-{}-{}", dir, self.name(), suffix)
+    }
+
+    /**
+     * Arquivo de dados com o nome original ou, se ele não existir, sua versão comprimida (.gz)
+     */
+    fn data_file_name(&self, dir: &str, suffix: &str) -> String {
+        let file_name = self.file_name(dir, suffix);
+        let compressed = format!("{}.gz", file_name);
+        if !std::path::Path::new(&file_name).exists()
+            && std::path::Path::new(&compressed).exists()
+        {
+            compressed
+        } else {
+            file_name
+        }
+    }
+
+    /**
+     * Arquivos (rótulos, imagens) de treino
+     */
+    pub fn train_files(&self, dir: &str) -> (String, String) {
+        (
+            self.data_file_name(dir, "train-labels-idx1-ubyte"),
+            self.data_file_name(dir, "train-images-idx3-ubyte"),
+        )
+    }
+
+    /**
+     * Arquivos (rótulos, imagens) de teste
+     */
+    pub fn test_files(&self, dir: &str) -> (String, String) {
+        (
+            self.data_file_name(dir, "test-labels-idx1-ubyte"),
+            self.data_file_name(dir, "test-images-idx3-ubyte"),
+        )
+    }
+
+    pub fn mapping_file(&self, dir: &str) -> String {
+        self.file_name(dir, "mapping.txt")
+    }
+}
+
+/**
+ * Mapeamento dos rótulos para caracteres. Cada linha do arquivo tem o rótulo seguido
+ * de um ou mais códigos ASCII (na divisão letters, maiúscula e minúscula: "1 65 97").
+ */
+#[derive(Debug, Clone)]
+pub struct LabelMapping {
+    labels: Vec<(u8, String)>,
+}
+
+impl LabelMapping {
+    pub fn parse(text: &str) -> std::io::Result<LabelMapping> {
+        let invalid = |line: &str| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Linha de mapeamento inválida: {}", line),
+            )
+        };
+        let mut labels = Vec::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let mut fields = line.split_whitespace();
+            let label: u8 = fields
+                .next()
+                .and_then(|field| field.parse().ok())
+                .ok_or_else(|| invalid(line))?;
+            let characters = fields
+                .map(|field| field.parse::<u8>().map(char::from))
+                .collect::<Result<String, _>>()
+                .map_err(|_| invalid(line))?;
+            if characters.is_empty() {
+                return Err(invalid(line));
+            }
+            labels.push((label, characters));
+        }
+        Ok(LabelMapping { labels })
+    }
+
+    pub fn read_file(file_name: &str) -> std::io::Result<LabelMapping> {
+        LabelMapping::parse(&std::fs::read_to_string(file_name)?)
+    }
+
+    pub fn num_classes(&self) -> usize {
+        self.labels.len()
+    }
+
+    /**
+     * Caracteres do rótulo do arquivo (não do índice da classe)
+     */
+    pub fn characters(&self, label: u8) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(mapped_label, _)| *mapped_label == label)
+            .map(|(_, characters)| characters.as_str())
+    }
+}
+
+/**
+ * Confere se os arquivos são de rótulos (u8, 1 dimensão) e imagens (u8, 3 dimensões) com a mesma quantidade de itens
+ */
+pub(crate) fn check_headers(
+    labels: &IdxHeader,
+    images: &IdxHeader,
+    label_file_name: &str,
+    image_file_name: &str,
+) -> std::io::Result<()> {
+    let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+    if labels.data_type() != IdxType::U8 || labels.dims().len() != 1 {
+        return Err(invalid(format!(
+            "{}: esperado um arquivo IDX de rótulos u8",
+            label_file_name
+        )));
+    }
+    if images.data_type() != IdxType::U8 || images.dims().len() != 3 {
+        return Err(invalid(format!(
+            "{}: esperado um arquivo IDX de imagens u8",
+            image_file_name
+        )));
+    }
+    if labels.num_items() != images.num_items() {
+        return Err(invalid(
+            "Quantidades de rótulos e imagens diferentes".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/**
+ * Amostra no formato da rede: pixels normalizados para 0..1 em uma coluna
+ * e saída esperada com uma posição por classe da divisão
+ */
+fn emnist_sample(image: &[u8], label: u8, split: Split) -> Sample {
+    let pixels = image.iter().map(|&pixel| pixel as f64 / 255.0).collect();
+    let mut expected = Matrix::new(split.num_classes(), 1);
+    expected[split.class_index(label)][0] = 1.0;
+    (Matrix::from_vec(image.len(), 1, pixels), expected)
+}
+
+fn emnist_files(dir: &str, split: Split, train: bool) -> (String, String) {
+    if train {
+        split.train_files(dir)
+    } else {
+        split.test_files(dir)
+    }
+}
+
+/**
+ * Dataset do EMNIST com acesso por índice: entradas com os pixels normalizados para 0..1
+ * e saídas esperadas com uma posição por classe da divisão.
+ * Cada amostra é lida do disco, por isso os arquivos precisam estar descomprimidos;
+ * para os .gz use InMemoryEmnist.
+ */
+pub struct EmnistDataset {
+    parser: Parser,
+    split: Split,
+}
+
+impl EmnistDataset {
+    /**
+     * Recusa arquivos gzip: voltar no fluxo comprimido recomeça a descompressão do início,
+     * e com os índices embaralhados cada época ficaria quadrática no tamanho do arquivo.
+     */
+    pub fn new(parser: Parser, split: Split) -> std::io::Result<EmnistDataset> {
+        if parser.is_gzip() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "EmnistDataset não faz acesso aleatório a arquivos .gz; use InMemoryEmnist ou descomprima os arquivos",
+            ));
+        }
+        Ok(EmnistDataset { parser, split })
+    }
+
+    /**
+     * Arquivos de treino ou de teste da divisão, no diretório dir
+     */
+    pub fn open(dir: &str, split: Split, train: bool) -> std::io::Result<EmnistDataset> {
+        let (labels_file, images_file) = emnist_files(dir, split, train);
+        EmnistDataset::new(Parser::setup(&labels_file, &images_file), split)
+    }
+
+    pub fn split(&self) -> Split {
+        self.split
+    }
+}
+
+impl Dataset for EmnistDataset {
+    fn len(&self) -> usize {
+        self.parser.len()
+    }
+
+    fn get(&mut self, index: usize) -> Sample {
+        let (image, label) = self.parser.read(index);
+        emnist_sample(&image, label, self.split)
+    }
+
+    fn label(&mut self, index: usize) -> usize {
+        let label = self.parser.read_label(index);
+        self.split.class_index(label)
+    }
+}
+
+/**
+ * Conjunto EMNIST carregado inteiro na memória, com as imagens já transpostas.
+ * Os arquivos são lidos uma única vez (inclusive os .gz), e cada amostra depois é só uma cópia da memória.
+ * O byclass de treino ocupa cerca de 550MB (697.932 imagens de 784 bytes).
+ */
+pub struct InMemoryEmnist {
+    labels: Vec<u8>,
+    images: Vec<u8>,
+    image_rows: usize,
+    image_cols: usize,
+    split: Split,
+}
+
+impl InMemoryEmnist {
+    pub fn load(
+        label_file_name: &str,
+        image_file_name: &str,
+        split: Split,
+    ) -> std::io::Result<InMemoryEmnist> {
+        let mut label_file = DataFile::open(label_file_name)?;
+        let mut image_file = DataFile::open(image_file_name)?;
+        let labels_header = IdxHeader::read_from(&mut label_file)?;
+        let images_header = IdxHeader::read_from(&mut image_file)?;
+        check_headers(
+            &labels_header,
+            &images_header,
+            label_file_name,
+            image_file_name,
+        )?;
+
+        let mut labels = vec![0; labels_header.num_items()];
+        label_file.read_exact(&mut labels)?;
+
+        let image_rows = images_header.dims()[1];
+        let image_cols = images_header.dims()[2];
+        let image_len = images_header.item_len();
+        let mut images = vec![0; images_header.num_items() * image_len];
+        //Lê em blocos de imagens, transpondo cada uma para a posição final
+        let mut buffer = vec![0; image_len * 1024];
+        for chunk in images.chunks_mut(buffer.len()) {
+            let read_buffer = &mut buffer[..chunk.len()];
+            image_file.read_exact(read_buffer)?;
+            for (image, transposed) in read_buffer
+                .chunks(image_len)
+                .zip(chunk.chunks_mut(image_len))
+            {
+                Parser::transpose_image(image, image_rows, image_cols, transposed);
+            }
+        }
+        Ok(InMemoryEmnist {
+            labels,
+            images,
+            image_rows,
+            image_cols,
+            split,
+        })
+    }
+
+    /**
+     * Arquivos de treino ou de teste da divisão, no diretório dir
+     */
+    pub fn open(dir: &str, split: Split, train: bool) -> std::io::Result<InMemoryEmnist> {
+        let (labels_file, images_file) = emnist_files(dir, split, train);
+        InMemoryEmnist::load(&labels_file, &images_file, split)
+    }
+
+    pub fn image_rows(&self) -> usize {
+        self.image_rows
+    }
+
+    pub fn image_cols(&self) -> usize {
+        self.image_cols
+    }
+
+    /**
+     * Imagem já transposta (mesmo formato de Parser::read)
+     */
+    pub fn image(&self, index: usize) -> &[u8] {
+        let image_len = self.image_rows * self.image_cols;
+        &self.images[index * image_len..(index + 1) * image_len]
+    }
+
+    /**
+     * Rótulo como está no arquivo (não o índice da classe)
+     */
+    pub fn raw_label(&self, index: usize) -> u8 {
+        self.labels[index]
+    }
+}
+
+impl Dataset for InMemoryEmnist {
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn get(&mut self, index: usize) -> Sample {
+        self.sample(index)
+    }
+
+    fn label(&mut self, index: usize) -> usize {
+        self.split.class_index(self.labels[index])
+    }
+}
+
+impl SharedDataset for InMemoryEmnist {
+    fn sample(&self, index: usize) -> Sample {
+        emnist_sample(self.image(index), self.labels[index], self.split)
+    }
+}
+
+/**
+ * Conjunto EMNIST lido de arquivos mapeados na memória, para conjuntos maiores que a RAM.
+ * Os arquivos precisam estar descomprimidos; a transposição é feita a cada amostra.
+ * Disponível apenas em unix 64 bits, e os arquivos não podem ser modificados enquanto o conjunto existir
+ * (ver Mmap::open).
+ */
+pub struct MappedEmnist {
+    labels: Mmap,
+    images: Mmap,
+    labels_offset: usize,
+    images_offset: usize,
+    num_items: usize,
+    image_rows: usize,
+    image_cols: usize,
+    split: Split,
+}
+
+impl MappedEmnist {
+    pub fn load(
+        label_file_name: &str,
+        image_file_name: &str,
+        split: Split,
+    ) -> std::io::Result<MappedEmnist> {
+        let labels = Mmap::open(label_file_name)?;
+        let images = Mmap::open(image_file_name)?;
+        for (map, file_name) in [(&labels, label_file_name), (&images, image_file_name)] {
+            if map.starts_with(&GZIP_MAGIC) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "{}: arquivos comprimidos não podem ser mapeados, use InMemoryEmnist",
+                        file_name
+                    ),
+                ));
+            }
+        }
+        let labels_header = IdxHeader::read_from(&mut &labels[..])?;
+        let images_header = IdxHeader::read_from(&mut &images[..])?;
+        check_headers(
+            &labels_header,
+            &images_header,
+            label_file_name,
+            image_file_name,
+        )?;
+
+        let num_items = labels_header.num_items();
+        let labels_offset = labels_header.byte_len();
+        let images_offset = images_header.byte_len();
+        if labels.len() < labels_offset + num_items
+            || images.len() < images_offset + num_items * images_header.item_len()
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Arquivo IDX menor que o indicado no cabeçalho",
+            ));
+        }
+        Ok(MappedEmnist {
+            labels,
+            images,
+            labels_offset,
+            images_offset,
+            num_items,
+            image_rows: images_header.dims()[1],
+            image_cols: images_header.dims()[2],
+            split,
+        })
+    }
+
+    /**
+     * Arquivos de treino ou de teste da divisão, no diretório dir
+     */
+    pub fn open(dir: &str, split: Split, train: bool) -> std::io::Result<MappedEmnist> {
+        let (labels_file, images_file) = emnist_files(dir, split, train);
+        MappedEmnist::load(&labels_file, &images_file, split)
+    }
+
+    pub fn image_rows(&self) -> usize {
+        self.image_rows
+    }
+
+    pub fn image_cols(&self) -> usize {
+        self.image_cols
+    }
+
+    /**
+     * Imagem transposta (mesmo formato de Parser::read)
+     */
+    pub fn image(&self, index: usize) -> Vec<u8> {
+        assert!(index < self.num_items);
+        let image_len = self.image_rows * self.image_cols;
+        let start = self.images_offset + index * image_len;
+        let mut transposed = vec![0; image_len];
+        Parser::transpose_image(
+            &self.images[start..start + image_len],
+            self.image_rows,
+            self.image_cols,
+            &mut transposed,
+        );
+        transposed
+    }
+
+    /**
+     * Rótulo como está no arquivo (não o índice da classe)
+     */
+    pub fn raw_label(&self, index: usize) -> u8 {
+        assert!(index < self.num_items);
+        self.labels[self.labels_offset + index]
+    }
+}
+
+impl Dataset for MappedEmnist {
+    fn len(&self) -> usize {
+        self.num_items
+    }
+
+    fn get(&mut self, index: usize) -> Sample {
+        self.sample(index)
+    }
+
+    fn label(&mut self, index: usize) -> usize {
+        self.split.class_index(self.raw_label(index))
+    }
+}
+
+impl SharedDataset for MappedEmnist {
+    fn sample(&self, index: usize) -> Sample {
+        emnist_sample(&self.image(index), self.raw_label(index), self.split)
+    }
+}
+
 /**
  * Struct para gerar imagens no formato bmp.
  * Usada apenas para validar o parser visualmente
@@ -190,7 +760,7 @@ impl Dib {
         array[28..32].copy_from_slice(&self.y_per_m.to_le_bytes());
         array[32..36].copy_from_slice(&self.colors_used.to_le_bytes());
         array[36..40].copy_from_slice(&self.colors_important.to_le_bytes());
-        return array;
+        array
     }
 }
 
@@ -214,7 +784,7 @@ impl Bitmap {
         array[2..6].copy_from_slice(&self.size.to_le_bytes());
         array[6..10].copy_from_slice(&self.reserved.to_le_bytes());
         array[10..14].copy_from_slice(&self.offset.to_le_bytes());
-        return array;
+        array
     }
 
     pub fn create_bitmap(image_buffer: &[u8]) -> Vec<u8> {
@@ -233,7 +803,7 @@ impl Bitmap {
             byte_array[idx + 3] = 0; // Reserved (Alpha/Padding)
         }
         // let offset_pixels = 1078;
-        byte_array[1078..].copy_from_slice(&image_buffer);
+        byte_array[1078..].copy_from_slice(image_buffer);
         byte_array.to_vec()
     }
 }
@@ -246,7 +816,8 @@ mod tests {
         io::{Seek, SeekFrom},
     };
 
-    use crate::nn_matrix::Matrix;
+    use crate::nn_gzip::gzip_stored;
+    use crate::nn_idx::IdxTensor;
 
     // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
     use super::*;
@@ -255,7 +826,7 @@ mod tests {
         let mut file = File::open("emnist/emnist-digits-train-labels-idx1-ubyte").unwrap();
         let mut buffer: [u8; 64] = [0; 64];
 
-        let bytes_read = file.read(&mut buffer);
+        let _bytes_read = file.read(&mut buffer);
 
         let header = Parser::parse_label_header(&buffer);
 
@@ -263,9 +834,9 @@ mod tests {
             "Magic: {:?}, Num_Labels: {:?}",
             header.magic_number, header.num_labels
         );
-        file.seek(SeekFrom::Start((8))).unwrap();
+        file.seek(SeekFrom::Start(8)).unwrap();
         let mut label_buffer: [u8; 1] = [0];
-        let lb = file.read(&mut label_buffer);
+        let _lb = file.read(&mut label_buffer);
         println!("Label: {:?}", label_buffer[0]);
     }
 
@@ -288,7 +859,7 @@ mod tests {
         );
 
         let mut image_buffer: [u8; 28 * 28] = [0; 28 * 28];
-        file.seek(SeekFrom::Start((16))).unwrap();
+        file.seek(SeekFrom::Start(16)).unwrap();
 
         let mut i = 0;
         while let Ok(bytes_read) = file.read(&mut image_buffer) {
@@ -320,15 +891,15 @@ mod tests {
             let (img, label) = parser.read_next();
             println!("Label: {}", label);
             let bitmap = Bitmap::create_bitmap(&img);
-            let write = std::fs::write(format!("parsed_digit{}.bmp", i), &bitmap);
+            let _write = std::fs::write(format!("parsed_digit{}.bmp", i), &bitmap);
             
 
-            let matrix = Matrix::from_vec(28, 28, img.iter().map(|f| (*f as f64)).collect());
-            let nm = matrix.rotate(0.785398);
+            let matrix = Matrix::from_vec(28, 28, img.iter().map(|f| *f as f64).collect());
+            let nm = matrix.rotate(std::f64::consts::FRAC_PI_4);
 
-            let bmp:Vec<u8> = nm.data().iter().map(|f| (*f as u8)).collect();
+            let bmp:Vec<u8> = nm.data().iter().map(|f| *f as u8).collect();
             let bitmap = Bitmap::create_bitmap(&bmp);
-            let write = std::fs::write(format!("parsed_digit{}_rotated.bmp", i), &bitmap);
+            let _write = std::fs::write(format!("parsed_digit{}_rotated.bmp", i), &bitmap);
             
             // let translated_right = translate_right_f64(&img);
             // let rt = translated_right.iter().map(|f| (*f as u8));
@@ -341,4 +912,146 @@ mod tests {
             // let write = std::fs::write(format!("parsed_digit{}_left.bmp", i), bitmap);
         }
     }
+
+    #[test]
+    pub fn test_parser_idx() {
+        //2 imagens 2x3 armazenadas transpostas, como no EMNIST
+        let dir = std::env::temp_dir();
+        let labels_file = dir.join("nn_emnist_test_labels.idx");
+        let images_file = dir.join("nn_emnist_test_images.idx");
+        let labels_file = labels_file.to_str().unwrap();
+        let images_file = images_file.to_str().unwrap();
+        IdxTensor::new(IdxType::U8, vec![2], vec![4.0, 9.0])
+            .write_file(labels_file)
+            .unwrap();
+        let pixels: Vec<f64> = (0..12).map(|i| i as f64).collect();
+        IdxTensor::new(IdxType::U8, vec![2, 2, 3], pixels)
+            .write_file(images_file)
+            .unwrap();
+
+        let mut parser = Parser::setup(labels_file, images_file);
+        assert!(parser.image_rows() == 2 && parser.image_cols() == 3);
+        let (img, label) = parser.read_next();
+        assert!(label == 4);
+        //Transposta 3x2 ([0 3], [1 4], [2 5]) com as linhas de baixo para cima
+        assert!(img == vec![2, 5, 1, 4, 0, 3]);
+        let (img, label) = parser.read_next();
+        assert!(label == 9 && img[0] == 8);
+        assert!(!parser.has_more());
+        assert!(parser.read(0).1 == 4);
+
+        let mut dataset = EmnistDataset::new(parser, Split::Digits).unwrap();
+        assert!(dataset.len() == 2);
+        assert!(dataset.label(1) == 9);
+        let (input, expected) = dataset.get(0);
+        assert!(input.rows() == 6 && input[0][0] == 2.0 / 255.0);
+        assert!(expected.rows() == 10 && expected[4][0] == 1.0);
+
+        //Com as imagens comprimidas o acesso por índice é recusado
+        let gz_file = dir.join("nn_emnist_test_images.gz");
+        let gz_file = gz_file.to_str().unwrap();
+        std::fs::write(gz_file, gzip_stored(&std::fs::read(images_file).unwrap())).unwrap();
+        let parser = Parser::setup(labels_file, gz_file);
+        assert!(parser.is_gzip());
+        let error = EmnistDataset::new(parser, Split::Digits).err().unwrap();
+        assert!(error.kind() == std::io::ErrorKind::Unsupported);
+        assert!(InMemoryEmnist::load(labels_file, gz_file, Split::Digits).is_ok());
+
+        std::fs::remove_file(labels_file).unwrap();
+        std::fs::remove_file(images_file).unwrap();
+        std::fs::remove_file(gz_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(unix, target_pointer_width = "64"))]
+    pub fn test_backends() {
+        //Mais imagens que um bloco da leitura em memória (1024)
+        let count = 1500;
+        let dir = std::env::temp_dir();
+        let labels_file = dir.join("nn_emnist_backends_labels.idx");
+        let images_file = dir.join("nn_emnist_backends_images.idx");
+        let labels_file = labels_file.to_str().unwrap();
+        let images_file = images_file.to_str().unwrap();
+        let labels: Vec<f64> = (0..count).map(|i| (i % 10) as f64).collect();
+        IdxTensor::new(IdxType::U8, vec![count], labels)
+            .write_file(labels_file)
+            .unwrap();
+        let pixels: Vec<f64> = (0..count * 6).map(|i| (i * 7 % 256) as f64).collect();
+        IdxTensor::new(IdxType::U8, vec![count, 2, 3], pixels)
+            .write_file(images_file)
+            .unwrap();
+
+        let mut parser = Parser::setup(labels_file, images_file);
+        let mut memory = InMemoryEmnist::load(labels_file, images_file, Split::Digits).unwrap();
+        let mut mapped = MappedEmnist::load(labels_file, images_file, Split::Digits).unwrap();
+        assert!(memory.len() == count && mapped.len() == count);
+        assert!(memory.image_rows() == 2 && mapped.image_cols() == 3);
+        for index in [0, 1, 1023, 1024, 1499] {
+            let (image, label) = parser.read(index);
+            assert!(memory.image(index) == image && mapped.image(index) == image);
+            assert!(memory.raw_label(index) == label && mapped.raw_label(index) == label);
+            assert!(memory.label(index) == label as usize);
+            let sample = memory.get(index);
+            assert!(sample.0 == mapped.get(index).0 && sample.1 == mapped.get(index).1);
+        }
+
+        //Arquivos comprimidos só podem ser carregados na memória
+        let gz_file = dir.join("nn_emnist_backends_images.gz");
+        let gz_file = gz_file.to_str().unwrap();
+        std::fs::write(gz_file, [0x1F, 0x8B, 8, 0]).unwrap();
+        assert!(MappedEmnist::load(labels_file, gz_file, Split::Digits).is_err());
+        //Rótulos no lugar das imagens
+        assert!(InMemoryEmnist::load(labels_file, labels_file, Split::Digits).is_err());
+
+        std::fs::remove_file(labels_file).unwrap();
+        std::fs::remove_file(images_file).unwrap();
+        std::fs::remove_file(gz_file).unwrap();
+    }
+
+    #[test]
+    pub fn test_splits() {
+        for split in Split::ALL {
+            assert!(Split::from_name(split.name()) == Some(split));
+        }
+        assert!(Split::from_name("emnist").is_none());
+        let (labels, images) = Split::Letters.train_files("emnist");
+        assert!(labels == "emnist/emnist-letters-train-labels-idx1-ubyte");
+        assert!(images == "emnist/emnist-letters-train-images-idx3-ubyte");
+        assert!(Split::Balanced.mapping_file("emnist") == "emnist/emnist-balanced-mapping.txt");
+
+        //Rótulos da divisão letters vão de 1 a 26
+        assert!(Split::Letters.class_index(1) == 0);
+        assert!(Split::Letters.class_index(26) == 25);
+        assert!(Split::Digits.class_index(0) == 0);
+        assert!(Split::ByClass.class_index(61) == 61);
+        assert!(Split::Letters.label(0) == 1);
+        assert!(Split::Mnist.label(9) == 9);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn test_invalid_label() {
+        Split::Digits.class_index(10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rótulo 0 inválido em letters")]
+    pub fn test_invalid_letters_label() {
+        Split::Letters.class_index(0);
+    }
+
+    #[test]
+    pub fn test_label_mapping() {
+        let digits = LabelMapping::parse("0 48\n1 49\n9 57\n").unwrap();
+        assert!(digits.num_classes() == 3);
+        assert!(digits.characters(9) == Some("9"));
+        assert!(digits.characters(2).is_none());
+
+        let letters = LabelMapping::parse("1 65 97\n2 66 98\n").unwrap();
+        assert!(letters.characters(2) == Some("Bb"));
+
+        assert!(LabelMapping::parse("1\n").is_err());
+        assert!(LabelMapping::parse("a 65\n").is_err());
+        assert!(LabelMapping::parse("1 300\n").is_err());
+    }
 }
@@ -0,0 +1,403 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://cs231n.github.io/neural-networks-3/#gradcheck
+#[cfg(test)]
+use crate::nn_layer::NetworkLayer;
+use crate::nn_matrix::Matrix;
+use crate::nn_network::NeuralNetwork;
+
+//Menor escala usada no erro relativo: evita que o arredondamento das diferenças finitas
+//domine a comparação de derivadas praticamente nulas
+const GRADIENT_FLOOR: f64 = 1e-6;
+
+/**
+ * Compara os gradientes de generate_gradients com diferenças finitas centrais
+ * (C(p + eps) - C(p - eps)) / 2eps, perturbando cada peso e viés da rede.
+ * Devolve o maior erro relativo |analítico - numérico| / max(|analítico|, |numérico|, 1e-6) de cada camada,
+ * na ordem das camadas (camadas sem parâmetros têm erro 0).
+ *
+ * O custo é calculado com a rede em modo de treinamento, o mesmo usado na retropropagação.
+ * O estado das camadas (ex: estatísticas da BatchNorm) é restaurado após cada propagação
+ * e os parâmetros ao final, de forma que a verificação não altera a rede.
+ */
+pub fn gradient_check(
+    network: &mut NeuralNetwork,
+    input: &Matrix,
+    target: &Matrix,
+    epsilon: f64,
+) -> Vec<f64> {
+    let states = layer_states(network);
+    network.forward(input, true);
+    let gradients = network.generate_gradients(input.clone(), target.clone());
+    restore_states(network, &states);
+
+    let mut errors = Vec::with_capacity(network.num_layers());
+    for (i, gradient) in gradients.iter().enumerate() {
+        let layer = i + 1;
+        let weights_rows = network.borrow_layer(layer).weights().rows();
+        let weights_error = check_parameter(
+            network,
+            layer,
+            false,
+            &gradient.dense_weight(weights_rows),
+            (input, target),
+            &states,
+            epsilon,
+        );
+        let biases_error = check_parameter(
+            network,
+            layer,
+            true,
+            &gradient.delta,
+            (input, target),
+            &states,
+            epsilon,
+        );
+        errors.push(f64::max(weights_error, biases_error));
+    }
+    errors
+}
+
+/**
+ * Maior erro relativo entre as derivadas de um parâmetro (pesos ou viéses) da camada
+ */
+fn check_parameter(
+    network: &mut NeuralNetwork,
+    layer: usize,
+    biases: bool,
+    analytic: &Matrix,
+    (input, target): (&Matrix, &Matrix),
+    states: &[Vec<Matrix>],
+    epsilon: f64,
+) -> f64 {
+    let original = if biases {
+        network.borrow_layer(layer).biases().clone()
+    } else {
+        network.borrow_layer(layer).weights().clone()
+    };
+    assert!(analytic.rows() == original.rows() && analytic.cols() == original.cols());
+    let cost_with = |network: &mut NeuralNetwork, parameter: Matrix| {
+        if biases {
+            network.borrow_layer_mut(layer).fix_bias(parameter);
+        } else {
+            network.borrow_layer_mut(layer).fix_weights(parameter);
+        }
+        network.forward(input, true);
+        let cost = network.cost(target);
+        restore_states(network, states);
+        cost
+    };
+
+    let mut max_error: f64 = 0.0;
+    for i in 0..original.rows() {
+        for j in 0..original.cols() {
+            let mut perturbed = original.clone();
+            perturbed[i][j] += epsilon;
+            let cost_plus = cost_with(network, perturbed.clone());
+            perturbed[i][j] -= 2.0 * epsilon;
+            let cost_minus = cost_with(network, perturbed);
+            let numeric = (cost_plus - cost_minus) / (2.0 * epsilon);
+            max_error = max_error.max(relative_error(analytic[i][j], numeric));
+        }
+    }
+    cost_with(network, original);
+    max_error
+}
+
+fn layer_states(network: &NeuralNetwork) -> Vec<Vec<Matrix>> {
+    (1..=network.num_layers())
+        .map(|layer| network.borrow_layer(layer).state())
+        .collect()
+}
+
+fn restore_states(network: &mut NeuralNetwork, states: &[Vec<Matrix>]) {
+    for (i, state) in states.iter().enumerate() {
+        network.borrow_layer_mut(i + 1).fix_state(state.clone());
+    }
+}
+
+fn relative_error(analytic: f64, numeric: f64) -> f64 {
+    let scale = analytic.abs().max(numeric.abs()).max(GRADIENT_FLOOR);
+    (analytic - numeric).abs() / scale
+}
+
+/**
+ * Verificação de uma camada isolada, usada nos testes das camadas: com o custo auxiliar C = sum(w * y),
+ * cuja derivada ∂C/∂y é w (cost_weights), compara os gradientes da entrada, dos pesos e dos viéses
+ * de backpropagate com diferenças finitas centrais, com diferença absoluta máxima tolerance.
+ */
+#[cfg(test)]
+pub(crate) fn check_layer_gradients(
+    layer: &mut dyn NetworkLayer,
+    input: &Matrix,
+    cost_weights: &Matrix,
+    tolerance: f64,
+) {
+    let h = 1e-6;
+    layer.propagate(input);
+    let (gradient, input_gradient) = layer.backpropagate(cost_weights, input);
+
+    for idx in 0..input.num_elements() {
+        let (row, col) = (idx / input.cols(), idx % input.cols());
+        let mut perturbed = input.clone();
+        perturbed[(row, col)] += h;
+        let cost_plus = weighted_cost(layer, &perturbed, cost_weights);
+        perturbed[(row, col)] -= 2.0 * h;
+        let cost_minus = weighted_cost(layer, &perturbed, cost_weights);
+        let numeric = (cost_plus - cost_minus) / (2.0 * h);
+        assert!(
+            (numeric - input_gradient[row][col]).abs() < tolerance,
+            "dx[{}][{}]: analítico {} numérico {}",
+            row,
+            col,
+            input_gradient[row][col],
+            numeric
+        );
+    }
+
+    for biases in [false, true] {
+        let (original, analytic) = if biases {
+            (layer.biases().clone(), gradient.delta.clone())
+        } else {
+            let weights = layer.weights().clone();
+            let analytic = gradient.dense_weight(weights.rows());
+            (weights, analytic)
+        };
+        let cost_with = |layer: &mut dyn NetworkLayer, parameter: Matrix| {
+            if biases {
+                layer.fix_bias(parameter);
+            } else {
+                layer.fix_weights(parameter);
+            }
+            weighted_cost(layer, input, cost_weights)
+        };
+        for idx in 0..original.num_elements() {
+            let (row, col) = (idx / original.cols(), idx % original.cols());
+            let mut perturbed = original.clone();
+            perturbed[(row, col)] += h;
+            let cost_plus = cost_with(layer, perturbed.clone());
+            perturbed[(row, col)] -= 2.0 * h;
+            let cost_minus = cost_with(layer, perturbed);
+            let numeric = (cost_plus - cost_minus) / (2.0 * h);
+            assert!(
+                (numeric - analytic[row][col]).abs() < tolerance,
+                "{}[{}][{}]: analítico {} numérico {}",
+                if biases { "db" } else { "dw" },
+                row,
+                col,
+                analytic[row][col],
+                numeric
+            );
+        }
+        cost_with(layer, original);
+    }
+}
+
+//Custo auxiliar C = sum(w * y). O estado da camada (ex: estatísticas da BatchNorm) não é alterado.
+#[cfg(test)]
+fn weighted_cost(layer: &mut dyn NetworkLayer, input: &Matrix, cost_weights: &Matrix) -> f64 {
+    let state = layer.state();
+    layer.propagate(input);
+    layer.fix_state(state);
+    layer
+        .neurons()
+        .data()
+        .iter()
+        .zip(cost_weights.data())
+        .map(|(y, w)| y * w)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_attention::{MultiHeadAttention, PositionalEncoding, TokenMeanPool};
+    use crate::nn_autograd::AutogradLayer;
+    use crate::nn_embedding::Embedding;
+    use crate::nn_layer::{Layer, Sigmoid, Tanh};
+    use crate::nn_normalization::{BatchNorm, LayerNorm};
+    use crate::nn_recurrent::{Gru, Lstm, Rnn};
+    use crate::nn_regularization::Regularization;
+
+    const EPSILON: f64 = 1e-5;
+    const TOLERANCE: f64 = 1e-5;
+
+    fn assert_gradients(network: &mut NeuralNetwork, input: &Matrix, target: &Matrix) {
+        let errors = gradient_check(network, input, target, EPSILON);
+        println!("Erro relativo máximo por camada: {:?}", errors);
+        assert!(errors.len() == network.num_layers());
+        for (i, error) in errors.iter().enumerate() {
+            assert!(
+                *error < TOLERANCE,
+                "Camada {}: erro relativo {}",
+                i + 1,
+                error
+            );
+        }
+    }
+
+    fn batch_input() -> Matrix {
+        Matrix::from_vec(3, 3, vec![0.5, -0.3, 0.9, 0.1, 0.8, -0.7, -0.6, 0.2, 0.4])
+    }
+
+    fn batch_target() -> Matrix {
+        Matrix::from_vec(2, 3, vec![0.9, 0.1, 0.4, 0.2, 0.7, 0.5])
+    }
+
+    fn sequence_input() -> Matrix {
+        Matrix::from_vec(
+            3,
+            4,
+            vec![
+                0.5, -0.3, 0.9, 0.1, 0.8, -0.7, -0.6, 0.2, 0.4, 0.3, -0.1, 0.6,
+            ],
+        )
+    }
+
+    #[test]
+    fn test_dense() {
+        let mut network = NeuralNetwork::new(2, 0.1);
+        network.add_layer(Layer::new::<Sigmoid>(3, 4));
+        network.add_layer(Layer::new::<Tanh>(4, 2));
+        assert_gradients(&mut network, &batch_input(), &batch_target());
+    }
+
+    #[test]
+    fn test_normalization() {
+        let mut network = NeuralNetwork::new(5, 0.1);
+        network.add_layer(Layer::new::<Tanh>(3, 4));
+        network.add_layer(BatchNorm::new_1d(4));
+        network.add_layer(Layer::new::<Tanh>(4, 4));
+        network.add_layer(LayerNorm::new(4));
+        network.add_layer(Layer::new::<Sigmoid>(4, 2));
+        assert_gradients(&mut network, &batch_input(), &batch_target());
+    }
+
+    #[test]
+    fn test_recurrent() {
+        let target = Matrix::from_vec(2, 1, vec![0.8, 0.3]);
+        let mut network = NeuralNetwork::new(2, 0.1);
+        network.add_layer(Rnn::new::<Tanh>(3, 4));
+        network.add_layer(Layer::new::<Sigmoid>(4, 2));
+        assert_gradients(&mut network, &sequence_input(), &target);
+
+        let mut network = NeuralNetwork::new(2, 0.1);
+        network.add_layer(Lstm::new(3, 4));
+        network.add_layer(Layer::new::<Sigmoid>(4, 2));
+        assert_gradients(&mut network, &sequence_input(), &target);
+
+        let mut network = NeuralNetwork::new(2, 0.1);
+        network.add_layer(Gru::new(3, 4));
+        network.add_layer(Layer::new::<Sigmoid>(4, 2));
+        assert_gradients(&mut network, &sequence_input(), &target);
+    }
+
+    #[test]
+    fn test_attention() {
+        let input = Matrix::from_vec(
+            4,
+            3,
+            vec![
+                0.5, -0.3, 0.9, 0.1, 0.8, -0.7, -0.6, 0.2, 0.4, 0.3, -0.1, 0.6,
+            ],
+        );
+        let mut network = NeuralNetwork::new(4, 0.1);
+        network.add_layer(PositionalEncoding::new(4));
+        network.add_layer(MultiHeadAttention::new(4, 2));
+        network.add_layer(TokenMeanPool::new());
+        network.add_layer(Layer::new::<Sigmoid>(4, 2));
+        assert_gradients(
+            &mut network,
+            &input,
+            &Matrix::from_vec(2, 1, vec![0.8, 0.3]),
+        );
+    }
+
+    #[test]
+    fn test_embedding() {
+        let mut network = NeuralNetwork::new(3, 0.1);
+        network.add_layer(Embedding::new(5, 3));
+        network.add_layer(Gru::new(3, 4));
+        network.add_layer(Layer::new::<Sigmoid>(4, 2));
+        let input = Embedding::indices_to_matrix(&[1, 4, 1, 0]);
+        assert_gradients(
+            &mut network,
+            &input,
+            &Matrix::from_vec(2, 1, vec![0.8, 0.3]),
+        );
+    }
+
+    #[test]
+    fn test_autograd() {
+        let mut network = NeuralNetwork::new(2, 0.1);
+        network.add_layer(AutogradLayer::new(
+            Matrix::new_random_glorot(4, 3),
+            Matrix::from_vec(4, 1, vec![0.1, -0.2, 0.3, 0.0]),
+            |tape, input, weights, biases| {
+                let z = tape.matmul(weights, input);
+                let z = tape.add_column(z, biases);
+                tape.activation::<Tanh>(z)
+            },
+        ));
+        network.add_layer(Layer::new::<Sigmoid>(4, 2));
+        assert_gradients(&mut network, &batch_input(), &batch_target());
+    }
+
+    #[test]
+    fn test_regularization() {
+        let mut network = NeuralNetwork::new(3, 0.1);
+        network.add_layer(Layer::new::<Tanh>(3, 4));
+        network.add_layer(BatchNorm::new_1d(4));
+        network.add_layer(Layer::new::<Sigmoid>(4, 2));
+        network.set_regularization_all(Regularization::elastic_net(0.01, 0.05));
+        let mut with_biases = Regularization::l2(0.1);
+        with_biases.set_include_biases(true);
+        network.set_regularization(3, with_biases);
+        assert_gradients(&mut network, &batch_input(), &batch_target());
+    }
+
+    #[test]
+    fn test_keeps_layer_state() {
+        let mut network = NeuralNetwork::new(3, 0.1);
+        network.add_layer(Layer::new::<Tanh>(3, 4));
+        network.add_layer(BatchNorm::new_1d(4));
+        network.add_layer(Layer::new::<Sigmoid>(4, 2));
+        network.train(batch_input(), batch_target());
+        let state = network.borrow_layer(2).state();
+        let weights = network.borrow_layer(1).weights().clone();
+        assert_gradients(&mut network, &batch_input(), &batch_target());
+        //Estatísticas da BatchNorm (média e variância) e parâmetros inalterados
+        assert!(network.borrow_layer(2).state() == state);
+        assert!(*network.borrow_layer(1).weights() == weights);
+    }
+
+    #[test]
+    fn test_detects_wrong_gradient() {
+        //Camada com a derivada da ativação errada: o erro deve ser detectado
+        let mut network = NeuralNetwork::new(1, 0.1);
+        network.add_layer(Layer::new_with_function(
+            3,
+            2,
+            |val, _| val.tanh(),
+            |val| 1.0 - val.tanh(),
+        ));
+        let errors = gradient_check(&mut network, &batch_input(), &batch_target(), EPSILON);
+        println!("Erro relativo máximo por camada: {:?}", errors);
+        assert!(errors[0] > 1e-2);
+    }
+}
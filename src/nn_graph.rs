@@ -0,0 +1,502 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://arxiv.org/abs/1512.03385 (Deep Residual Learning for Image Recognition)
+use std::collections::VecDeque;
+
+use crate::nn_layer::{Gradient, NetworkLayer};
+use crate::nn_loss::{Loss, MeanSquaredError};
+use crate::nn_matrix::Matrix;
+
+/**
+ * Identificador de um nó do grafo, devolvido ao adicionar camadas e junções
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+/**
+ * Forma de combinar as saídas de vários nós
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum Merge {
+    Add,    //Soma elemento a elemento (conexões residuais). As entradas devem ter as mesmas dimensões
+    Concat, //Empilha as linhas (features) das entradas, na ordem dada. O número de colunas deve ser igual
+}
+
+enum Node {
+    Input,
+    Layer {
+        layer: Box<dyn NetworkLayer>,
+        input: NodeId,
+    },
+    Merge {
+        merge: Merge,
+        inputs: Vec<NodeId>,
+        output: Matrix,
+    },
+}
+
+/**
+ * Rede neural em forma de grafo acíclico: cada nó é uma camada (com uma entrada) ou uma junção
+ * de vários nós (soma ou concatenação), permitindo blocos residuais e modelos com vários ramos.
+ *
+ * Um nó só pode receber nós já existentes como entrada, de forma que a ordem de inserção já é
+ * uma ordem topológica: a propagação segue essa ordem e a retropropagação a ordem inversa,
+ * acumulando ∂C/∂a nos nós usados por mais de um caminho.
+ */
+pub struct GraphNetwork {
+    nodes: Vec<Node>,
+    input: Matrix,
+    output_node: NodeId,
+    learning_rate: f64,
+    loss: Box<dyn Loss>,
+}
+
+impl GraphNetwork {
+    pub fn new(learning_rate: f64) -> GraphNetwork {
+        GraphNetwork {
+            nodes: vec![Node::Input],
+            input: Matrix::new(0, 0),
+            output_node: NodeId(0),
+            learning_rate,
+            loss: Box::new(MeanSquaredError {}),
+        }
+    }
+
+    /**
+     * Nó que representa a entrada da rede
+     */
+    pub fn input_node(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /**
+     * Adiciona uma camada alimentada pela saída do nó informado.
+     * O nó adicionado por último é a saída da rede, salvo se set_output for usado.
+     */
+    pub fn add_layer(&mut self, layer: impl NetworkLayer + 'static, input: NodeId) -> NodeId {
+        assert!(input.0 < self.nodes.len());
+        self.push_node(Node::Layer {
+            layer: Box::new(layer),
+            input,
+        })
+    }
+
+    pub fn add_merge(&mut self, merge: Merge, inputs: &[NodeId]) -> NodeId {
+        assert!(!inputs.is_empty());
+        assert!(inputs.iter().all(|id| id.0 < self.nodes.len()));
+        self.push_node(Node::Merge {
+            merge,
+            inputs: inputs.to_vec(),
+            output: Matrix::new(0, 0),
+        })
+    }
+
+    fn push_node(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        self.output_node = NodeId(self.nodes.len() - 1);
+        self.output_node
+    }
+
+    pub fn set_output(&mut self, node: NodeId) {
+        assert!(node.0 < self.nodes.len());
+        self.output_node = node;
+    }
+
+    /**
+     * Função de custo usada na retropropagação (MSE por padrão)
+     */
+    pub fn set_loss(&mut self, loss: impl Loss + 'static) {
+        self.loss = Box::new(loss);
+    }
+
+    pub fn num_layers(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| matches!(node, Node::Layer { .. }))
+            .count()
+    }
+
+    pub fn borrow_layer(&self, node: NodeId) -> &dyn NetworkLayer {
+        match &self.nodes[node.0] {
+            Node::Layer { layer, .. } => layer.as_ref(),
+            _ => panic!("Nó {} não é uma camada", node.0),
+        }
+    }
+
+    /**
+     * Treinamento com uma amostra (ou um lote, uma amostra por coluna), como em NeuralNetwork::train
+     */
+    pub fn train(&mut self, input: Matrix, expected_output: Matrix) {
+        self.forward(&input, true);
+        let mut gradients = self.generate_gradients(expected_output);
+        self.adjust_parameters(&mut gradients);
+    }
+
+    pub fn train_batch(
+        &mut self,
+        input: Matrix,
+        expected_output: Matrix,
+        cur_gradients: &mut VecDeque<Gradient>,
+    ) {
+        self.forward(&input, true);
+        let gradients = self.generate_gradients(expected_output);
+
+        if cur_gradients.is_empty() {
+            cur_gradients.clone_from(&gradients);
+        } else {
+            cur_gradients
+                .iter_mut()
+                .zip(gradients)
+                .for_each(|(cur_gradient, gradient)| cur_gradient.accumulate(&gradient));
+        }
+    }
+
+    /**
+     * Os gradientes seguem a ordem de inserção das camadas (as junções não têm parâmetros)
+     */
+    pub fn adjust_parameters(&mut self, gradients: &mut VecDeque<Gradient>) {
+        assert!(gradients.len() == self.num_layers());
+        let layers = self.nodes.iter_mut().filter_map(|node| match node {
+            Node::Layer { layer, .. } => Some(layer),
+            _ => None,
+        });
+        for (layer, gradient) in layers.zip(gradients) {
+            layer.adjust_parameters(gradient, self.learning_rate);
+        }
+    }
+
+    pub fn classify(&mut self, input: &Matrix) -> &Matrix {
+        self.forward(input, false)
+    }
+
+    fn forward(&mut self, input: &Matrix, training: bool) -> &Matrix {
+        assert!(self.output_node.0 > 0);
+        self.input = input.clone();
+        for i in 1..self.nodes.len() {
+            //Nós [0..i) já propagados e [i..len) restantes (borrow checker)
+            let (done, remaining) = self.nodes.split_at_mut(i);
+            match &mut remaining[0] {
+                Node::Input => unreachable!(),
+                Node::Layer { layer, input } => {
+                    layer.set_training(training);
+                    layer.propagate(node_output(done, &self.input, *input));
+                }
+                Node::Merge {
+                    merge,
+                    inputs,
+                    output,
+                } => {
+                    let inputs: Vec<&Matrix> = inputs
+                        .iter()
+                        .map(|&id| node_output(done, &self.input, id))
+                        .collect();
+                    *output = merge.apply(&inputs);
+                }
+            }
+        }
+        node_output(&self.nodes, &self.input, self.output_node)
+    }
+
+    /**
+     * Retropropagação em ordem topológica inversa. O ∂C/∂a de cada nó é a soma dos gradientes
+     * vindos de todos os nós que o usam. Camadas que não alcançam a saída recebem gradiente nulo.
+     */
+    pub fn generate_gradients(&mut self, expected_output: Matrix) -> VecDeque<Gradient> {
+        let output = node_output(&self.nodes, &self.input, self.output_node);
+        assert!(output.rows() == expected_output.rows() && output.cols() == expected_output.cols());
+
+        //∂C/∂a do nó de saída
+        let output_gradient = self.loss.gradient(output, &expected_output);
+
+        let mut node_gradients: Vec<Option<Matrix>> = (0..self.nodes.len()).map(|_| None).collect();
+        node_gradients[self.output_node.0] = Some(output_gradient);
+        let mut gradients: VecDeque<Gradient> = VecDeque::with_capacity(self.num_layers());
+        for i in (1..self.nodes.len()).rev() {
+            let node_gradient = node_gradients[i].take();
+            let (done, remaining) = self.nodes.split_at_mut(i);
+            match &mut remaining[0] {
+                Node::Input => unreachable!(),
+                Node::Layer { layer, input } => match node_gradient {
+                    Some(node_gradient) => {
+                        let (gradient, input_gradient) = layer
+                            .backpropagate(&node_gradient, node_output(done, &self.input, *input));
+                        gradients.push_front(gradient);
+                        accumulate(&mut node_gradients[input.0], input_gradient);
+                    }
+                    None => gradients.push_front(layer.zero_gradient()),
+                },
+                Node::Merge { merge, inputs, .. } => {
+                    let Some(node_gradient) = node_gradient else {
+                        continue;
+                    };
+                    match merge {
+                        Merge::Add => {
+                            for id in inputs.iter() {
+                                accumulate(&mut node_gradients[id.0], node_gradient.clone());
+                            }
+                        }
+                        Merge::Concat => {
+                            let mut first_row = 0;
+                            for id in inputs.iter() {
+                                let rows = node_output(done, &self.input, *id).rows();
+                                accumulate(
+                                    &mut node_gradients[id.0],
+                                    node_gradient.rows_range(first_row, rows),
+                                );
+                                first_row += rows;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        gradients
+    }
+}
+
+impl Merge {
+    fn apply(&self, inputs: &[&Matrix]) -> Matrix {
+        match self {
+            Merge::Add => {
+                let mut output = inputs[0].clone();
+                for input in &inputs[1..] {
+                    assert!(input.rows() == output.rows() && input.cols() == output.cols());
+                    output += *input;
+                }
+                output
+            }
+            Merge::Concat => {
+                let cols = inputs[0].cols();
+                assert!(inputs.iter().all(|input| input.cols() == cols));
+                let rows = inputs.iter().map(|input| input.rows()).sum();
+                let mut output = Matrix::new(rows, cols);
+                let mut first_row = 0;
+                for input in inputs {
+                    output.set_rows(first_row, input);
+                    first_row += input.rows();
+                }
+                output
+            }
+        }
+    }
+}
+
+fn node_output<'a>(nodes: &'a [Node], input: &'a Matrix, id: NodeId) -> &'a Matrix {
+    match &nodes[id.0] {
+        Node::Input => input,
+        Node::Layer { layer, .. } => layer.neurons(),
+        Node::Merge { output, .. } => output,
+    }
+}
+
+fn accumulate(node_gradient: &mut Option<Matrix>, gradient: Matrix) {
+    match node_gradient {
+        Some(total) => *total += &gradient,
+        None => *node_gradient = Some(gradient),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_embedding::Embedding;
+    use crate::nn_layer::{Identity, Layer, Relu, Sigmoid, Tanh};
+    use crate::nn_network::NeuralNetwork;
+
+    #[test]
+    fn test_residual_add() {
+        let mut network = GraphNetwork::new(0.1);
+        let input = network.input_node();
+        let mut layer = Layer::new::<Identity>(2, 2);
+        layer.fix_weights(Matrix::from_vec(2, 2, vec![1.0, 2.0, 0.0, 1.0]));
+        layer.fix_bias(Matrix::from_vec(2, 1, vec![0.5, -0.5]));
+        let hidden = network.add_layer(layer, input);
+        network.add_merge(Merge::Add, &[hidden, input]);
+
+        let output = network.classify(&Matrix::from_vec(2, 1, vec![1.0, 3.0]));
+        print!("Output: {}", output);
+        //(W·x + b) + x
+        assert!(*output == Matrix::from_vec(2, 1, vec![8.5, 5.5]));
+    }
+
+    #[test]
+    fn test_concat() {
+        let mut network = GraphNetwork::new(0.1);
+        let input = network.input_node();
+        let hidden = network.add_layer(Layer::new::<Relu>(3, 2), input);
+        let concat = network.add_merge(Merge::Concat, &[input, hidden]);
+        let input_matrix = Matrix::from_vec(3, 2, vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+        network.classify(&input_matrix);
+        let hidden_output = network.borrow_layer(hidden).neurons().clone();
+        let output = network.classify(&input_matrix);
+        assert!(output.rows() == 5 && output.cols() == 2);
+        assert!(output.rows_range(0, 3) == input_matrix);
+        assert!(output.rows_range(3, 2) == hidden_output);
+        assert!(network.output_node == concat);
+    }
+
+    #[test]
+    fn test_chain_matches_neural_network() {
+        let mut chain = NeuralNetwork::new(2, 0.1);
+        chain.add_layer(Layer::new::<Sigmoid>(3, 4));
+        chain.add_layer(Layer::new::<Tanh>(4, 2));
+        let mut graph = GraphNetwork::new(0.1);
+        let hidden = graph.add_layer(Layer::new::<Sigmoid>(3, 4), graph.input_node());
+        let output = graph.add_layer(Layer::new::<Tanh>(4, 2), hidden);
+        for (i, id) in [hidden, output].into_iter().enumerate() {
+            set_weights(&mut graph, id, chain.borrow_layer(i + 1).weights().clone());
+            match &mut graph.nodes[id.0] {
+                Node::Layer { layer, .. } => {
+                    layer.fix_bias(chain.borrow_layer(i + 1).biases().clone())
+                }
+                _ => unreachable!(),
+            }
+        }
+        let input = Matrix::from_vec(3, 1, vec![0.3, -0.2, 0.8]);
+        let expected = Matrix::from_vec(2, 1, vec![0.5, -0.5]);
+
+        chain.classify(&input);
+        let chain_gradients = chain.generate_gradients(input.clone(), expected.clone());
+        graph.classify(&input);
+        let graph_gradients = graph.generate_gradients(expected);
+        for (a, b) in chain_gradients.iter().zip(graph_gradients.iter()) {
+            assert!(a.weight == b.weight);
+            assert!(a.delta == b.delta);
+        }
+    }
+
+    #[test]
+    fn test_gradients() {
+        //Bloco residual seguido de concatenação com um ramo paralelo
+        let mut network = GraphNetwork::new(0.1);
+        let input = network.input_node();
+        let hidden1 = network.add_layer(Layer::new::<Tanh>(3, 3), input);
+        let hidden2 = network.add_layer(Layer::new::<Sigmoid>(3, 3), hidden1);
+        let residual = network.add_merge(Merge::Add, &[hidden2, input]);
+        let branch = network.add_layer(Layer::new::<Tanh>(3, 2), input);
+        let concat = network.add_merge(Merge::Concat, &[residual, branch]);
+        network.add_layer(Layer::new::<Tanh>(5, 2), concat);
+
+        let input = Matrix::from_vec(3, 2, vec![0.5, -0.3, 0.1, 0.8, -0.6, 0.2]);
+        let expected = Matrix::from_vec(2, 2, vec![0.2, -0.4, 0.7, 0.1]);
+        let cost = |network: &mut GraphNetwork| -> f64 {
+            let output = network.classify(&input);
+            let mut total = 0.0;
+            for i in 0..output.rows() {
+                for j in 0..output.cols() {
+                    total += (output[i][j] - expected[i][j]).powi(2);
+                }
+            }
+            total / output.cols() as f64
+        };
+
+        network.classify(&input);
+        let gradients = network.generate_gradients(expected.clone());
+        let layer_ids: Vec<NodeId> = (1..network.nodes.len())
+            .map(NodeId)
+            .filter(|id| matches!(network.nodes[id.0], Node::Layer { .. }))
+            .collect();
+        let epsilon = 1e-6;
+        for (id, gradient) in layer_ids.iter().zip(gradients.iter()) {
+            let weights = network.borrow_layer(*id).weights().clone();
+            for i in 0..weights.rows() {
+                for j in 0..weights.cols() {
+                    let mut perturbed = weights.clone();
+                    perturbed[i][j] += epsilon;
+                    set_weights(&mut network, *id, perturbed.clone());
+                    let cost_plus = cost(&mut network);
+                    perturbed[i][j] -= 2.0 * epsilon;
+                    set_weights(&mut network, *id, perturbed);
+                    let cost_minus = cost(&mut network);
+                    set_weights(&mut network, *id, weights.clone());
+                    let numeric = (cost_plus - cost_minus) / (2.0 * epsilon);
+                    assert!(
+                        (numeric - gradient.weight[i][j]).abs() < 1e-6,
+                        "Nó {} peso ({}, {}): numérico {} analítico {}",
+                        id.0,
+                        i,
+                        j,
+                        numeric,
+                        gradient.weight[i][j]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dangling_embedding() {
+        //O segundo embedding não alcança a saída: recebe um gradiente esparso vazio
+        let mut network = GraphNetwork::new(0.1);
+        let input = network.input_node();
+        let embedding = network.add_layer(Embedding::new(4, 2), input);
+        let output = network.add_layer(Layer::new::<Sigmoid>(2, 1), embedding);
+        let dangling = network.add_layer(Embedding::new(4, 3), input);
+        network.set_output(output);
+
+        let tokens = Embedding::indices_to_matrix(&[1, 3]);
+        let expected = Matrix::from_vec(1, 2, vec![1.0, 0.0]);
+        let table = network.borrow_layer(dangling).weights().clone();
+        network.train(tokens.clone(), expected.clone());
+
+        let mut gradients = VecDeque::new();
+        network.train_batch(tokens.clone(), expected.clone(), &mut gradients);
+        network.train_batch(tokens, expected, &mut gradients);
+        assert!(gradients[2].rows == Some(Vec::new()));
+        network.adjust_parameters(&mut gradients);
+        assert!(*network.borrow_layer(dangling).weights() == table);
+    }
+
+    fn set_weights(network: &mut GraphNetwork, id: NodeId, weights: Matrix) {
+        match &mut network.nodes[id.0] {
+            Node::Layer { layer, .. } => layer.fix_weights(weights),
+            _ => panic!("Nó não é uma camada"),
+        }
+    }
+
+    #[test]
+    fn test_train_residual_network() {
+        let mut network = GraphNetwork::new(0.1);
+        let mut block_input = network.add_layer(Layer::new::<Tanh>(4, 6), network.input_node());
+        for _ in 0..4 {
+            let hidden = network.add_layer(Layer::new::<Relu>(6, 6), block_input);
+            let hidden = network.add_layer(Layer::new::<Tanh>(6, 6), hidden);
+            block_input = network.add_merge(Merge::Add, &[hidden, block_input]);
+        }
+        network.add_layer(Layer::new::<Sigmoid>(6, 2), block_input);
+        assert!(network.num_layers() == 10);
+
+        let input = Matrix::from_vec(4, 2, vec![0.9, 0.1, 0.2, 0.8, 0.7, 0.3, 0.1, 0.6]);
+        let expected = Matrix::from_vec(2, 2, vec![1.0, 0.0, 0.0, 1.0]);
+        let squared_error = |output: &Matrix| -> f64 {
+            output
+                .data()
+                .iter()
+                .zip(expected.data())
+                .map(|(a, y)| (a - y).powi(2))
+                .sum()
+        };
+        let initial_error = squared_error(network.classify(&input));
+        for _ in 0..100 {
+            network.train(input.clone(), expected.clone());
+        }
+        let final_error = squared_error(network.classify(&input));
+        println!("Error: {} -> {}", initial_error, final_error);
+        assert!(final_error < initial_error);
+    }
+}
@@ -0,0 +1,940 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://www.rfc-editor.org/rfc/rfc1951 (DEFLATE)
+//https://www.rfc-editor.org/rfc/rfc1952 (gzip)
+//https://github.com/madler/zlib/blob/develop/contrib/puff/puff.c
+use std::io::{Read, Seek, SeekFrom};
+
+pub const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const WINDOW_SIZE: usize = 32 * 1024;
+const INPUT_BUFFER_SIZE: usize = 64 * 1024;
+const MAX_BITS: usize = 15;
+//Códigos de até FAST_BITS bits são decodificados com uma consulta à tabela
+const FAST_BITS: usize = 10;
+
+//Comprimentos (símbolos 257..285) e distâncias: valor base e bits extras
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+//Ordem dos comprimentos do código dos comprimentos nos blocos dinâmicos
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+/**
+ * CRC-32 do gzip, acumulado: crc32(crc32(0, a), b) == crc32(0, a + b)
+ */
+pub fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut c = !crc;
+    for &byte in data {
+        c = CRC_TABLE[((c ^ byte as u32) & 0xFF) as usize] ^ (c >> 8);
+    }
+    !c
+}
+
+fn invalid_data(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
+
+/**
+ * Leitura de bits na ordem do DEFLATE (bit menos significativo de cada byte primeiro).
+ * A entrada é lida em blocos de INPUT_BUFFER_SIZE bytes, e até 64 bits ficam disponíveis para consulta.
+ */
+struct BitReader<R: Read> {
+    input: R,
+    data: Vec<u8>,
+    start: usize, //Próximo byte de data ainda não passado para buffer
+    end: usize,
+    buffer: u64,
+    count: u32,
+}
+
+impl<R: Read> BitReader<R> {
+    fn new(input: R) -> BitReader<R> {
+        BitReader {
+            input,
+            data: vec![0; INPUT_BUFFER_SIZE],
+            start: 0,
+            end: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    /**
+     * Lê o próximo bloco da entrada; false no fim do arquivo
+     */
+    fn refill(&mut self) -> std::io::Result<bool> {
+        loop {
+            match self.input.read(&mut self.data) {
+                Ok(count) => {
+                    self.start = 0;
+                    self.end = count;
+                    return Ok(count > 0);
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /**
+     * Garante n bits em buffer, ou menos se a entrada terminar antes
+     */
+    fn fill(&mut self, n: u32) -> std::io::Result<()> {
+        while self.count < n {
+            if self.start == self.end && !self.refill()? {
+                return Ok(());
+            }
+            self.buffer |= (self.data[self.start] as u64) << self.count;
+            self.start += 1;
+            self.count += 8;
+        }
+        Ok(())
+    }
+
+    /**
+     * Próximos n bits sem consumi-los, e quantos deles existem de fato na entrada
+     */
+    fn peek(&mut self, n: u32) -> std::io::Result<(u32, u32)> {
+        self.fill(n)?;
+        Ok(((self.buffer & ((1u64 << n) - 1)) as u32, self.count.min(n)))
+    }
+
+    fn consume(&mut self, n: u32) {
+        self.buffer >>= n;
+        self.count -= n;
+    }
+
+    fn bits(&mut self, n: u32) -> std::io::Result<u32> {
+        let (value, available) = self.peek(n)?;
+        if available < n {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Fim inesperado do fluxo DEFLATE",
+            ));
+        }
+        self.consume(n);
+        Ok(value)
+    }
+
+    /**
+     * Descarta os bits restantes do byte atual
+     */
+    fn align(&mut self) {
+        self.consume(self.count % 8);
+    }
+
+    /**
+     * Preenche output com os próximos bytes (a leitura precisa estar alinhada em bytes)
+     */
+    fn read_bytes(&mut self, output: &mut [u8]) -> std::io::Result<()> {
+        let mut filled = 0;
+        while filled < output.len() && self.count >= 8 {
+            output[filled] = self.buffer as u8;
+            self.consume(8);
+            filled += 1;
+        }
+        while filled < output.len() {
+            if self.start == self.end && !self.refill()? {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "Fim inesperado do fluxo DEFLATE",
+                ));
+            }
+            let count = (self.end - self.start).min(output.len() - filled);
+            output[filled..filled + count]
+                .copy_from_slice(&self.data[self.start..self.start + count]);
+            self.start += count;
+            filled += count;
+        }
+        Ok(())
+    }
+
+    /**
+     * Descarta tudo o que foi lido, depois de reposicionar a entrada
+     */
+    fn clear(&mut self) {
+        self.start = 0;
+        self.end = 0;
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    fn byte(&mut self) -> std::io::Result<u8> {
+        Ok(self.bits(8)? as u8)
+    }
+
+    fn u16_le(&mut self) -> std::io::Result<u16> {
+        Ok(self.bits(16)? as u16)
+    }
+
+    fn u32_le(&mut self) -> std::io::Result<u32> {
+        Ok(self.bits(16)? | (self.bits(16)? << 16))
+    }
+}
+
+/**
+ * Código de Huffman canônico: quantidade de códigos por comprimento e símbolos em ordem de código.
+ * A tabela fast é indexada pelos próximos FAST_BITS bits da entrada e guarda (comprimento << 9 | símbolo)
+ * dos códigos curtos (0 para os mais longos, decodificados bit a bit).
+ */
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+    fast: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> std::io::Result<Huffman> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        //Códigos a mais do que cabem em algum comprimento tornam o código inválido
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err(invalid_data("Código de Huffman inválido"));
+            }
+        }
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for length in 1..=MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        counts[0] = 0;
+
+        //Os códigos são lidos do bit mais significativo para o menos, então o índice é o código invertido
+        let mut fast = vec![0u16; 1 << FAST_BITS];
+        let mut code: u32 = 0;
+        let mut index = 0;
+        for (length, &count) in counts.iter().enumerate().take(FAST_BITS + 1).skip(1) {
+            for _ in 0..count {
+                let entry = (length << 9) as u16 | symbols[index];
+                let mut position = (code.reverse_bits() >> (32 - length)) as usize;
+                while position < fast.len() {
+                    fast[position] = entry;
+                    position += 1 << length;
+                }
+                code += 1;
+                index += 1;
+            }
+            code <<= 1;
+        }
+        Ok(Huffman {
+            counts,
+            symbols,
+            fast,
+        })
+    }
+
+    fn decode<R: Read>(&self, input: &mut BitReader<R>) -> std::io::Result<u16> {
+        let (bits, available) = input.peek(FAST_BITS as u32)?;
+        let entry = self.fast[bits as usize];
+        let length = (entry >> 9) as u32;
+        //Perto do fim da entrada, os bits ausentes podem levar a um código mais longo que os disponíveis
+        if entry != 0 && length <= available {
+            input.consume(length);
+            return Ok(entry & 0x1FF);
+        }
+        self.decode_bits(input)
+    }
+
+    fn decode_bits<R: Read>(&self, input: &mut BitReader<R>) -> std::io::Result<u16> {
+        let mut code: i32 = 0; //Bits lidos até agora
+        let mut first: i32 = 0; //Primeiro código do comprimento atual
+        let mut index: i32 = 0; //Índice do primeiro símbolo do comprimento atual
+        for length in 1..=MAX_BITS {
+            code |= input.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid_data("Código de Huffman inexistente"))
+    }
+}
+
+enum Block {
+    Header, //Próximo passo é ler o cabeçalho de um bloco
+    Stored {
+        remaining: usize,
+    },
+    Compressed {
+        literals: Huffman,
+        distances: Huffman,
+    },
+    Done,
+}
+
+/**
+ * Descompressão de um fluxo DEFLATE sob demanda: cada chamada de read decodifica apenas
+ * o necessário para preencher o buffer, mantendo somente a janela de 32KB das distâncias.
+ * Depois de preencher o buffer, o próximo símbolo já é decodificado, de forma que is_done
+ * fica verdadeiro na mesma leitura que entrega o último byte.
+ */
+pub struct Inflate<R: Read> {
+    input: BitReader<R>,
+    block: Block,
+    last_block: bool,
+    window: Vec<u8>,
+    written: usize,       //Total de bytes produzidos
+    literal: Option<u8>,  //Literal decodificado que não coube no buffer da última leitura
+    copy_length: usize,   //Bytes pendentes de uma cópia (comprimento, distância)
+    copy_distance: usize, //que não couberam no buffer da última leitura
+}
+
+impl<R: Read> Inflate<R> {
+    pub fn new(input: R) -> Inflate<R> {
+        Inflate::with_reader(BitReader::new(input))
+    }
+
+    fn with_reader(input: BitReader<R>) -> Inflate<R> {
+        Inflate {
+            input,
+            block: Block::Header,
+            last_block: false,
+            window: vec![0; WINDOW_SIZE],
+            written: 0,
+            literal: None,
+            copy_length: 0,
+            copy_distance: 0,
+        }
+    }
+
+    /**
+     * Recomeça a decodificação a partir da posição atual da entrada
+     */
+    fn reset(&mut self) {
+        self.block = Block::Header;
+        self.last_block = false;
+        self.written = 0;
+        self.literal = None;
+        self.copy_length = 0;
+        self.copy_distance = 0;
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.block, Block::Done)
+    }
+
+    fn emit(&mut self, byte: u8, output: &mut [u8], produced: &mut usize) {
+        self.window[self.written % WINDOW_SIZE] = byte;
+        self.written += 1;
+        output[*produced] = byte;
+        *produced += 1;
+    }
+
+    /**
+     * Copia bytes de um bloco sem compressão diretamente da entrada para output
+     */
+    fn emit_stored(&mut self, output: &mut [u8]) -> std::io::Result<()> {
+        self.input.read_bytes(output)?;
+        for &byte in output.iter() {
+            self.window[self.written % WINDOW_SIZE] = byte;
+            self.written += 1;
+        }
+        Ok(())
+    }
+
+    fn read_block_header(&mut self) -> std::io::Result<()> {
+        if self.last_block {
+            self.block = Block::Done;
+            return Ok(());
+        }
+        self.last_block = self.input.bits(1)? == 1;
+        self.block = match self.input.bits(2)? {
+            0 => {
+                self.input.align();
+                let length = self.input.u16_le()?;
+                if self.input.u16_le()? != !length {
+                    return Err(invalid_data("Bloco sem compressão com tamanho inválido"));
+                }
+                Block::Stored {
+                    remaining: length as usize,
+                }
+            }
+            1 => fixed_block()?,
+            2 => self.dynamic_block()?,
+            _ => return Err(invalid_data("Tipo de bloco DEFLATE inválido")),
+        };
+        Ok(())
+    }
+
+    fn dynamic_block(&mut self) -> std::io::Result<Block> {
+        let num_literals = self.input.bits(5)? as usize + 257;
+        let num_distances = self.input.bits(5)? as usize + 1;
+        let num_code_lengths = self.input.bits(4)? as usize + 4;
+        if num_literals > 286 || num_distances > 30 {
+            return Err(invalid_data("Quantidade de códigos inválida"));
+        }
+        let mut code_lengths = [0u8; 19];
+        for &position in CODE_LENGTH_ORDER.iter().take(num_code_lengths) {
+            code_lengths[position] = self.input.bits(3)? as u8;
+        }
+        let code_length_code = Huffman::new(&code_lengths)?;
+
+        //Comprimentos dos códigos de literais e distâncias, com repetições codificadas
+        let mut lengths = vec![0u8; num_literals + num_distances];
+        let mut index = 0;
+        while index < lengths.len() {
+            let symbol = code_length_code.decode(&mut self.input)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    if index == 0 {
+                        return Err(invalid_data("Repetição sem comprimento anterior"));
+                    }
+                    (lengths[index - 1], 3 + self.input.bits(2)? as usize)
+                }
+                17 => (0, 3 + self.input.bits(3)? as usize),
+                _ => (0, 11 + self.input.bits(7)? as usize),
+            };
+            if index + repeat > lengths.len() {
+                return Err(invalid_data("Repetição além da quantidade de códigos"));
+            }
+            lengths[index..index + repeat].fill(value);
+            index += repeat;
+        }
+        if lengths[256] == 0 {
+            return Err(invalid_data("Bloco sem código de fim"));
+        }
+        Ok(Block::Compressed {
+            literals: Huffman::new(&lengths[..num_literals])?,
+            distances: Huffman::new(&lengths[num_literals..])?,
+        })
+    }
+
+    /**
+     * Continua uma cópia pendente (comprimento, distância) da janela
+     */
+    fn copy(&mut self, output: &mut [u8], produced: &mut usize) {
+        while self.copy_length > 0 && *produced < output.len() {
+            let byte = self.window[(self.written - self.copy_distance) % WINDOW_SIZE];
+            self.emit(byte, output, produced);
+            self.copy_length -= 1;
+        }
+    }
+}
+
+fn fixed_block() -> std::io::Result<Block> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok(Block::Compressed {
+        literals: Huffman::new(&lengths)?,
+        distances: Huffman::new(&[5; 30])?,
+    })
+}
+
+impl<R: Read> Read for Inflate<R> {
+    fn read(&mut self, output: &mut [u8]) -> std::io::Result<usize> {
+        let mut produced = 0;
+        loop {
+            if let Some(byte) = self.literal {
+                if produced == output.len() {
+                    break;
+                }
+                self.emit(byte, output, &mut produced);
+                self.literal = None;
+                continue;
+            }
+            if self.copy_length > 0 {
+                if produced == output.len() {
+                    break;
+                }
+                self.copy(output, &mut produced);
+                continue;
+            }
+            //O bloco é retirado temporariamente para que self possa ser emprestado como mutável
+            match std::mem::replace(&mut self.block, Block::Done) {
+                Block::Header => self.read_block_header()?,
+                Block::Done => break,
+                Block::Stored { remaining } => {
+                    if remaining == 0 {
+                        self.block = Block::Header;
+                    } else if produced == output.len() {
+                        self.block = Block::Stored { remaining };
+                        break;
+                    } else {
+                        let count = remaining.min(output.len() - produced);
+                        self.emit_stored(&mut output[produced..produced + count])?;
+                        produced += count;
+                        self.block = Block::Stored {
+                            remaining: remaining - count,
+                        };
+                    }
+                }
+                Block::Compressed {
+                    literals,
+                    distances,
+                } => {
+                    let symbol = literals.decode(&mut self.input)? as usize;
+                    if symbol < 256 {
+                        self.literal = Some(symbol as u8);
+                    } else if symbol > 256 {
+                        let symbol = symbol - 257;
+                        if symbol >= LENGTH_BASE.len() {
+                            return Err(invalid_data("Símbolo de comprimento inválido"));
+                        }
+                        let length = LENGTH_BASE[symbol] as usize
+                            + self.input.bits(LENGTH_EXTRA[symbol] as u32)? as usize;
+                        let symbol = distances.decode(&mut self.input)? as usize;
+                        if symbol >= DISTANCE_BASE.len() {
+                            return Err(invalid_data("Símbolo de distância inválido"));
+                        }
+                        let distance = DISTANCE_BASE[symbol] as usize
+                            + self.input.bits(DISTANCE_EXTRA[symbol] as u32)? as usize;
+                        if distance > self.written.min(WINDOW_SIZE) {
+                            return Err(invalid_data("Distância além dos dados produzidos"));
+                        }
+                        self.copy_length = length;
+                        self.copy_distance = distance;
+                    }
+                    //Símbolo 256: fim do bloco
+                    if symbol != 256 {
+                        self.block = Block::Compressed {
+                            literals,
+                            distances,
+                        };
+                    } else {
+                        self.block = Block::Header;
+                    }
+                }
+            }
+        }
+        Ok(produced)
+    }
+}
+
+/**
+ * Descompressão de arquivos gzip (um membro), sob demanda.
+ * O CRC-32 e o tamanho do conteúdo são verificados na leitura que entrega o último byte,
+ * então mesmo quem lê exatamente o tamanho do conteúdo (read_exact) recebe o erro.
+ */
+pub struct GzDecoder<R: Read> {
+    inflate: Inflate<R>,
+    crc: u32,
+    position: u64, //Bytes descomprimidos já entregues
+    finished: bool,
+}
+
+impl<R: Read> GzDecoder<R> {
+    pub fn new(input: R) -> std::io::Result<GzDecoder<R>> {
+        let mut input = BitReader::new(input);
+        read_gzip_header(&mut input)?;
+        Ok(GzDecoder {
+            inflate: Inflate::with_reader(input),
+            crc: 0,
+            position: 0,
+            finished: false,
+        })
+    }
+
+    fn read_trailer(&mut self) -> std::io::Result<()> {
+        let input = &mut self.inflate.input;
+        input.align();
+        let crc = input.u32_le()?;
+        let size = input.u32_le()?;
+        if crc != self.crc {
+            return Err(invalid_data("CRC do arquivo gzip não confere"));
+        }
+        if size != self.position as u32 {
+            return Err(invalid_data("Tamanho do arquivo gzip não confere"));
+        }
+        self.finished = true;
+        Ok(())
+    }
+}
+
+fn read_gzip_header<R: Read>(input: &mut BitReader<R>) -> std::io::Result<()> {
+    if [input.byte()?, input.byte()?] != GZIP_MAGIC {
+        return Err(invalid_data("Arquivo não está no formato gzip"));
+    }
+    if input.byte()? != 8 {
+        return Err(invalid_data("Método de compressão gzip não suportado"));
+    }
+    let flags = input.byte()?;
+    //Data de modificação (4), flags extras (1) e sistema operacional (1)
+    for _ in 0..6 {
+        input.byte()?;
+    }
+    if flags & 0x04 != 0 {
+        let extra_length = input.u16_le()?;
+        for _ in 0..extra_length {
+            input.byte()?;
+        }
+    }
+    //Nome do arquivo e comentário, terminados em 0
+    for flag in [0x08, 0x10] {
+        if flags & flag != 0 {
+            while input.byte()? != 0 {}
+        }
+    }
+    if flags & 0x02 != 0 {
+        input.u16_le()?;
+    }
+    Ok(())
+}
+
+impl<R: Read> Read for GzDecoder<R> {
+    fn read(&mut self, output: &mut [u8]) -> std::io::Result<usize> {
+        if self.finished || output.is_empty() {
+            return Ok(0);
+        }
+        let produced = self.inflate.read(output)?;
+        self.crc = crc32(self.crc, &output[..produced]);
+        self.position += produced as u64;
+        if self.inflate.is_done() {
+            self.read_trailer()?;
+        }
+        Ok(produced)
+    }
+}
+
+/**
+ * Posicionamento no conteúdo descomprimido. Avançar decodifica e descarta os bytes intermediários;
+ * voltar recomeça a descompressão do início do arquivo. Leituras sequenciais não têm custo extra.
+ */
+impl<R: Read + Seek> GzDecoder<R> {
+    fn rewind(&mut self) -> std::io::Result<()> {
+        let input = &mut self.inflate.input;
+        input.input.seek(SeekFrom::Start(0))?;
+        input.clear();
+        read_gzip_header(input)?;
+        self.inflate.reset();
+        self.crc = 0;
+        self.position = 0;
+        self.finished = false;
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Seek for GzDecoder<R> {
+    fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+        let target = match position {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(offset) => self
+                .position
+                .checked_add_signed(offset)
+                .ok_or_else(|| invalid_data("Posição negativa"))?,
+            SeekFrom::End(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "Tamanho do conteúdo gzip desconhecido",
+                ));
+            }
+        };
+        if target < self.position {
+            self.rewind()?;
+        }
+        let mut skip_buffer = [0u8; 4096];
+        while self.position < target {
+            let count = (target - self.position).min(skip_buffer.len() as u64) as usize;
+            if self.read(&mut skip_buffer[..count])? == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "Posição além do fim do arquivo gzip",
+                ));
+            }
+        }
+        Ok(self.position)
+    }
+}
+
+/**
+ * Arquivo de dados lido diretamente ou descomprimido, conforme o número mágico do gzip
+ */
+pub enum DataFile {
+    Raw(std::fs::File),
+    Gzip(Box<GzDecoder<std::fs::File>>),
+}
+
+impl DataFile {
+    pub fn open(file_name: &str) -> std::io::Result<DataFile> {
+        let mut file = std::fs::File::open(file_name)?;
+        let mut magic = [0u8; 2];
+        let is_gzip = match file.read_exact(&mut magic) {
+            Ok(()) => magic == GZIP_MAGIC,
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => false,
+            Err(error) => return Err(error),
+        };
+        file.seek(SeekFrom::Start(0))?;
+        if is_gzip {
+            Ok(DataFile::Gzip(Box::new(GzDecoder::new(file)?)))
+        } else {
+            Ok(DataFile::Raw(file))
+        }
+    }
+
+    pub fn is_gzip(&self) -> bool {
+        matches!(self, DataFile::Gzip(_))
+    }
+}
+
+impl Read for DataFile {
+    fn read(&mut self, output: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            DataFile::Raw(file) => file.read(output),
+            DataFile::Gzip(decoder) => decoder.read(output),
+        }
+    }
+}
+
+impl Seek for DataFile {
+    fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+        match self {
+            DataFile::Raw(file) => file.seek(position),
+            DataFile::Gzip(decoder) => decoder.seek(position),
+        }
+    }
+}
+
+/**
+ * gzip com blocos sem compressão, suficiente para gerar arquivos de teste
+ */
+#[cfg(test)]
+pub(crate) fn gzip_stored(data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0x1F, 0x8B, 8, 0, 0, 0, 0, 0, 0, 0xFF];
+    let chunks: Vec<&[u8]> = data.chunks(u16::MAX as usize).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        bytes.push(if i == chunks.len() - 1 { 1 } else { 0 });
+        bytes.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&(!(chunk.len() as u16)).to_le_bytes());
+        bytes.extend_from_slice(chunk);
+    }
+    bytes.extend_from_slice(&crc32(0, data).to_le_bytes());
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_idx::{IdxReader, IdxTensor, IdxType};
+    use std::io::Cursor;
+
+    //Gerados com o módulo gzip do Python (mtime = 0)
+    //"Ola, mundo! Ola, mundo!\n" com nome de arquivo, bloco com código fixo
+    const FIXED: &str =
+        "1f8b08080000000002ff6f6c612e74787400f3cf49d451c82dcd4bc95754f047b0b900949c856218000000";
+    //Mesmo conteúdo sem compressão (bloco armazenado)
+    const STORED: &str = "1f8b08000000000000ff011800e7ff4f6c612c206d756e646f21204f6c612c206d756e646f210a949c856218000000";
+    //Conteúdo de big_text(), bloco com código dinâmico
+    const DYNAMIC: &str = "1f8b08000000000002ffedd0bb0942611084d1dc2a2cc1dddf6739660a17fb0f05d9b905189f68b28fe16cefcfeb793c1db6dfd66ccfaed9f3ec65f63a7b9bbdcf3ed2d9832956929566255aa956b2956e255c2977cabd7f4db953ee943be54eb953ee943be595f24a79ed0c29af9457ca2b6584081122448810214284081122448810214284081122448810214284081122448810214284081122448810214284081122448810214284081122448810214284081122448810214284081122448810214284081122448810214284081122448810214284081122448810214284081122fc9ff00b2af48e5c4b660000";
+    const TEXT: &[u8] = b"Ola, mundo! Ola, mundo!\n";
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn big_text() -> Vec<u8> {
+        (0..3000)
+            .flat_map(|i| format!("linha {}\n", i % 37).into_bytes())
+            .collect()
+    }
+
+    fn decompress(bytes: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        GzDecoder::new(Cursor::new(bytes))?.read_to_end(&mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert!(crc32(0, b"123456789") == 0xCBF43926);
+        assert!(crc32(crc32(0, b"1234"), b"56789") == 0xCBF43926);
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert!(decompress(hex(FIXED)).unwrap() == TEXT);
+        assert!(decompress(hex(STORED)).unwrap() == TEXT);
+        assert!(decompress(hex(DYNAMIC)).unwrap() == big_text());
+        assert!(decompress(gzip_stored(&big_text())).unwrap() == big_text());
+
+        //DEFLATE puro, com cópias que se sobrepõem ("abc" repetido)
+        let mut output = Vec::new();
+        Inflate::new(Cursor::new(hex("4b4c4a4ec48600")))
+            .read_to_end(&mut output)
+            .unwrap();
+        assert!(output == b"abcabcabcabcabcabcabcabc");
+    }
+
+    #[test]
+    fn test_small_reads() {
+        //Leituras menores que as cópias: o restante fica pendente para a próxima leitura
+        let mut decoder = GzDecoder::new(Cursor::new(hex(DYNAMIC))).unwrap();
+        let mut output = Vec::new();
+        let mut buffer = [0u8; 7];
+        loop {
+            let count = decoder.read(&mut buffer).unwrap();
+            if count == 0 {
+                break;
+            }
+            output.extend_from_slice(&buffer[..count]);
+        }
+        assert!(output == big_text());
+    }
+
+    #[test]
+    fn test_corrupted() {
+        let mut bytes = hex(FIXED);
+        let crc_position = bytes.len() - 8;
+        bytes[crc_position] ^= 1;
+        assert!(decompress(bytes).is_err());
+
+        let mut truncated = hex(DYNAMIC);
+        truncated.truncate(100);
+        assert!(decompress(truncated).is_err());
+
+        assert!(decompress(b"nao e gzip".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_exact_read() {
+        //Quem lê exatamente o tamanho do conteúdo não chega a pedir o fim do arquivo
+        let read_exact = |bytes: Vec<u8>, len: usize| -> std::io::Result<Vec<u8>> {
+            let mut output = vec![0u8; len];
+            GzDecoder::new(Cursor::new(bytes))?.read_exact(&mut output)?;
+            Ok(output)
+        };
+        for (bytes, text) in [
+            (hex(FIXED), TEXT.to_vec()),
+            (hex(DYNAMIC), big_text()),
+            (gzip_stored(&big_text()), big_text()),
+        ] {
+            assert!(read_exact(bytes.clone(), text.len()).unwrap() == text);
+            let mut corrupted = bytes.clone();
+            let crc_position = corrupted.len() - 8;
+            corrupted[crc_position] ^= 1;
+            assert!(read_exact(corrupted, text.len()).is_err());
+            let mut wrong_size = bytes;
+            let size_position = wrong_size.len() - 4;
+            wrong_size[size_position] ^= 1;
+            assert!(read_exact(wrong_size, text.len()).is_err());
+        }
+
+        //Arquivo comprimido corrompido falha em IdxTensor::read_file, que lê só o tamanho do cabeçalho
+        let tensor = IdxTensor::new(IdxType::U8, vec![3, 2], vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let mut bytes = Vec::new();
+        tensor.write_to(&mut bytes).unwrap();
+        let mut gzip = gzip_stored(&bytes);
+        let crc_position = gzip.len() - 8;
+        gzip[crc_position] ^= 1;
+        let file_name = std::env::temp_dir().join("nn_gzip_test_corrupted-idx2-ubyte.gz");
+        let file_name = file_name.to_str().unwrap();
+        std::fs::write(file_name, gzip).unwrap();
+        assert!(IdxTensor::read_file(file_name).is_err());
+        std::fs::remove_file(file_name).unwrap();
+    }
+
+    #[test]
+    fn test_seek() {
+        let text = big_text();
+        let mut decoder = GzDecoder::new(Cursor::new(hex(DYNAMIC))).unwrap();
+        let mut buffer = [0u8; 10];
+        assert!(decoder.seek(SeekFrom::Start(1000)).unwrap() == 1000);
+        decoder.read_exact(&mut buffer).unwrap();
+        assert!(buffer == text[1000..1010]);
+        //Voltar recomeça do início
+        decoder.seek(SeekFrom::Start(5)).unwrap();
+        decoder.read_exact(&mut buffer).unwrap();
+        assert!(buffer == text[5..15]);
+        assert!(decoder.seek(SeekFrom::Current(100)).unwrap() == 115);
+        assert!(decoder.seek(SeekFrom::End(0)).is_err());
+        assert!(
+            decoder
+                .seek(SeekFrom::Start(text.len() as u64 + 1))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_idx_gzip() {
+        let data: Vec<f64> = (0..60).map(|i| (i * 7 % 256) as f64).collect();
+        let tensor = IdxTensor::new(IdxType::U8, vec![5, 3, 4], data);
+        let mut bytes = Vec::new();
+        tensor.write_to(&mut bytes).unwrap();
+
+        let file_name = std::env::temp_dir().join("nn_gzip_test-idx3-ubyte.gz");
+        let file_name = file_name.to_str().unwrap();
+        std::fs::write(file_name, gzip_stored(&bytes)).unwrap();
+        assert!(DataFile::open(file_name).unwrap().is_gzip());
+        assert!(IdxTensor::read_file(file_name).unwrap() == tensor);
+        let mut reader = IdxReader::open(file_name).unwrap();
+        assert!(reader.read_item(3).unwrap() == tensor.item(3));
+        assert!(reader.read_item(1).unwrap() == tensor.item(1));
+
+        //Arquivos sem compressão continuam sendo lidos diretamente
+        std::fs::write(file_name, &bytes).unwrap();
+        assert!(!DataFile::open(file_name).unwrap().is_gzip());
+        assert!(IdxTensor::read_file(file_name).unwrap() == tensor);
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
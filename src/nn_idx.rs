@@ -0,0 +1,567 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://web.archive.org/web/20020622183530/http://yann.lecun.com/exdb/mnist/
+use crate::nn_gzip::DataFile;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/**
+ * Formato IDX: número mágico 0x00 0x00 <tipo> <número de dimensões>,
+ * seguido do tamanho de cada dimensão (u32 big-endian) e dos dados, também big-endian.
+ * A primeira dimensão é a quantidade de itens (ex: imagens); as demais, a forma de cada item.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdxType {
+    U8,
+    I8,
+    I16,
+    I32,
+    F32,
+    F64,
+}
+
+impl IdxType {
+    pub fn from_code(code: u8) -> Option<IdxType> {
+        match code {
+            0x08 => Some(IdxType::U8),
+            0x09 => Some(IdxType::I8),
+            0x0B => Some(IdxType::I16),
+            0x0C => Some(IdxType::I32),
+            0x0D => Some(IdxType::F32),
+            0x0E => Some(IdxType::F64),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> u8 {
+        match self {
+            IdxType::U8 => 0x08,
+            IdxType::I8 => 0x09,
+            IdxType::I16 => 0x0B,
+            IdxType::I32 => 0x0C,
+            IdxType::F32 => 0x0D,
+            IdxType::F64 => 0x0E,
+        }
+    }
+
+    /**
+     * Tamanho de um elemento em bytes
+     */
+    pub fn size(&self) -> usize {
+        match self {
+            IdxType::U8 | IdxType::I8 => 1,
+            IdxType::I16 => 2,
+            IdxType::I32 | IdxType::F32 => 4,
+            IdxType::F64 => 8,
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> f64 {
+        match self {
+            IdxType::U8 => bytes[0] as f64,
+            IdxType::I8 => bytes[0] as i8 as f64,
+            IdxType::I16 => i16::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            IdxType::I32 => i32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            IdxType::F32 => f32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            IdxType::F64 => f64::from_be_bytes(bytes.try_into().unwrap()),
+        }
+    }
+
+    /**
+     * Codifica o valor no tipo. Valores inteiros fora do intervalo do tipo ou com parte fracionária
+     * não são representáveis e geram erro; em F32 o valor é arredondado.
+     */
+    fn encode(&self, value: f64, output: &mut Vec<u8>) -> std::io::Result<()> {
+        let (min, max) = match self {
+            IdxType::U8 => (u8::MIN as f64, u8::MAX as f64),
+            IdxType::I8 => (i8::MIN as f64, i8::MAX as f64),
+            IdxType::I16 => (i16::MIN as f64, i16::MAX as f64),
+            IdxType::I32 => (i32::MIN as f64, i32::MAX as f64),
+            IdxType::F32 | IdxType::F64 => (f64::NEG_INFINITY, f64::INFINITY),
+        };
+        let integer = !matches!(self, IdxType::F32 | IdxType::F64);
+        if integer && (value.fract() != 0.0 || value < min || value > max) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Valor {} não representável em {:?}", value, self),
+            ));
+        }
+        match self {
+            IdxType::U8 => output.push(value as u8),
+            IdxType::I8 => output.push(value as i8 as u8),
+            IdxType::I16 => output.extend_from_slice(&(value as i16).to_be_bytes()),
+            IdxType::I32 => output.extend_from_slice(&(value as i32).to_be_bytes()),
+            IdxType::F32 => output.extend_from_slice(&(value as f32).to_be_bytes()),
+            IdxType::F64 => output.extend_from_slice(&value.to_be_bytes()),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdxHeader {
+    data_type: IdxType,
+    dims: Vec<usize>,
+}
+
+impl IdxHeader {
+    pub fn new(data_type: IdxType, dims: Vec<usize>) -> IdxHeader {
+        assert!(!dims.is_empty() && dims.len() <= u8::MAX as usize);
+        assert!(
+            data_len(data_type, &dims).is_some(),
+            "Dimensões IDX grandes demais"
+        );
+        IdxHeader { data_type, dims }
+    }
+
+    pub fn data_type(&self) -> IdxType {
+        self.data_type
+    }
+
+    pub fn dims(&self) -> &[usize] {
+        &self.dims
+    }
+
+    /**
+     * Quantidade de itens (primeira dimensão)
+     */
+    pub fn num_items(&self) -> usize {
+        self.dims[0]
+    }
+
+    /**
+     * Elementos por item (produto das demais dimensões)
+     */
+    pub fn item_len(&self) -> usize {
+        self.dims[1..].iter().product()
+    }
+
+    /**
+     * Bytes de um item
+     */
+    pub fn item_bytes(&self) -> usize {
+        self.item_len() * self.data_type.size()
+    }
+
+    /**
+     * Tamanho do cabeçalho em bytes, posição do primeiro dado no arquivo
+     */
+    pub fn byte_len(&self) -> usize {
+        4 + 4 * self.dims.len()
+    }
+
+    /**
+     * Tamanho do arquivo em bytes: cabeçalho e dados
+     */
+    pub fn file_len(&self) -> usize {
+        self.byte_len() + self.num_items() * self.item_bytes()
+    }
+
+    /**
+     * As dimensões vêm do arquivo: o tamanho total (e, portanto, o de cada item e o deslocamento de cada item)
+     * é verificado aqui, para que um arquivo corrompido gere erro em vez de estouro nos produtos.
+     */
+    pub fn read_from(reader: &mut impl Read) -> std::io::Result<IdxHeader> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic[0] != 0 || magic[1] != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Número mágico IDX inválido: {:02x?}", magic),
+            ));
+        }
+        let data_type = IdxType::from_code(magic[2]).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Tipo de dado IDX desconhecido: 0x{:02x}", magic[2]),
+            )
+        })?;
+        if magic[3] == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Arquivo IDX sem dimensões",
+            ));
+        }
+        let mut dims = Vec::with_capacity(magic[3] as usize);
+        let mut u32_buffer = [0u8; 4];
+        for _ in 0..magic[3] {
+            reader.read_exact(&mut u32_buffer)?;
+            dims.push(u32::from_be_bytes(u32_buffer) as usize);
+        }
+        if data_len(data_type, &dims).is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Dimensões IDX grandes demais: {:?}", dims),
+            ));
+        }
+        Ok(IdxHeader { data_type, dims })
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(&[0, 0, self.data_type.code(), self.dims.len() as u8])?;
+        for &dim in &self.dims {
+            let dim = u32::try_from(dim).map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Dimensão {} não cabe em u32", dim),
+                )
+            })?;
+            writer.write_all(&dim.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Tamanho do arquivo (cabeçalho e dados) em bytes, ou None se não couber em usize.
+ * O tamanho de um item é verificado separadamente porque, com 0 itens, o total não limita o item.
+ */
+fn data_len(data_type: IdxType, dims: &[usize]) -> Option<usize> {
+    let item_bytes = dims[1..]
+        .iter()
+        .try_fold(data_type.size(), |bytes, &dim| bytes.checked_mul(dim))?;
+    dims[0]
+        .checked_mul(item_bytes)?
+        .checked_add(4 + 4 * dims.len())
+}
+
+/**
+ * Tensor IDX de qualquer tipo e número de dimensões, carregado inteiramente na memória.
+ * Os valores são armazenados como f64, que representa exatamente todos os tipos do formato.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdxTensor {
+    header: IdxHeader,
+    data: Vec<f64>,
+}
+
+impl IdxTensor {
+    pub fn new(data_type: IdxType, dims: Vec<usize>, data: Vec<f64>) -> IdxTensor {
+        let header = IdxHeader::new(data_type, dims);
+        assert!(header.num_items() * header.item_len() == data.len());
+        IdxTensor { header, data }
+    }
+
+    pub fn header(&self) -> &IdxHeader {
+        &self.header
+    }
+
+    pub fn data_type(&self) -> IdxType {
+        self.header.data_type
+    }
+
+    pub fn dims(&self) -> &[usize] {
+        &self.header.dims
+    }
+
+    pub fn data(&self) -> &[f64] {
+        &self.data
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.header.num_items()
+    }
+
+    /**
+     * Elementos do item (ex: pixels de uma imagem, em ordem de linhas)
+     */
+    pub fn item(&self, index: usize) -> &[f64] {
+        assert!(index < self.num_items());
+        let item_len = self.header.item_len();
+        &self.data[index * item_len..(index + 1) * item_len]
+    }
+
+    /**
+     * Os dados são lidos sem reservar antes o tamanho do cabeçalho, que pode estar corrompido:
+     * um arquivo menor que o declarado termina em UnexpectedEof em vez de uma alocação enorme.
+     */
+    pub fn read_from(reader: &mut impl Read) -> std::io::Result<IdxTensor> {
+        let header = IdxHeader::read_from(reader)?;
+        let element_size = header.data_type.size();
+        let bytes = read_bytes(reader, header.num_items() * header.item_bytes())?;
+        let data = bytes
+            .chunks_exact(element_size)
+            .map(|element| header.data_type.decode(element))
+            .collect();
+        Ok(IdxTensor { header, data })
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        self.header.write_to(writer)?;
+        let mut bytes = Vec::with_capacity(self.data.len() * self.data_type().size());
+        for &value in &self.data {
+            self.data_type().encode(value, &mut bytes)?;
+        }
+        writer.write_all(&bytes)
+    }
+
+    /**
+     * Lê o arquivo, descomprimindo-o se estiver no formato gzip
+     */
+    pub fn read_file(file_name: &str) -> std::io::Result<IdxTensor> {
+        let file = DataFile::open(file_name)?;
+        IdxTensor::read_from(&mut std::io::BufReader::new(file))
+    }
+
+    pub fn write_file(&self, file_name: &str) -> std::io::Result<()> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(file_name)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+}
+
+/**
+ * Leitura de itens sob demanda, sem carregar o arquivo inteiro (ex: os 240 mil exemplos do EMNIST)
+ */
+pub struct IdxReader<R: Read + Seek> {
+    reader: R,
+    header: IdxHeader,
+}
+
+impl IdxReader<DataFile> {
+    /**
+     * Abre o arquivo, descomprimindo-o sob demanda se estiver no formato gzip (ex: *-idx3-ubyte.gz)
+     */
+    pub fn open(file_name: &str) -> std::io::Result<IdxReader<DataFile>> {
+        IdxReader::new(DataFile::open(file_name)?)
+    }
+}
+
+impl<R: Read + Seek> IdxReader<R> {
+    /**
+     * Quando o tamanho da entrada é conhecido, o cabeçalho não pode declarar mais dados do que ela tem
+     * (em arquivos gzip o tamanho descomprimido não é conhecido, e a falta de dados aparece na leitura)
+     */
+    pub fn new(mut reader: R) -> std::io::Result<IdxReader<R>> {
+        reader.seek(SeekFrom::Start(0))?;
+        let header = IdxHeader::read_from(&mut reader)?;
+        match reader.seek(SeekFrom::End(0)) {
+            Ok(len) if len < header.file_len() as u64 => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "Arquivo IDX com {} bytes, menor que os {} do cabeçalho",
+                        len,
+                        header.file_len()
+                    ),
+                ));
+            }
+            Ok(_) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::Unsupported => {}
+            Err(error) => return Err(error),
+        }
+        Ok(IdxReader { reader, header })
+    }
+
+    pub fn header(&self) -> &IdxHeader {
+        &self.header
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.header.num_items()
+    }
+
+    pub fn read_item(&mut self, index: usize) -> std::io::Result<Vec<f64>> {
+        let element_size = self.header.data_type.size();
+        self.seek_item(index)?;
+        let bytes = read_bytes(&mut self.reader, self.header.item_bytes())?;
+        Ok(bytes
+            .chunks_exact(element_size)
+            .map(|element| self.header.data_type.decode(element))
+            .collect())
+    }
+
+    /**
+     * Bytes do item sem conversão (big-endian). Em arquivos U8, são os próprios valores.
+     */
+    pub fn read_item_bytes(&mut self, index: usize, buffer: &mut [u8]) -> std::io::Result<()> {
+        assert!(buffer.len() == self.header.item_bytes());
+        self.seek_item(index)?;
+        self.reader.read_exact(buffer)
+    }
+
+    fn seek_item(&mut self, index: usize) -> std::io::Result<()> {
+        if index >= self.num_items() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("Item {} inexistente ({} itens)", index, self.num_items()),
+            ));
+        }
+        let offset = self.header.byte_len() + index * self.header.item_bytes();
+        self.reader.seek(SeekFrom::Start(offset as u64))?;
+        Ok(())
+    }
+
+    pub fn read_all(&mut self) -> std::io::Result<IdxTensor> {
+        self.reader.seek(SeekFrom::Start(0))?;
+        IdxTensor::read_from(&mut self.reader)
+    }
+}
+
+/**
+ * Lê exatamente len bytes, alocando conforme eles chegam
+ */
+fn read_bytes(reader: &mut impl Read, len: usize) -> std::io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < len {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!("Arquivo IDX com {} de {} bytes de dados", bytes.len(), len),
+        ));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use std::io::Cursor;
+
+    fn round_trip(tensor: &IdxTensor) -> IdxTensor {
+        let mut bytes = Vec::new();
+        tensor.write_to(&mut bytes).unwrap();
+        IdxTensor::read_from(&mut Cursor::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn test_mnist_header() {
+        //Cabeçalho de um arquivo de rótulos do MNIST: 0x00000801, 3 itens
+        let bytes = vec![0, 0, 8, 1, 0, 0, 0, 3, 7, 2, 1];
+        let tensor = IdxTensor::read_from(&mut Cursor::new(bytes.clone())).unwrap();
+        assert!(tensor.data_type() == IdxType::U8);
+        assert!(tensor.dims() == [3]);
+        assert!(tensor.data() == [7.0, 2.0, 1.0]);
+        assert!(tensor.header().byte_len() == 8);
+
+        let mut written = Vec::new();
+        tensor.write_to(&mut written).unwrap();
+        assert!(written == bytes);
+    }
+
+    #[test]
+    fn test_round_trip_all_types() {
+        let cases = [
+            (IdxType::U8, vec![0.0, 255.0, 17.0, 3.0]),
+            (IdxType::I8, vec![-128.0, 127.0, 0.0, -1.0]),
+            (IdxType::I16, vec![-32768.0, 32767.0, 300.0, -2.0]),
+            (
+                IdxType::I32,
+                vec![-2147483648.0, 2147483647.0, 70000.0, -5.0],
+            ),
+            (IdxType::F32, vec![0.5, -1.25, 1e10, 3.0]),
+            (
+                IdxType::F64,
+                vec![0.1, -1e-300, std::f64::consts::PI, 1e300],
+            ),
+        ];
+        for (data_type, data) in cases {
+            let tensor = IdxTensor::new(data_type, vec![4], data);
+            let read = round_trip(&tensor);
+            println!("{:?}: {:?}", data_type, read.data());
+            assert!(read == tensor);
+        }
+    }
+
+    #[test]
+    fn test_rank() {
+        //Rank 4: 2 itens de 3x2x2
+        let data: Vec<f64> = (0..24).map(|i| i as f64 - 12.0).collect();
+        let tensor = IdxTensor::new(IdxType::I16, vec![2, 3, 2, 2], data);
+        let read = round_trip(&tensor);
+        assert!(read.dims() == [2, 3, 2, 2]);
+        assert!(read.header().item_len() == 12);
+        assert!(read.item(1)[0] == 0.0 && read.item(1)[11] == 11.0);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let invalid_magic = vec![1, 0, 8, 1, 0, 0, 0, 0];
+        assert!(IdxTensor::read_from(&mut Cursor::new(invalid_magic)).is_err());
+        let unknown_type = vec![0, 0, 0x0A, 1, 0, 0, 0, 0];
+        assert!(IdxTensor::read_from(&mut Cursor::new(unknown_type)).is_err());
+        let truncated = vec![0, 0, 8, 1, 0, 0, 0, 3, 1];
+        assert!(IdxTensor::read_from(&mut Cursor::new(truncated)).is_err());
+
+        let mut bytes = Vec::new();
+        let not_representable = IdxTensor::new(IdxType::U8, vec![1], vec![256.0]);
+        assert!(not_representable.write_to(&mut bytes).is_err());
+        let fractional = IdxTensor::new(IdxType::I32, vec![1], vec![0.5]);
+        assert!(fractional.write_to(&mut bytes).is_err());
+    }
+
+    #[test]
+    fn test_reader() {
+        let data: Vec<f64> = (0..12).map(|i| i as f64 * 0.5).collect();
+        let tensor = IdxTensor::new(IdxType::F32, vec![3, 2, 2], data);
+        let mut bytes = Vec::new();
+        tensor.write_to(&mut bytes).unwrap();
+
+        let mut reader = IdxReader::new(Cursor::new(bytes)).unwrap();
+        assert!(reader.num_items() == 3);
+        assert!(reader.read_item(2).unwrap() == tensor.item(2));
+        assert!(reader.read_item(0).unwrap() == tensor.item(0));
+        assert!(reader.read_item(3).is_err());
+        assert!(reader.read_all().unwrap() == tensor);
+    }
+
+    #[test]
+    fn test_file() {
+        let file_name = std::env::temp_dir().join("nn_idx_test.idx");
+        let file_name = file_name.to_str().unwrap();
+        let tensor = IdxTensor::new(IdxType::U8, vec![2, 2], vec![1.0, 2.0, 3.0, 4.0]);
+        tensor.write_file(file_name).unwrap();
+        assert!(IdxTensor::read_file(file_name).unwrap() == tensor);
+        let mut reader = IdxReader::open(file_name).unwrap();
+        let mut buffer = [0u8; 2];
+        reader.read_item_bytes(1, &mut buffer).unwrap();
+        assert!(buffer == [3, 4]);
+        std::fs::remove_file(file_name).unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_dims() {
+        //Quatro dimensões de 2^32 - 1 em f64 não cabem em usize
+        let mut bytes = vec![0, 0, 0x0E, 4];
+        for _ in 0..4 {
+            bytes.extend_from_slice(&u32::MAX.to_be_bytes());
+        }
+        let error = IdxHeader::read_from(&mut Cursor::new(bytes.clone())).unwrap_err();
+        assert!(error.kind() == std::io::ErrorKind::InvalidData);
+        let error = IdxTensor::read_from(&mut Cursor::new(bytes)).unwrap_err();
+        assert!(error.kind() == std::io::ErrorKind::InvalidData);
+
+        //Cabeçalho declara 1000 itens, mas o arquivo só tem 3
+        let bytes = vec![0, 0, 8, 1, 0, 0, 0x03, 0xE8, 7, 2, 1];
+        let error = IdxReader::new(Cursor::new(bytes.clone())).err().unwrap();
+        assert!(error.kind() == std::io::ErrorKind::InvalidData);
+        let error = IdxTensor::read_from(&mut Cursor::new(bytes)).unwrap_err();
+        assert!(error.kind() == std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_write_large_dim() {
+        let header = IdxHeader::new(IdxType::U8, vec![0, 1 << 32]);
+        let error = header.write_to(&mut Vec::new()).unwrap_err();
+        assert!(error.kind() == std::io::ErrorKind::InvalidInput);
+    }
+}
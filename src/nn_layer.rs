@@ -20,10 +20,16 @@ use core::f64;
 //https://www.3blue1brown.com/lessons/backpropagation-calculus#title
 use crate::nn_matrix::Matrix;
 
+/**
+ * Gradientes dos pesos (weight) e viéses (delta) de uma camada.
+ * Gradientes esparsos (ex: Embedding) têm em weight apenas as linhas dos pesos que receberam gradiente,
+ * cujos índices estão em rows, em ordem crescente. Gradientes densos têm rows = None.
+ */
 #[derive(Debug, Clone)]
 pub struct Gradient {
     pub weight: Matrix,
     pub delta: Matrix,
+    pub rows: Option<Vec<usize>>,
 }
 
 impl Gradient {
@@ -31,33 +37,77 @@ impl Gradient {
         self.weight.zero();
         self.delta.zero();
     }
+
+    /**
+     * Soma outro gradiente da mesma camada (acumulação de um lote).
+     * Gradientes esparsos ficam com a união das linhas dos dois.
+     */
+    pub fn accumulate(&mut self, other: &Gradient) {
+        self.delta += &other.delta;
+        match (&self.rows, &other.rows) {
+            (None, None) => self.weight += &other.weight,
+            (Some(rows), Some(other_rows)) => {
+                let mut merged: Vec<usize> = rows.iter().chain(other_rows).copied().collect();
+                merged.sort_unstable();
+                merged.dedup();
+                let cols = self.weight.cols();
+                assert!(other.weight.cols() == cols);
+                let mut weight = Matrix::new(merged.len(), cols);
+                for (source_rows, source) in [(rows, &self.weight), (other_rows, &other.weight)] {
+                    for (i, row) in source_rows.iter().enumerate() {
+                        let target = merged.binary_search(row).unwrap();
+                        for j in 0..cols {
+                            weight[target][j] += source[i][j];
+                        }
+                    }
+                }
+                self.weight = weight;
+                self.rows = Some(merged);
+            }
+            _ => panic!("Gradientes densos e esparsos não podem ser somados"),
+        }
+    }
+
+    /**
+     * Gradiente dos pesos com todas as linhas (parameter_rows), zero nas linhas ausentes de um gradiente esparso
+     */
+    pub fn dense_weight(&self, parameter_rows: usize) -> Matrix {
+        match &self.rows {
+            None => self.weight.clone(),
+            Some(rows) => {
+                let mut dense = Matrix::new(parameter_rows, self.weight.cols());
+                for (i, &row) in rows.iter().enumerate() {
+                    dense[row].copy_from_slice(&self.weight[i]);
+                }
+                dense
+            }
+        }
+    }
 }
 
 pub trait ActivationFunction {
-    fn activate(val: f64, z: &Vec<f64>) -> f64;
+    fn activate(val: f64, z: &[f64]) -> f64;
     fn derivative(val: f64) -> f64;
 }
 
-pub struct Sigmoid {
-    zed_sum: f64,
-}
+pub struct Sigmoid {}
 impl ActivationFunction for Sigmoid {
-    fn activate(val: f64, _z: &Vec<f64>) -> f64 {
+    fn activate(val: f64, _z: &[f64]) -> f64 {
         1.0 / (1.0 + std::f64::consts::E.powf(-val))
     }
     fn derivative(val: f64) -> f64 {
-        let sigma = Sigmoid::activate(val, &vec![1.0]);
+        let sigma = Sigmoid::activate(val, &[1.0]);
         sigma * (1.0 - sigma)
     }
 }
 pub struct Relu {}
 impl ActivationFunction for Relu {
-    fn activate(val: f64, _z: &Vec<f64>) -> f64 {
+    fn activate(val: f64, _z: &[f64]) -> f64 {
         f64::max(0.0, val)
     }
     fn derivative(val: f64) -> f64 {
         match val {
-            0.0 | _ if val < 0.0 => 0.0,
+            _ if val < 0.0 => 0.0,
             _ => 1.0,
         }
     }
@@ -70,11 +120,11 @@ pub struct Softmax {
 }
 
 impl Softmax {
-    pub fn activate(&mut self, val: f64, z: &Vec<f64>) -> f64 {
+    pub fn activate(&mut self, val: f64, z: &[f64]) -> f64 {
         let max = z.iter().fold(f64::NEG_INFINITY, |acc, &val| acc.max(val));
         if self.cached_zed != *z {
             // println!("\tCloning zed.");
-            self.cached_zed = z.clone();
+            self.cached_zed = z.to_vec();
             self.cached_sum = z.iter().map(|&val| (val - max).exp()).sum();
             self.cached_max = z.iter().fold(f64::NEG_INFINITY, |acc, &val| acc.max(val));
             // println!("\t\tCalculated max {}, sum {}", self.cached_max, self.cached_sum);
@@ -90,7 +140,7 @@ impl Softmax {
     }
 }
 // impl ActivationFunction for Softmax {
-//     fn activate(val: f64, z:&Vec<f64>) -> f64 {
+//     fn activate(val: f64, z:&[f64]) -> f64 {
 //         //Softmax com ajuste para estabilidade numérica ()
 //         let max =z.iter().fold(f64::NEG_INFINITY, |acc, &val|acc.max(val));
 //         let sum:f64 = z.iter().map(|&val|(val-max).exp()).sum();
@@ -102,21 +152,88 @@ impl Softmax {
 // }
 pub struct Identity {}
 impl ActivationFunction for Identity {
-    fn activate(val: f64, _z: &Vec<f64>) -> f64 {
+    fn activate(val: f64, _z: &[f64]) -> f64 {
         val
     }
     fn derivative(_: f64) -> f64 {
         1.0
     }
 }
+pub struct Tanh {}
+impl ActivationFunction for Tanh {
+    fn activate(val: f64, _z: &[f64]) -> f64 {
+        val.tanh()
+    }
+    fn derivative(val: f64) -> f64 {
+        1.0 - val.tanh().powi(2)
+    }
+}
+
+/**
+ * Interface comum a todos os tipos de camada da rede.
+ * A entrada é uma matriz em que cada coluna é uma amostra (um lote com uma única amostra é uma matriz coluna).
+ * Camadas sem pesos ou viéses no sentido da camada densa usam essas matrizes para seus próprios parâmetros
+ * (a BatchNorm armazena gamma em weights e beta em biases), o que mantém o formato de Gradient.
+ */
+pub trait NetworkLayer {
+    fn propagate(&mut self, input: &Matrix);
+    fn neurons(&self) -> &Matrix;
+    fn weights(&self) -> &Matrix;
+    fn biases(&self) -> &Matrix;
+    fn fix_weights(&mut self, weights: Matrix);
+    fn fix_bias(&mut self, biases: Matrix);
+
+    /**
+     * Retropropagação genérica. Recebe ∂C/∂a da camada (output_gradient) e a ativação da camada anterior,
+     * devolve o gradiente dos parâmetros e ∂C/∂a_(l-1), que será o output_gradient da camada anterior.
+     */
+    fn backpropagate(
+        &mut self,
+        output_gradient: &Matrix,
+        prev_activations: &Matrix,
+    ) -> (Gradient, Matrix);
+    fn adjust_parameters(&mut self, gradients: &mut Gradient, learning_rate: f64);
+
+    /**
+     * Gradiente nulo dos parâmetros, no formato que adjust_parameters espera
+     * (ex: camadas que não alcançam a saída de um GraphNetwork).
+     */
+    fn zero_gradient(&self) -> Gradient {
+        Gradient {
+            weight: Matrix::new(self.weights().rows(), self.weights().cols()),
+            delta: Matrix::new(self.biases().rows(), self.biases().cols()),
+            rows: None,
+        }
+    }
+
+    /**
+     * Alterna entre os modos de treinamento e inferência.
+     * Apenas camadas cujo comportamento depende do modo (ex: BatchNorm) precisam implementar.
+     */
+    fn set_training(&mut self, _training: bool) {}
+
+    /**
+     * Estado não treinável que deve ser salvo junto dos parâmetros nos checkpoints
+     * (ex: médias e variâncias acumuladas da BatchNorm).
+     */
+    fn state(&self) -> Vec<Matrix> {
+        Vec::new()
+    }
+    fn fix_state(&mut self, state: Vec<Matrix>) {
+        assert!(state.is_empty());
+    }
+}
 
 // type Link = Box<Layer>;
+//Função de ativação de cada neurônio, que recebe também todos os zed da camada (usados pela Softmax)
+type ActivationClosure = Box<dyn FnMut(f64, &[f64]) -> f64>;
+
 pub struct Layer {
     neurons: Matrix,
     zed: Matrix,
     weights: Matrix,
     biases: Matrix,
-    activation_function: Box<dyn FnMut(f64, &Vec<f64>) -> f64>, //    fn(f64, z: &Vec<f64>) -> f64,
+    activation_function: ActivationClosure, //    fn(f64, z: &[f64]) -> f64,
     activation_derivative: fn(f64) -> f64,
 }
 
@@ -138,7 +255,7 @@ impl Layer {
     pub fn new_with_function(
         prev_layer_neurons: usize,
         layer_neurons: usize,
-        activation_f: impl FnMut(f64, &Vec<f64>) -> f64 + 'static,
+        activation_f: impl FnMut(f64, &[f64]) -> f64 + 'static,
         activation_d: fn(f64) -> f64,
     ) -> Layer {
         Layer {
@@ -155,30 +272,10 @@ impl Layer {
         self.neurons.rows()
     }
 
-    pub fn neurons(&self) -> &Matrix {
-        &self.neurons
-    }
-    pub fn weights(&self) -> &Matrix {
-        &self.weights
-    }
     pub fn zed(&self) -> &Matrix {
         &self.zed
     }
 
-    /**
-     * Fixa os valores dos pesos para geração de casos de teste
-     */
-    pub fn fix_weights(&mut self, weights: Matrix) {
-        assert!(weights.rows() == self.weights.rows() && weights.cols() == self.weights.cols());
-        self.weights = weights;
-    }
-    /**
-     * Fixa os valores dos pesos para geração de casos de teste
-     */
-    pub fn fix_bias(&mut self, biases: Matrix) {
-        assert!(biases.rows() == self.biases.rows() && biases.cols() == self.biases.cols());
-        self.biases = biases;
-    }
     /**
      * Fixa os valores da soma ponderada para geração de casos de teste
      */
@@ -191,27 +288,13 @@ impl Layer {
         self.neurons = input;
     }
 
-    pub fn propagate(&mut self, input_neurons: &Matrix) {
-        //activation = act_fn( bias + sum_i(input_neurons_i * weights_i) )
-        // let weight_transpose = self.weights.transpose();
-        let dot_product = &(self.weights) * &input_neurons; //A ordem importa (input * weights) geraria erro!
-
-        //Armazena o resultado para a fase de backprop
-        self.zed = dot_product + &self.biases;
-        assert!(self.zed.rows() == self.neurons.rows());
-        //Biased_values deve ser uma matriz nx1
-        for i in 0..self.zed.rows() {
-            self.neurons[i][0] = (self.activation_function)(self.zed[i][0], &self.zed.data());
-        }
-    }
-
     pub fn cost(&mut self, expected: &Matrix) -> f64 {
         assert!(self.neurons.rows() == expected.rows());
         let mut sum = 0.0;
         for i in 0..self.neurons.rows() {
             sum += (self.neurons[i][0] - expected[i][0]).powi(2);
         }
-        return 0.5 * sum;
+        0.5 * sum
     }
 
     /**
@@ -257,6 +340,7 @@ impl Layer {
         Gradient {
             weight: weight_derivatives,
             delta: deltas,
+            rows: None,
         }
     }
 
@@ -312,10 +396,89 @@ impl Layer {
         Gradient {
             weight: weight_derivatives,
             delta: deltas,
+            rows: None,
         }
     }
 
-    pub fn adjust_parameters(&mut self, gradients: &mut Gradient, learning_rate: f64) {
+}
+
+impl NetworkLayer for Layer {
+    fn propagate(&mut self, input_neurons: &Matrix) {
+        //activation = act_fn( bias + sum_i(input_neurons_i * weights_i) )
+        // let weight_transpose = self.weights.transpose();
+        let dot_product = &(self.weights) * input_neurons; //A ordem importa (input * weights) geraria erro!
+
+        //Armazena o resultado para a fase de backprop
+        //Cada coluna da entrada é uma amostra, o viés é somado a todas elas
+        self.zed = dot_product;
+        self.zed.mut_add_column(&self.biases);
+        assert!(self.zed.rows() == self.neurons.rows());
+        if self.neurons.cols() != self.zed.cols() {
+            self.neurons = Matrix::new(self.zed.rows(), self.zed.cols());
+        }
+        for j in 0..self.zed.cols() {
+            //A função de ativação recebe as somas ponderadas da amostra (necessário para a Softmax)
+            let sample_zed = self.zed.column(j);
+            for i in 0..self.zed.rows() {
+                self.neurons[i][j] = (self.activation_function)(self.zed[i][j], sample_zed.data());
+            }
+        }
+    }
+
+    fn neurons(&self) -> &Matrix {
+        &self.neurons
+    }
+    fn weights(&self) -> &Matrix {
+        &self.weights
+    }
+    fn biases(&self) -> &Matrix {
+        &self.biases
+    }
+
+    /**
+     * Fixa os valores dos pesos para geração de casos de teste
+     */
+    fn fix_weights(&mut self, weights: Matrix) {
+        assert!(weights.rows() == self.weights.rows() && weights.cols() == self.weights.cols());
+        self.weights = weights;
+    }
+    /**
+     * Fixa os valores dos pesos para geração de casos de teste
+     */
+    fn fix_bias(&mut self, biases: Matrix) {
+        assert!(biases.rows() == self.biases.rows() && biases.cols() == self.biases.cols());
+        self.biases = biases;
+    }
+
+    /**
+     * Mesmas fórmulas de backpropagate_hidden_layer, escritas com operações matriciais:
+     * δ = ∂C/∂a ⊙ activation'(z)
+     * ∂C/∂w = δ * a_(l-1)^T   (o produto soma as contribuições de todas as amostras do lote)
+     * ∂C/∂b = soma das colunas de δ
+     * ∂C/∂a_(l-1) = w^T * δ
+     * Para a camada de saída, ∂C/∂a é a derivada do custo, recaindo em backpropagate_output_layer.
+     */
+    fn backpropagate(
+        &mut self,
+        output_gradient: &Matrix,
+        prev_activations: &Matrix,
+    ) -> (Gradient, Matrix) {
+        let mut deltas = self.zed.clone().map(self.activation_derivative);
+        deltas.mut_hadamard_product(output_gradient);
+
+        let weight_derivatives = &deltas * &prev_activations.transpose();
+        let input_gradient = &self.weights.transpose() * &deltas;
+        (
+            Gradient {
+                weight: weight_derivatives,
+                delta: deltas.sum_columns(),
+                rows: None,
+            },
+            input_gradient,
+        )
+    }
+
+    fn adjust_parameters(&mut self, gradients: &mut Gradient, learning_rate: f64) {
         // println!("Gradients are 0 ? Weights: {}", gradients.weight.is_zero());
         let sub = gradients.weight.mut_scalar_product(learning_rate);
         // println!("Scalar Prod are 0 ? Weights: {}", sub.is_zero());
@@ -397,7 +560,6 @@ mod tests {
         //[ -0,48                                   | -0.24 -0.24 -0.24 -0.24 -0.24 |
         //   2,04   X [0.5, 0.5, 0.5, 0.5, 0.5] ->  | 1.02   1.02   1.02    1.02    1.02 |
         //   4,56]                                  | 2.28   2.28   2.28    2.28    2.28 |
-        println!("{},{},{}", (0 % 3), (1 % 3), (2 % 3));
         let expected_derivatives = Matrix::from_vec(
             3,
             5,
@@ -458,7 +620,6 @@ mod tests {
                 | 0.74 |                | 1.0 |   | 0.74 |                            | 0.74*1.0 0.74*0.5 | |0.74 0.37 |
                 | 0.8  |                | 0.0 |   | 0    |                            | 0         0       | | 0      0 |
                  */
-        println!("{},{},{}", (0 % 3), (1 % 3), (2 % 3));
         let expected_derivatives =
             Matrix::from_vec(4, 2, vec![0.62, 0.31, 0.0, 0.0, 0.74, 0.37, 0.0, 0.0]);
 
@@ -473,4 +634,45 @@ mod tests {
 
         assert!(gradient.weight == expected_derivatives);
     }
+
+    #[test]
+    fn test_backpropagate_matches_hidden_layer() {
+        let mut hidden_layer = Layer::new::<Sigmoid>(2, 4);
+        let next_weights = Matrix::from_vec(
+            3,
+            4,
+            vec![1.1, 1.2, 1.3, 1.4, 1.5, 1.6, 1.7, 1.8, 1.9, 2.0, 2.1, 2.2],
+        );
+        let next_deltas = Matrix::from_vec(3, 1, vec![0.9, -0.5, 0.2]);
+        let previous_mock = Matrix::from_vec(2, 1, vec![1.0, 0.5]);
+        hidden_layer.propagate(&previous_mock);
+
+        let expected =
+            hidden_layer.backpropagate_hidden_layer(&next_weights, &next_deltas, &previous_mock);
+        //A versão genérica recebe ∂C/∂a = W_(l+1)^T * δ_(l+1) já calculado pela camada seguinte
+        let output_gradient = &next_weights.transpose() * &next_deltas;
+        let (gradient, input_gradient) =
+            hidden_layer.backpropagate(&output_gradient, &previous_mock);
+
+        println!("Weight Derivatives:{}", gradient.weight);
+        println!("Expected Derivatives:{}", expected.weight);
+        assert!(gradient.weight == expected.weight);
+        assert!(gradient.delta == expected.delta);
+        assert!(input_gradient == &hidden_layer.weights.transpose() * &expected.delta);
+    }
+
+    #[test]
+    fn test_propagate_batch() {
+        //Propagar um lote deve ser equivalente a propagar cada amostra separadamente
+        let mut layer = Layer::new::<Sigmoid>(3, 2);
+        layer.fix_bias(Matrix::from_vec(2, 1, vec![0.1, -0.2]));
+        let batch = Matrix::from_vec(3, 2, vec![1.0, -1.0, 0.5, 2.0, 0.0, 0.3]);
+        layer.propagate(&batch);
+        let batch_output = layer.neurons().clone();
+
+        for j in 0..2 {
+            layer.propagate(&batch.column(j));
+            assert!(batch_output.column(j) == *layer.neurons());
+        }
+    }
 }
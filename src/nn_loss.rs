@@ -0,0 +1,220 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://en.wikipedia.org/wiki/Huber_loss
+//https://en.wikipedia.org/wiki/Cross-entropy#Cross-entropy_loss_function_and_logistic_regression
+use crate::nn_matrix::Matrix;
+
+//Saídas são limitadas a [BCE_EPSILON, 1 - BCE_EPSILON] na entropia cruzada, evitando log(0)
+const BCE_EPSILON: f64 = 1e-12;
+
+/**
+ * Função de custo elemento a elemento. O custo de um lote (uma amostra por coluna) é a soma
+ * dos custos dos elementos dividida pelo número de amostras, mesma convenção do custo MSE original.
+ */
+pub trait Loss {
+    fn element_cost(&self, output: f64, expected: f64) -> f64;
+    fn element_derivative(&self, output: f64, expected: f64) -> f64;
+
+    fn cost(&self, output: &Matrix, expected_output: &Matrix) -> f64 {
+        assert_same_shape(output, expected_output);
+        let total: f64 = output
+            .data()
+            .iter()
+            .zip(expected_output.data())
+            .map(|(&a, &y)| self.element_cost(a, y))
+            .sum();
+        total / output.cols() as f64
+    }
+
+    /**
+     * ∂C/∂a da camada de saída, já dividido pelo número de amostras
+     */
+    fn gradient(&self, output: &Matrix, expected_output: &Matrix) -> Matrix {
+        assert_same_shape(output, expected_output);
+        let batch_size = output.cols() as f64;
+        let data = output
+            .data()
+            .iter()
+            .zip(expected_output.data())
+            .map(|(&a, &y)| self.element_derivative(a, y) / batch_size)
+            .collect();
+        Matrix::from_vec(output.rows(), output.cols(), data)
+    }
+
+    /**
+     * Saídas independentes, uma por rótulo (classificação multi-rótulo).
+     * Nesse caso a acurácia de Trainer usa o limiar 0.5 em cada saída no lugar da maior saída.
+     */
+    fn multi_label(&self) -> bool {
+        false
+    }
+}
+
+fn assert_same_shape(output: &Matrix, expected_output: &Matrix) {
+    assert!(output.rows() == expected_output.rows() && output.cols() == expected_output.cols());
+}
+
+/**
+ * (a - y)², o custo padrão da rede
+ */
+pub struct MeanSquaredError {}
+impl Loss for MeanSquaredError {
+    fn element_cost(&self, output: f64, expected: f64) -> f64 {
+        (output - expected).powi(2)
+    }
+    fn element_derivative(&self, output: f64, expected: f64) -> f64 {
+        2.0 * (output - expected)
+    }
+}
+
+/**
+ * |a - y|. Menos sensível a outliers que o MSE. A derivada em a = y é considerada 0.
+ */
+pub struct MeanAbsoluteError {}
+impl Loss for MeanAbsoluteError {
+    fn element_cost(&self, output: f64, expected: f64) -> f64 {
+        (output - expected).abs()
+    }
+    fn element_derivative(&self, output: f64, expected: f64) -> f64 {
+        let error = output - expected;
+        if error == 0.0 { 0.0 } else { error.signum() }
+    }
+}
+
+/**
+ * Quadrático para erros até delta e linear acima disso:
+ * 0.5e² se |e| <= delta, senão delta(|e| - 0.5delta)
+ */
+pub struct Huber {
+    delta: f64,
+}
+
+impl Huber {
+    pub fn new(delta: f64) -> Huber {
+        assert!(delta > 0.0);
+        Huber { delta }
+    }
+}
+
+impl Loss for Huber {
+    fn element_cost(&self, output: f64, expected: f64) -> f64 {
+        let error = (output - expected).abs();
+        if error <= self.delta {
+            0.5 * error * error
+        } else {
+            self.delta * (error - 0.5 * self.delta)
+        }
+    }
+    fn element_derivative(&self, output: f64, expected: f64) -> f64 {
+        (output - expected).clamp(-self.delta, self.delta)
+    }
+}
+
+/**
+ * Entropia cruzada binária por elemento: -[y ln(a) + (1 - y) ln(1 - a)].
+ * Usada em classificação multi-rótulo, com uma Sigmoid independente por rótulo na camada de saída
+ * e saídas esperadas 0 ou 1 (uma amostra pode ter vários rótulos).
+ */
+pub struct BinaryCrossEntropy {}
+impl Loss for BinaryCrossEntropy {
+    fn element_cost(&self, output: f64, expected: f64) -> f64 {
+        let a = output.clamp(BCE_EPSILON, 1.0 - BCE_EPSILON);
+        -(expected * a.ln() + (1.0 - expected) * (1.0 - a).ln())
+    }
+    fn element_derivative(&self, output: f64, expected: f64) -> f64 {
+        let a = output.clamp(BCE_EPSILON, 1.0 - BCE_EPSILON);
+        (a - expected) / (a * (1.0 - a))
+    }
+    fn multi_label(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+
+    fn check_derivative(loss: &dyn Loss, output: f64, expected: f64) {
+        let epsilon = 1e-6;
+        let numeric = (loss.element_cost(output + epsilon, expected)
+            - loss.element_cost(output - epsilon, expected))
+            / (2.0 * epsilon);
+        let analytic = loss.element_derivative(output, expected);
+        assert!(
+            (numeric - analytic).abs() < 1e-6,
+            "a={} y={}: numérico {} analítico {}",
+            output,
+            expected,
+            numeric,
+            analytic
+        );
+    }
+
+    #[test]
+    fn test_derivatives() {
+        let losses: [&dyn Loss; 3] = [
+            &MeanSquaredError {},
+            &MeanAbsoluteError {},
+            &Huber::new(1.0),
+        ];
+        for loss in losses {
+            for (output, expected) in [(0.3, 1.0), (2.5, -1.0), (-0.2, 0.1), (4.0, 0.5)] {
+                check_derivative(loss, output, expected);
+            }
+        }
+        for (output, expected) in [(0.3, 1.0), (0.9, 0.0), (0.5, 0.5), (0.01, 1.0)] {
+            check_derivative(&BinaryCrossEntropy {}, output, expected);
+        }
+    }
+
+    #[test]
+    fn test_batch_cost() {
+        let output = Matrix::from_vec(2, 2, vec![1.0, 3.0, -1.0, 0.5]);
+        let expected = Matrix::from_vec(2, 2, vec![0.0, 1.0, 1.0, 0.5]);
+        //Erros: [1, 2, -2, 0], média por amostra (2 colunas)
+        assert!(MeanSquaredError {}.cost(&output, &expected) == 4.5);
+        assert!(MeanAbsoluteError {}.cost(&output, &expected) == 2.5);
+        //Huber(1): 0.5 + 1.5 + 1.5 + 0
+        assert!(Huber::new(1.0).cost(&output, &expected) == 1.75);
+
+        let gradient = Huber::new(1.0).gradient(&output, &expected);
+        assert!(gradient == Matrix::from_vec(2, 2, vec![0.5, 0.5, -0.5, 0.0]));
+    }
+
+    #[test]
+    fn test_binary_cross_entropy() {
+        let output = Matrix::from_vec(2, 1, vec![0.8, 0.25]);
+        let expected = Matrix::from_vec(2, 1, vec![1.0, 0.0]);
+        let cost = BinaryCrossEntropy {}.cost(&output, &expected);
+        assert!((cost - (-(0.8f64.ln()) - 0.75f64.ln())).abs() < 1e-12);
+        //Saídas saturadas não produzem infinito
+        let saturated = Matrix::from_vec(2, 1, vec![0.0, 1.0]);
+        assert!(
+            BinaryCrossEntropy {}
+                .cost(&saturated, &expected)
+                .is_finite()
+        );
+        assert!(
+            BinaryCrossEntropy {}
+                .gradient(&saturated, &expected)
+                .data()
+                .iter()
+                .all(|v| v.is_finite())
+        );
+    }
+}
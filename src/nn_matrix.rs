@@ -74,7 +74,7 @@ impl Matrix {
         [-(sqrt(6)/sqrt(n_in+n_out)),(sqrt(6)/sqrt(n_in+n_out))]
     */
     pub fn new_random_glorot(n_in: usize, n_out: usize) -> Matrix {
-        let high = ((6.0 as f64).sqrt() / ((n_in + n_out) as f64)).sqrt();
+        let high = (6.0_f64.sqrt() / ((n_in + n_out) as f64)).sqrt();
         let low = -high;
         let mut rng = rand::rng();
         let distribution = rand::distr::Uniform::new(low, high).unwrap();
@@ -82,7 +82,7 @@ impl Matrix {
             rows: n_in,
             cols: n_out,
             data: (0..(n_in * n_out))
-                .map(|_| rng.sample(&distribution))
+                .map(|_| rng.sample(distribution))
                 .collect(),
         }
     }
@@ -98,7 +98,7 @@ impl Matrix {
             rows: n_in,
             cols: n_out,
             data: (0..(n_in * n_out))
-                .map(|_| rng.sample(&distribution))
+                .map(|_| rng.sample(distribution))
                 .collect(),
         }
     }
@@ -114,7 +114,7 @@ impl Matrix {
     fn index(&self, row: usize, col: usize) -> usize {
         assert!(row < self.rows); //Índice da linha deve ser válido 0 <= row < self.rows
         assert!(col < self.cols); //Índice da coluna deve ser válido 0 <= row < self.rows
-        return row * self.cols + col;
+        row * self.cols + col
     }
 
     pub fn num_elements(&self) -> usize {
@@ -139,7 +139,7 @@ impl Matrix {
                 product[i][j] = row_product;
             }
         }
-        return product;
+        product
     }
 
     /* Implementação ingênua da transposição de matrizes.
@@ -158,15 +158,17 @@ impl Matrix {
                 transpose[i][j] = self[j][i];
             }
         }
-        return transpose;
+        transpose
     }
 
     pub fn hadamard_product(&self, other: &Matrix) -> Matrix {
         assert!(self.rows == other.rows && self.cols == other.cols);
-        let mut produtc_vec = vec![0.0; self.num_elements()];
-        for i in 0..self.num_elements() {
-            produtc_vec[i] = self.data[i] * other.data[i];
-        }
+        let produtc_vec = self
+            .data
+            .iter()
+            .zip(&other.data)
+            .map(|(a, b)| a * b)
+            .collect();
         Matrix {
             rows: self.rows(),
             cols: self.cols,
@@ -176,7 +178,7 @@ impl Matrix {
     pub fn mut_hadamard_product(&mut self, other: &Matrix) {
         if self.rows == other.rows && self.cols == other.cols {
             for i in 0..self.num_elements() {
-                self.data[i] = self.data[i] * other.data[i];
+                self.data[i] *= other.data[i];
             }
             return;
         }
@@ -184,7 +186,7 @@ impl Matrix {
         assert!(self.rows == other.rows && other.cols == 1);
         for i in 0..self.num_elements() {
             let j = i % other.rows;
-            self.data[i] = self.data[i] * other.data[j];
+            self.data[i] *= other.data[j];
         }
     }
 
@@ -202,7 +204,7 @@ impl Matrix {
 
     pub fn mut_scalar_product(&mut self, scalar: f64) -> &Matrix {
         for i in 0..self.num_elements() {
-            self.data[i] = self.data[i] * scalar;
+            self.data[i] *= scalar;
         }
         self
     }
@@ -235,6 +237,118 @@ impl Matrix {
         }
     }
 
+    /**
+     * Copia a coluna j como uma nova matriz coluna (rows x 1).
+     * Quando a matriz representa um lote, cada coluna é uma amostra.
+     */
+    pub fn column(&self, col: usize) -> Matrix {
+        assert!(col < self.cols);
+        Matrix {
+            rows: self.rows,
+            cols: 1,
+            data: (0..self.rows).map(|i| self[i][col]).collect(),
+        }
+    }
+
+    pub fn set_column(&mut self, col: usize, column: &Matrix) {
+        assert!(col < self.cols && column.rows == self.rows && column.cols == 1);
+        for i in 0..self.rows {
+            self[i][col] = column.data[i];
+        }
+    }
+
+    //Broadcasting: soma a matriz coluna other a todas as colunas de self
+    pub fn mut_add_column(&mut self, other: &Matrix) {
+        assert!(self.rows == other.rows && other.cols == 1);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                self[i][j] += other.data[i];
+            }
+        }
+    }
+
+    //Copia count linhas a partir de first como uma nova matriz
+    pub fn rows_range(&self, first: usize, count: usize) -> Matrix {
+        assert!(first + count <= self.rows);
+        Matrix {
+            rows: count,
+            cols: self.cols,
+            data: self.data[first * self.cols..(first + count) * self.cols].to_vec(),
+        }
+    }
+
+    //Sobrescreve as linhas a partir de first com as linhas de block
+    pub fn set_rows(&mut self, first: usize, block: &Matrix) {
+        assert!(first + block.rows <= self.rows && block.cols == self.cols);
+        self.data[first * self.cols..(first + block.rows) * self.cols].copy_from_slice(&block.data);
+    }
+
+    /**
+     * Softmax aplicada a cada linha, com o mesmo ajuste para estabilidade numérica da camada Softmax.
+     * Valores -infinito resultam em probabilidade 0 (usado para mascarar posições).
+     */
+    pub fn softmax_rows(&self) -> Matrix {
+        let mut result = Matrix::new(self.rows, self.cols);
+        for i in 0..self.rows {
+            let max = self[i]
+                .iter()
+                .fold(f64::NEG_INFINITY, |acc, &val| acc.max(val));
+            let sum: f64 = self[i].iter().map(|&val| (val - max).exp()).sum();
+            for j in 0..self.cols {
+                result[i][j] = (self[i][j] - max).exp() / sum;
+            }
+        }
+        result
+    }
+
+    //Soma as colunas, gerando uma matriz coluna (rows x 1). Usada para acumular os gradientes de um lote
+    pub fn sum_columns(&self) -> Matrix {
+        let mut sum = Matrix::new(self.rows, 1);
+        for i in 0..self.rows {
+            sum.data[i] = self[i].iter().sum();
+        }
+        sum
+    }
+
+    /**
+     * Serializa a matriz: dimensões em u32 seguidas dos valores em f64, tudo em big-endian
+     * (mesma convenção dos arquivos do MNIST).
+     */
+    pub fn write_to(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        writer.write_all(&(self.rows as u32).to_be_bytes())?;
+        writer.write_all(&(self.cols as u32).to_be_bytes())?;
+        for value in &self.data {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    /**
+     * As dimensões vêm do arquivo: nada é reservado a partir delas, os valores são guardados conforme são lidos
+     * (um arquivo corrompido termina em erro, não em uma alocação enorme)
+     */
+    pub fn read_from(reader: &mut impl std::io::Read) -> std::io::Result<Matrix> {
+        let mut u32_buffer = [0u8; 4];
+        reader.read_exact(&mut u32_buffer)?;
+        let rows = u32::from_be_bytes(u32_buffer) as usize;
+        reader.read_exact(&mut u32_buffer)?;
+        let cols = u32::from_be_bytes(u32_buffer) as usize;
+        let len = rows.checked_mul(cols).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Matriz {}x{} grande demais", rows, cols),
+            )
+        })?;
+
+        let mut f64_buffer = [0u8; 8];
+        let mut data = Vec::new();
+        for _ in 0..len {
+            reader.read_exact(&mut f64_buffer)?;
+            data.push(f64::from_be_bytes(f64_buffer));
+        }
+        Ok(Matrix::from_vec(rows, cols, data))
+    }
+
     pub fn mut_translate_right(&mut self, num_cols: usize) {
         assert!(num_cols <= self.cols);
         for i in 0..self.rows {
@@ -272,50 +386,73 @@ impl Matrix {
         }
     }
     
+    /**
+     * Rotação de theta radianos em torno do centro (sentido horário na imagem, com as linhas de cima para baixo)
+     */
     pub fn rotate(self, theta: f64) -> Matrix {
-        let mut rotated = Matrix { rows:self.rows, cols: self.cols, data: vec![0.0; self.num_elements()] };
-        let cx = self.cols as f64 / 2.0;
-        let cy = self.rows as f64 / 2.0;
-        let sin = f64::sin(theta);
-        let cos = f64::cos(theta);
-
-        for y in 0..self.rows{
-            for x in 0..self.cols{
-                let coord_x = x as f64 - cx;
-                let coord_y = y as f64 - cy;
-                
-                let orig_x = coord_x  * cos + coord_y  * sin;
-                let orig_y = -(coord_x ) * sin + coord_y  * cos;
-
-                let x1 = f64::round(orig_x + cx as f64);
-                let y1 = f64::round(orig_y + cy as f64);
-                
-                if x1 >=0.0 && x1 < self.cols as f64 && y1 >=0.0 && y1 < self.rows as f64{
-                    //Provavelmente incorreto, precisa interpolar caso o pixel não esteja em coordenadas inteiras
-                    rotated[y][x] = self[x1 as usize][y1 as usize];
-                }else{
-                    rotated[y][x] = 0.0;
-                }
+        let (sin, cos) = theta.sin_cos();
+        self.affine([[cos, -sin], [sin, cos]], (0.0, 0.0))
+    }
 
+    /**
+     * Transformação afim em torno do centro: o ponto p = (coluna, linha) relativo ao centro vai para transform * p + translation.
+     * Cada elemento do resultado é lido da posição de origem (transformação inversa) com interpolação bilinear,
+     * e posições fora da matriz valem 0.
+     */
+    pub fn affine(&self, transform: [[f64; 2]; 2], translation: (f64, f64)) -> Matrix {
+        let [[a, b], [c, d]] = transform;
+        let det = a * d - b * c;
+        assert!(det.abs() > EPSILON, "Transformação afim não inversível");
+        let cx = (self.cols as f64 - 1.0) / 2.0;
+        let cy = (self.rows as f64 - 1.0) / 2.0;
+        let mut transformed = Matrix::new(self.rows, self.cols);
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                let dx = x as f64 - cx - translation.0;
+                let dy = y as f64 - cy - translation.1;
+                let orig_x = (d * dx - b * dy) / det + cx;
+                let orig_y = (-c * dx + a * dy) / det + cy;
+                transformed[y][x] = self.bilinear(orig_y, orig_x);
             }
         }
-        rotated
+        transformed
     }
 
+    /**
+     * Valor na posição fracionária (row, col), interpolado entre os 4 elementos vizinhos.
+     * Elementos fora da matriz valem 0.
+     */
+    pub fn bilinear(&self, row: f64, col: f64) -> f64 {
+        let row0 = row.floor();
+        let col0 = col.floor();
+        let row_weight = row - row0;
+        let col_weight = col - col0;
+        let value = |i: f64, j: f64| {
+            if i >= 0.0 && i < self.rows as f64 && j >= 0.0 && j < self.cols as f64 {
+                self[i as usize][j as usize]
+            } else {
+                0.0
+            }
+        };
+        let top = (1.0 - col_weight) * value(row0, col0) + col_weight * value(row0, col0 + 1.0);
+        let bottom = (1.0 - col_weight) * value(row0 + 1.0, col0)
+            + col_weight * value(row0 + 1.0, col0 + 1.0);
+        (1.0 - row_weight) * top + row_weight * bottom
+    }
 }
 
 impl fmt::Display for Matrix {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({} x {})\n", self.rows, self.cols)?;
+        writeln!(f, "({} x {})", self.rows, self.cols)?;
         for i in 0..self.rows {
             write!(f, "| ")?;
             let start_index = i * self.cols;
             let slice = &self.data[start_index..start_index + self.cols];
-            for i in 0..self.cols {
+            for value in slice {
                 //Imprime alinhado à direita (>) com largura FMT_NUM_WIDTH e FMT_NUM_PRECISION casas decimais
-                write!(f, "{:>FMT_NUM_WIDTH$.FMT_NUM_PRECISION$}  ", slice[i])?;
+                write!(f, "{:>FMT_NUM_WIDTH$.FMT_NUM_PRECISION$}  ", value)?;
             }
-            write!(f, "|\n")?;
+            writeln!(f, "|")?;
         }
         write!(f, "")
     }
@@ -327,7 +464,7 @@ impl Index<(usize, usize)> for Matrix {
     fn index(&self, index: (usize, usize)) -> &Self::Output {
         let (row, col) = index;
 
-        return &self.data[self.index(row, col)];
+        &self.data[self.index(row, col)]
     }
 }
 
@@ -336,7 +473,7 @@ impl IndexMut<(usize, usize)> for Matrix {
     fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
         let (row, col) = index;
         let idx = self.index(row, col); //necessário separar por conta do borrow abaixo
-        return &mut self.data[idx];
+        &mut self.data[idx]
     }
 }
 
@@ -345,16 +482,14 @@ impl Index<usize> for Matrix {
     type Output = [f64];
     fn index(&self, index: usize) -> &Self::Output {
         let idx_base = self.index(index, 0);
-        let slice = &self.data[idx_base..idx_base + self.cols];
-        return slice;
+        &self.data[idx_base..idx_base + self.cols]
     }
 }
 
 impl IndexMut<usize> for Matrix {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         let idx_base = index * self.cols;
-        let slice = &mut self.data[idx_base..idx_base + self.cols];
-        return slice;
+        &mut self.data[idx_base..idx_base + self.cols]
     }
 }
 
@@ -363,11 +498,11 @@ fn f64_nearly_equal(a: f64, b: f64, epsilon: f64) -> bool {
     let abs_b = b.abs();
     let diff = (a - b).abs();
     if a == b {
-        return true;
+        true
     } else if a == 0.0 || b == 0.0 || (abs_a + abs_b < f64::MIN) {
-        return diff < (epsilon * f64::MIN);
+        diff < (epsilon * f64::MIN)
     } else {
-        return diff / f64::min(abs_a + abs_b, f64::MAX) < epsilon;
+        diff / f64::min(abs_a + abs_b, f64::MAX) < epsilon
     }
 }
 
@@ -409,7 +544,7 @@ impl Add<&Matrix> for Matrix {
         for i in 0..(self.num_elements()) {
             result.data[i] = self.data[i] + rhs.data[i];
         }
-        return result;
+        result
     }
 }
 
@@ -441,11 +576,11 @@ mod tests {
             cols: 3,
             data: vec![1.0, 2.0, 3.0, -4.0, 0.0, 5.0],
         };
-        print!("{}\n", base_matrix);
+        println!("{}", base_matrix);
         base_matrix[0][1] = 5.0;
-        print!("{}\n", base_matrix);
+        println!("{}", base_matrix);
         base_matrix[(1, 2)] = -40.0;
-        print!("{}\n", base_matrix);
+        println!("{}", base_matrix);
     }
 
     #[test]
@@ -486,7 +621,7 @@ mod tests {
             cols: 2,
             data: vec![2.0, 7.0, -1.0, 0.0, 4.0, 1.0],
         };
-        let panic = other * base_matrix;
+        let _panic = other * base_matrix;
     }
     #[test]
     fn test_transpose() {
@@ -544,7 +679,7 @@ mod tests {
             cols: 2,
             data: vec![2.0, 7.0, -1.0, 0.0, 4.0, 1.0],
         };
-        let panic = other + &base_matrix;
+        let _panic = other + &base_matrix;
     }
 
     #[test]
@@ -586,7 +721,7 @@ mod tests {
         let mut b_l = base_matrix.clone();
         let mut b_u = base_matrix.clone();
         let mut b_d = base_matrix.clone();
-        let mut right_2 = Matrix {
+        let right_2 = Matrix {
             rows: 4,
             cols: 4,
             data: vec![
@@ -632,4 +767,100 @@ mod tests {
         assert!(b_d == down_2);
 
     }
+
+    #[test]
+    fn test_columns() {
+        let mut batch = Matrix {
+            rows: 2,
+            cols: 3,
+            data: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+        };
+        let bias = Matrix {
+            rows: 2,
+            cols: 1,
+            data: vec![0.5, -1.0],
+        };
+        batch.mut_add_column(&bias);
+        let expected = Matrix {
+            rows: 2,
+            cols: 3,
+            data: vec![1.5, 2.5, 3.5, 3.0, 4.0, 5.0],
+        };
+        assert!(batch == expected);
+        assert!(batch.column(1) == Matrix::from_vec(2, 1, vec![2.5, 4.0]));
+        assert!(batch.sum_columns() == Matrix::from_vec(2, 1, vec![7.5, 12.0]));
+
+        batch.set_column(0, &Matrix::from_vec(2, 1, vec![0.0, 0.0]));
+        assert!(batch.column(0).is_zero());
+    }
+
+    #[test]
+    fn test_softmax_rows() {
+        let base_matrix = Matrix {
+            rows: 2,
+            cols: 3,
+            data: vec![1.0, 2.0, 3.0, 0.5, f64::NEG_INFINITY, 0.5],
+        };
+        let softmax = base_matrix.softmax_rows();
+        print!("Softmax: {}", softmax);
+        //e^-2, e^-1, e^0 normalizados
+        let expected = Matrix {
+            rows: 2,
+            cols: 3,
+            data: vec![
+                0.09003057317038046,
+                0.24472847105479764,
+                0.6652409557748219,
+                0.5,
+                0.0,
+                0.5,
+            ],
+        };
+        assert!(softmax == expected);
+
+        let mut stacked = Matrix::new(4, 3);
+        stacked.set_rows(2, &base_matrix);
+        assert!(stacked.rows_range(2, 2).data()[..3] == base_matrix.data()[..3]);
+        assert!(stacked.rows_range(0, 2).is_zero());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let base_matrix = Matrix {
+            rows: 2,
+            cols: 2,
+            data: vec![1.0, -2.5, 3.25, 1e-7],
+        };
+        let mut buffer: Vec<u8> = Vec::new();
+        base_matrix.write_to(&mut buffer).unwrap();
+        assert!(buffer.len() == 8 + 4 * 8);
+
+        let read = Matrix::read_from(&mut buffer.as_slice()).unwrap();
+        assert!(read == base_matrix);
+
+        //Dimensões corrompidas: erro ao faltarem os valores, sem reservar memória para eles
+        let mut corrupted = [u32::MAX.to_be_bytes(), u32::MAX.to_be_bytes()].concat();
+        corrupted.extend_from_slice(&1.0f64.to_be_bytes());
+        let error = Matrix::read_from(&mut corrupted.as_slice()).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            std::io::ErrorKind::UnexpectedEof | std::io::ErrorKind::InvalidData
+        ));
+    }
+
+    #[test]
+    fn test_rotate() {
+        let base_matrix = Matrix::from_vec(3, 3, (1..10).map(|v| v as f64).collect());
+        //90 graus no sentido horário: a primeira coluna, de baixo para cima, vira a primeira linha
+        let rotated = base_matrix.clone().rotate(std::f64::consts::FRAC_PI_2);
+        let expected = Matrix::from_vec(3, 3, vec![7.0, 4.0, 1.0, 8.0, 5.0, 2.0, 9.0, 6.0, 3.0]);
+        assert!(rotated == expected);
+        assert!(base_matrix.clone().rotate(0.0) == base_matrix);
+
+        //Meio pixel para a direita: média das colunas vizinhas (0 fora da matriz)
+        let shifted = base_matrix.affine([[1.0, 0.0], [0.0, 1.0]], (0.5, 0.0));
+        assert!(shifted[0][0] == 0.5 && shifted[0][1] == 1.5 && shifted[2][2] == 8.5);
+        assert!(base_matrix.bilinear(0.5, 0.5) == 3.0);
+        assert!(base_matrix.bilinear(-1.0, 0.0) == 0.0);
+    }
 }
@@ -0,0 +1,778 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://en.wikipedia.org/wiki/Precision_and_recall
+//https://scikit-learn.org/stable/modules/model_evaluation.html#classification-metrics
+//https://en.wikipedia.org/wiki/Receiver_operating_characteristic#Area_under_the_curve
+use std::fmt;
+use std::fmt::Write;
+
+use crate::nn_matrix::Matrix;
+
+//Probabilidades são limitadas a [LOG_LOSS_EPSILON, 1 - LOG_LOSS_EPSILON] no log-loss, evitando log(0)
+const LOG_LOSS_EPSILON: f64 = 1e-15;
+
+/**
+ * Precisão, revocação e F1 de uma classe (ou de uma média entre classes)
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassScores {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+impl ClassScores {
+    fn new(precision: f64, recall: f64) -> ClassScores {
+        ClassScores {
+            precision,
+            recall,
+            f1: ratio(2.0 * precision * recall, precision + recall),
+        }
+    }
+}
+
+//Divisões por zero (classe sem amostras ou sem previsões) resultam em 0
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {
+        0.0
+    } else {
+        numerator / denominator
+    }
+}
+
+/**
+ * Matriz de confusão: linhas são as classes esperadas e colunas as classes previstas
+ */
+#[derive(Debug, Clone)]
+pub struct ConfusionMatrix {
+    counts: Vec<Vec<usize>>,
+}
+
+impl ConfusionMatrix {
+    pub fn new(num_classes: usize) -> ConfusionMatrix {
+        ConfusionMatrix {
+            counts: vec![vec![0; num_classes]; num_classes],
+        }
+    }
+
+    pub fn num_classes(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn add(&mut self, actual: usize, predicted: usize) {
+        self.counts[actual][predicted] += 1;
+    }
+
+    pub fn count(&self, actual: usize, predicted: usize) -> usize {
+        self.counts[actual][predicted]
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    /**
+     * Número de amostras da classe (soma da linha)
+     */
+    pub fn support(&self, class: usize) -> usize {
+        self.counts[class].iter().sum()
+    }
+
+    fn predicted_count(&self, class: usize) -> usize {
+        self.counts.iter().map(|row| row[class]).sum()
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        let right: usize = (0..self.num_classes()).map(|c| self.counts[c][c]).sum();
+        ratio(right as f64, self.total() as f64)
+    }
+
+    pub fn scores(&self, class: usize) -> ClassScores {
+        let true_positives = self.counts[class][class] as f64;
+        ClassScores::new(
+            ratio(true_positives, self.predicted_count(class) as f64),
+            ratio(true_positives, self.support(class) as f64),
+        )
+    }
+
+    /**
+     * Média simples entre as classes
+     */
+    pub fn macro_average(&self) -> ClassScores {
+        let n = self.num_classes() as f64;
+        let (precision, recall, f1) = self.sum_scores(|_| 1.0);
+        ClassScores {
+            precision: precision / n,
+            recall: recall / n,
+            f1: f1 / n,
+        }
+    }
+
+    /**
+     * Média ponderada pelo número de amostras de cada classe
+     */
+    pub fn weighted_average(&self) -> ClassScores {
+        let total = self.total() as f64;
+        let (precision, recall, f1) = self.sum_scores(|support| support as f64);
+        ClassScores {
+            precision: ratio(precision, total),
+            recall: ratio(recall, total),
+            f1: ratio(f1, total),
+        }
+    }
+
+    /**
+     * Métricas calculadas com a soma dos acertos e erros de todas as classes.
+     * Com uma classe por amostra, precisão, revocação e F1 micro são iguais à acurácia.
+     */
+    pub fn micro_average(&self) -> ClassScores {
+        let true_positives: usize = (0..self.num_classes()).map(|c| self.counts[c][c]).sum();
+        let predicted: usize = (0..self.num_classes())
+            .map(|c| self.predicted_count(c))
+            .sum();
+        let actual: usize = (0..self.num_classes()).map(|c| self.support(c)).sum();
+        ClassScores::new(
+            ratio(true_positives as f64, predicted as f64),
+            ratio(true_positives as f64, actual as f64),
+        )
+    }
+
+    fn sum_scores(&self, weight: fn(usize) -> f64) -> (f64, f64, f64) {
+        (0..self.num_classes()).fold((0.0, 0.0, 0.0), |(p, r, f), class| {
+            let scores = self.scores(class);
+            let w = weight(self.support(class));
+            (
+                p + w * scores.precision,
+                r + w * scores.recall,
+                f + w * scores.f1,
+            )
+        })
+    }
+}
+
+impl fmt::Display for ConfusionMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>8}", "real\\prev")?;
+        for predicted in 0..self.num_classes() {
+            write!(f, "{:>7}", predicted)?;
+        }
+        writeln!(f)?;
+        for (actual, row) in self.counts.iter().enumerate() {
+            write!(f, "{:>9}", actual)?;
+            for count in row {
+                write!(f, "{:>7}", count)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Índice da maior saída de uma coluna (classe prevista)
+ */
+pub fn argmax(output: &Matrix) -> usize {
+    assert!(output.cols() == 1);
+    output
+        .data()
+        .iter()
+        .enumerate()
+        .fold((0, f64::NEG_INFINITY), |(best_i, best_v), (i, &v)| {
+            if v > best_v { (i, v) } else { (best_i, best_v) }
+        })
+        .0
+}
+
+/**
+ * Acumula as saídas da rede (uma coluna por amostra, uma linha por classe) e as classes esperadas,
+ * produzindo a matriz de confusão, métricas por classe, acurácia top-k e log-loss.
+ */
+pub struct ClassificationMetrics {
+    confusion: ConfusionMatrix,
+    label_ranks: Vec<usize>, //Posição da classe esperada entre as saídas ordenadas (0 = maior saída)
+    log_loss_sum: f64,
+}
+
+impl ClassificationMetrics {
+    pub fn new(num_classes: usize) -> ClassificationMetrics {
+        ClassificationMetrics {
+            confusion: ConfusionMatrix::new(num_classes),
+            label_ranks: Vec::new(),
+            log_loss_sum: 0.0,
+        }
+    }
+
+    /**
+     * Registra a saída de uma amostra. As saídas são normalizadas para somar 1 antes do log-loss,
+     * já que as camadas de saída usadas (ex: Sigmoid) não produzem uma distribuição de probabilidades.
+     */
+    pub fn add(&mut self, output: &Matrix, label: usize) {
+        assert!(output.cols() == 1 && output.rows() == self.confusion.num_classes());
+        self.confusion.add(label, argmax(output));
+
+        let label_output = output[label][0];
+        let rank = output
+            .data()
+            .iter()
+            .enumerate()
+            .filter(|&(i, &v)| v > label_output || (v == label_output && i < label))
+            .count();
+        self.label_ranks.push(rank);
+
+        let sum: f64 = output.data().iter().sum();
+        let probability = ratio(label_output, sum).clamp(LOG_LOSS_EPSILON, 1.0 - LOG_LOSS_EPSILON);
+        self.log_loss_sum -= probability.ln();
+    }
+
+    pub fn samples(&self) -> usize {
+        self.label_ranks.len()
+    }
+
+    pub fn confusion_matrix(&self) -> &ConfusionMatrix {
+        &self.confusion
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        self.confusion.accuracy()
+    }
+
+    /**
+     * Fração das amostras cuja classe esperada está entre as k maiores saídas
+     */
+    pub fn top_k_accuracy(&self, k: usize) -> f64 {
+        let hits = self.label_ranks.iter().filter(|&&rank| rank < k).count();
+        ratio(hits as f64, self.samples() as f64)
+    }
+
+    /**
+     * Entropia cruzada média: -média(ln p(classe esperada))
+     */
+    pub fn log_loss(&self) -> f64 {
+        ratio(self.log_loss_sum, self.samples() as f64)
+    }
+
+    /**
+     * Relatório com a matriz de confusão, métricas por classe, médias, top-k e log-loss
+     */
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        self.write_report(&mut report)
+            .expect("Falha ao escrever em String");
+        report
+    }
+
+    fn write_report(&self, report: &mut String) -> fmt::Result {
+        writeln!(report, "Matriz de confusão:")?;
+        write!(report, "{}", self.confusion)?;
+        writeln!(report)?;
+        writeln!(
+            report,
+            "{:>12}{:>11}{:>11}{:>11}{:>9}",
+            "classe", "precisão", "revocação", "f1", "amostras"
+        )?;
+        let write_scores =
+            |report: &mut String, name: &str, scores: ClassScores, support: usize| {
+                writeln!(
+                    report,
+                    "{:>12}{:>11.4}{:>11.4}{:>11.4}{:>9}",
+                    name, scores.precision, scores.recall, scores.f1, support
+                )
+            };
+        for class in 0..self.confusion.num_classes() {
+            write_scores(
+                report,
+                &class.to_string(),
+                self.confusion.scores(class),
+                self.confusion.support(class),
+            )?;
+        }
+        writeln!(report)?;
+        let total = self.confusion.total();
+        write_scores(report, "macro", self.confusion.macro_average(), total)?;
+        write_scores(report, "micro", self.confusion.micro_average(), total)?;
+        write_scores(
+            report,
+            "ponderada",
+            self.confusion.weighted_average(),
+            total,
+        )?;
+        writeln!(report)?;
+        writeln!(report, "Acurácia: {:.4}", self.accuracy())?;
+        for k in [3, 5] {
+            if k < self.confusion.num_classes() {
+                writeln!(report, "Acurácia top-{}: {:.4}", k, self.top_k_accuracy(k))?;
+            }
+        }
+        writeln!(report, "Log-loss: {:.4}", self.log_loss())
+    }
+}
+
+/**
+ * Rótulos previstos no modo multi-rótulo: 1 onde a saída é maior ou igual ao limiar, 0 nos demais
+ */
+pub fn threshold_labels(output: &Matrix, threshold: f64) -> Matrix {
+    let mut labels = output.clone();
+    for i in 0..output.rows() {
+        for j in 0..output.cols() {
+            labels[i][j] = if output[i][j] >= threshold { 1.0 } else { 0.0 };
+        }
+    }
+    labels
+}
+
+/**
+ * Métricas de classificação multi-rótulo (cada amostra pode ter vários rótulos, um por linha).
+ * As saídas esperadas são 0 ou 1; as previsões são obtidas com threshold_labels.
+ */
+pub struct MultiLabelMetrics {
+    threshold: f64,
+    scores: Vec<Vec<(f64, bool)>>, //Por rótulo: (saída da rede, rótulo esperado) de cada amostra
+    wrong_labels: usize,
+    exact_matches: usize,
+    samples: usize,
+}
+
+impl MultiLabelMetrics {
+    pub fn new(num_labels: usize, threshold: f64) -> MultiLabelMetrics {
+        MultiLabelMetrics {
+            threshold,
+            scores: vec![Vec::new(); num_labels],
+            wrong_labels: 0,
+            exact_matches: 0,
+            samples: 0,
+        }
+    }
+
+    /**
+     * Registra as saídas da rede e os rótulos esperados (uma coluna por amostra)
+     */
+    pub fn add(&mut self, output: &Matrix, expected: &Matrix) {
+        assert!(output.rows() == self.scores.len());
+        assert!(output.rows() == expected.rows() && output.cols() == expected.cols());
+        let predicted = threshold_labels(output, self.threshold);
+        for j in 0..output.cols() {
+            let mut wrong = 0;
+            for (i, label_scores) in self.scores.iter_mut().enumerate() {
+                let positive = expected[i][j] >= 0.5;
+                label_scores.push((output[i][j], positive));
+                if (predicted[i][j] == 1.0) != positive {
+                    wrong += 1;
+                }
+            }
+            self.wrong_labels += wrong;
+            if wrong == 0 {
+                self.exact_matches += 1;
+            }
+            self.samples += 1;
+        }
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /**
+     * Fração dos pares (amostra, rótulo) previstos incorretamente
+     */
+    pub fn hamming_loss(&self) -> f64 {
+        ratio(
+            self.wrong_labels as f64,
+            (self.samples * self.scores.len()) as f64,
+        )
+    }
+
+    /**
+     * Fração das amostras com todos os rótulos previstos corretamente
+     */
+    pub fn subset_accuracy(&self) -> f64 {
+        ratio(self.exact_matches as f64, self.samples as f64)
+    }
+
+    /**
+     * Área sob a curva ROC do rótulo: probabilidade de uma amostra positiva ter saída maior
+     * que uma negativa (empates contam metade). None se o rótulo não tem amostras positivas e negativas.
+     */
+    pub fn auc(&self, label: usize) -> Option<f64> {
+        let mut scores = self.scores[label].clone();
+        scores.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let positives = scores.iter().filter(|(_, positive)| *positive).count();
+        let negatives = scores.len() - positives;
+        if positives == 0 || negatives == 0 {
+            return None;
+        }
+        //Estatística de Mann-Whitney: soma dos postos (médios, em empates) das amostras positivas
+        let mut positive_rank_sum = 0.0;
+        let mut start = 0;
+        while start < scores.len() {
+            let mut end = start;
+            while end < scores.len() && scores[end].0 == scores[start].0 {
+                end += 1;
+            }
+            let average_rank = (start + end + 1) as f64 / 2.0;
+            let tied_positives = scores[start..end].iter().filter(|(_, p)| *p).count();
+            positive_rank_sum += average_rank * tied_positives as f64;
+            start = end;
+        }
+        let positives = positives as f64;
+        Some(
+            (positive_rank_sum - positives * (positives + 1.0) / 2.0)
+                / (positives * negatives as f64),
+        )
+    }
+
+    /**
+     * Média da AUC entre os rótulos em que ela é definida
+     */
+    pub fn mean_auc(&self) -> Option<f64> {
+        let aucs: Vec<f64> = (0..self.scores.len())
+            .filter_map(|label| self.auc(label))
+            .collect();
+        if aucs.is_empty() {
+            None
+        } else {
+            Some(aucs.iter().sum::<f64>() / aucs.len() as f64)
+        }
+    }
+
+    pub fn report(&self) -> String {
+        let mut report = format!(
+            "Amostras: {}\nLimiar: {}\nHamming loss: {:.4}\nAcurácia de subconjunto: {:.4}\n",
+            self.samples,
+            self.threshold,
+            self.hamming_loss(),
+            self.subset_accuracy()
+        );
+        for label in 0..self.scores.len() {
+            match self.auc(label) {
+                Some(auc) => report.push_str(&format!("AUC rótulo {}: {:.4}\n", label, auc)),
+                None => report.push_str(&format!("AUC rótulo {}: indefinida\n", label)),
+            }
+        }
+        if let Some(auc) = self.mean_auc() {
+            report.push_str(&format!("AUC média: {:.4}\n", auc));
+        }
+        report
+    }
+}
+
+/**
+ * Métricas de regressão, acumuladas elemento a elemento e separadas por saída (linha):
+ * RMSE, MAE, R² (média entre as saídas) e MAPE.
+ */
+pub struct RegressionMetrics {
+    outputs: Vec<OutputStatistics>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct OutputStatistics {
+    count: usize,
+    squared_error_sum: f64,
+    absolute_error_sum: f64,
+    expected_mean: f64,
+    expected_m2: f64, //Soma dos quadrados dos desvios em relação à média (Welford)
+    percentage_error_sum: f64,
+    percentage_count: usize, //Elementos com saída esperada diferente de 0, usados no MAPE
+}
+
+impl RegressionMetrics {
+    pub fn new(num_outputs: usize) -> RegressionMetrics {
+        RegressionMetrics {
+            outputs: vec![OutputStatistics::default(); num_outputs],
+        }
+    }
+
+    /**
+     * Registra previsões e saídas esperadas (uma linha por saída, uma coluna por amostra)
+     */
+    pub fn add(&mut self, prediction: &Matrix, expected: &Matrix) {
+        assert!(prediction.rows() == self.outputs.len());
+        assert!(prediction.rows() == expected.rows() && prediction.cols() == expected.cols());
+        for (i, statistics) in self.outputs.iter_mut().enumerate() {
+            for j in 0..prediction.cols() {
+                let (a, y) = (prediction[i][j], expected[i][j]);
+                let error = a - y;
+                statistics.count += 1;
+                statistics.squared_error_sum += error * error;
+                statistics.absolute_error_sum += error.abs();
+                let delta = y - statistics.expected_mean;
+                statistics.expected_mean += delta / statistics.count as f64;
+                statistics.expected_m2 += delta * (y - statistics.expected_mean);
+                if y != 0.0 {
+                    statistics.percentage_error_sum += (error / y).abs();
+                    statistics.percentage_count += 1;
+                }
+            }
+        }
+    }
+
+    pub fn samples(&self) -> usize {
+        self.outputs
+            .first()
+            .map_or(0, |statistics| statistics.count)
+    }
+
+    fn total<F: Fn(&OutputStatistics) -> f64>(&self, f: F) -> f64 {
+        self.outputs.iter().map(f).sum()
+    }
+
+    fn element_count(&self) -> f64 {
+        self.total(|statistics| statistics.count as f64)
+    }
+
+    pub fn rmse(&self) -> f64 {
+        ratio(self.total(|s| s.squared_error_sum), self.element_count()).sqrt()
+    }
+
+    pub fn mae(&self) -> f64 {
+        ratio(self.total(|s| s.absolute_error_sum), self.element_count())
+    }
+
+    /**
+     * Coeficiente de determinação 1 - SS_res / SS_tot, calculado por saída e
+     * devolvido como a média entre as saídas. Uma saída constante tem R² 0.
+     * SS_tot é acumulado pelo algoritmo de Welford, que não perde precisão
+     * quando a média é grande em relação à variância (ao contrário de Σy² - (Σy)²/n).
+     */
+    pub fn r2(&self) -> f64 {
+        let r2_sum: f64 = self
+            .outputs
+            .iter()
+            .map(|s| {
+                if s.expected_m2 <= 0.0 {
+                    0.0
+                } else {
+                    1.0 - s.squared_error_sum / s.expected_m2
+                }
+            })
+            .sum();
+        ratio(r2_sum, self.outputs.len() as f64)
+    }
+
+    /**
+     * Erro percentual absoluto médio (em %). Elementos com saída esperada 0 são ignorados.
+     */
+    pub fn mape(&self) -> f64 {
+        100.0
+            * ratio(
+                self.total(|s| s.percentage_error_sum),
+                self.total(|s| s.percentage_count as f64),
+            )
+    }
+
+    pub fn report(&self) -> String {
+        format!(
+            "Amostras: {}\nRMSE: {:.6}\nMAE: {:.6}\nR²: {:.6}\nMAPE: {:.4}%\n",
+            self.samples(),
+            self.rmse(),
+            self.mae(),
+            self.r2(),
+            self.mape()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    //3 classes: esperadas [0,0,0,1,1,2], previstas [0,0,1,1,2,2]
+    fn confusion() -> ConfusionMatrix {
+        let mut confusion = ConfusionMatrix::new(3);
+        for (actual, predicted) in [(0, 0), (0, 0), (0, 1), (1, 1), (1, 2), (2, 2)] {
+            confusion.add(actual, predicted);
+        }
+        confusion
+    }
+
+    #[test]
+    fn test_confusion_matrix() {
+        let confusion = confusion();
+        print!("{}", confusion);
+        assert!(confusion.count(0, 1) == 1 && confusion.count(1, 2) == 1);
+        assert!(confusion.total() == 6);
+        assert!(close(confusion.accuracy(), 4.0 / 6.0));
+
+        let scores = confusion.scores(0);
+        assert!(close(scores.precision, 1.0) && close(scores.recall, 2.0 / 3.0));
+        assert!(close(scores.f1, 0.8));
+        let scores = confusion.scores(2);
+        assert!(close(scores.precision, 0.5) && close(scores.recall, 1.0));
+    }
+
+    #[test]
+    fn test_averages() {
+        let confusion = confusion();
+        //Precisões [1, 0.5, 0.5], revocações [2/3, 0.5, 1], f1 [0.8, 0.5, 2/3], amostras [3, 2, 1]
+        let macro_average = confusion.macro_average();
+        assert!(close(macro_average.precision, 2.0 / 3.0));
+        assert!(close(macro_average.recall, (2.0 / 3.0 + 0.5 + 1.0) / 3.0));
+        assert!(close(macro_average.f1, (0.8 + 0.5 + 2.0 / 3.0) / 3.0));
+
+        let weighted = confusion.weighted_average();
+        assert!(close(weighted.precision, (3.0 + 1.0 + 0.5) / 6.0));
+        assert!(close(weighted.recall, confusion.accuracy()));
+
+        let micro = confusion.micro_average();
+        assert!(close(micro.precision, confusion.accuracy()));
+        assert!(close(micro.f1, confusion.accuracy()));
+    }
+
+    #[test]
+    fn test_empty_class() {
+        let mut confusion = ConfusionMatrix::new(3);
+        confusion.add(0, 0);
+        confusion.add(1, 0);
+        let scores = confusion.scores(2);
+        assert!(scores.precision == 0.0 && scores.recall == 0.0 && scores.f1 == 0.0);
+    }
+
+    #[test]
+    fn test_top_k_and_log_loss() {
+        let mut metrics = ClassificationMetrics::new(4);
+        metrics.add(&Matrix::from_vec(4, 1, vec![0.7, 0.1, 0.1, 0.1]), 0);
+        metrics.add(&Matrix::from_vec(4, 1, vec![0.5, 0.3, 0.2, 0.0]), 1);
+        metrics.add(&Matrix::from_vec(4, 1, vec![0.1, 0.4, 0.3, 0.2]), 3);
+        assert!(close(metrics.accuracy(), 1.0 / 3.0));
+        assert!(close(metrics.top_k_accuracy(1), 1.0 / 3.0));
+        assert!(close(metrics.top_k_accuracy(2), 2.0 / 3.0));
+        assert!(close(metrics.top_k_accuracy(3), 1.0));
+        let expected = -(0.7f64.ln() + 0.3f64.ln() + 0.2f64.ln()) / 3.0;
+        assert!(close(metrics.log_loss(), expected));
+    }
+
+    #[test]
+    fn test_log_loss_normalizes_and_clips() {
+        let mut metrics = ClassificationMetrics::new(2);
+        //Saídas de sigmoides não somam 1: [0.6, 0.2] equivale a [0.75, 0.25]
+        metrics.add(&Matrix::from_vec(2, 1, vec![0.6, 0.2]), 0);
+        assert!(close(metrics.log_loss(), -(0.75f64.ln())));
+
+        let mut metrics = ClassificationMetrics::new(2);
+        metrics.add(&Matrix::from_vec(2, 1, vec![1.0, 0.0]), 1);
+        assert!(metrics.log_loss().is_finite());
+    }
+
+    #[test]
+    fn test_report() {
+        let mut metrics = ClassificationMetrics::new(10);
+        for label in 0..10 {
+            let mut output = Matrix::new(10, 1);
+            output[(label + label % 2) % 10][0] = 1.0;
+            metrics.add(&output, label);
+        }
+        let report = metrics.report();
+        print!("{}", report);
+        assert!(
+            report.contains("macro") && report.contains("top-5") && report.contains("Log-loss")
+        );
+        assert!(close(metrics.accuracy(), 0.5));
+    }
+
+    #[test]
+    fn test_regression_metrics() {
+        let mut metrics = RegressionMetrics::new(1);
+        metrics.add(
+            &Matrix::from_vec(1, 2, vec![2.5, 0.0]),
+            &Matrix::from_vec(1, 2, vec![3.0, -0.5]),
+        );
+        metrics.add(
+            &Matrix::from_vec(1, 1, vec![2.0]),
+            &Matrix::from_vec(1, 1, vec![2.0]),
+        );
+        metrics.add(
+            &Matrix::from_vec(1, 1, vec![8.0]),
+            &Matrix::from_vec(1, 1, vec![7.0]),
+        );
+        print!("{}", metrics.report());
+        //Erros: [-0.5, 0.5, 0, 1] (mesmo exemplo da documentação do scikit-learn)
+        assert!(metrics.samples() == 4);
+        assert!(close(metrics.mae(), 0.5));
+        assert!(close(metrics.rmse(), 0.375f64.sqrt()));
+        assert!(close(metrics.r2(), 0.9486081370449679));
+        let mape = 100.0 * (0.5 / 3.0 + 0.5 / 0.5 + 0.0 + 1.0 / 7.0) / 4.0;
+        assert!(close(metrics.mape(), mape));
+    }
+
+    #[test]
+    fn test_regression_multiple_outputs() {
+        let mut metrics = RegressionMetrics::new(2);
+        //Saída 0 perfeita (R² 1), saída 1 prevista pela média (R² 0); y = 0 fica fora do MAPE
+        metrics.add(
+            &Matrix::from_vec(2, 3, vec![1.0, 2.0, 3.0, 1.0, 1.0, 1.0]),
+            &Matrix::from_vec(2, 3, vec![1.0, 2.0, 3.0, 0.0, 1.0, 2.0]),
+        );
+        assert!(close(metrics.r2(), 0.5));
+        assert!(close(metrics.mape(), 100.0 * 0.5 / 5.0));
+    }
+
+    #[test]
+    fn test_r2_large_mean() {
+        //Mesmo exemplo de test_regression_metrics deslocado em 1e9: o R² não depende do deslocamento
+        let offset = 1e9;
+        let prediction = [2.5, 0.0, 2.0, 8.0].map(|a| a + offset);
+        let expected = [3.0, -0.5, 2.0, 7.0].map(|y| y + offset);
+        let mut metrics = RegressionMetrics::new(1);
+        metrics.add(
+            &Matrix::from_vec(1, 4, prediction.to_vec()),
+            &Matrix::from_vec(1, 4, expected.to_vec()),
+        );
+        assert!(close(metrics.r2(), 0.9486081370449679));
+    }
+
+    #[test]
+    fn test_multi_label_metrics() {
+        let mut metrics = MultiLabelMetrics::new(3, 0.5);
+        //Amostras nas colunas: as duas primeiras previstas corretamente, a terceira com os três rótulos errados
+        let output = Matrix::from_vec(3, 3, vec![0.9, 0.2, 0.4, 0.1, 0.7, 0.3, 0.6, 0.8, 0.5]);
+        let expected = Matrix::from_vec(3, 3, vec![1.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0]);
+        metrics.add(&output, &expected);
+        print!("{}", metrics.report());
+        assert!(
+            threshold_labels(&output, 0.5)
+                == Matrix::from_vec(3, 3, vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0])
+        );
+        assert!(metrics.samples() == 3);
+        assert!(close(metrics.hamming_loss(), 3.0 / 9.0));
+        assert!(close(metrics.subset_accuracy(), 2.0 / 3.0));
+    }
+
+    #[test]
+    fn test_auc() {
+        let mut metrics = MultiLabelMetrics::new(2, 0.5);
+        //Rótulo 0: positivos [0.8, 0.4], negativos [0.5, 0.1] -> 3 de 4 pares ordenados
+        //Rótulo 1: todos positivos, AUC indefinida
+        let output = Matrix::from_vec(2, 4, vec![0.8, 0.4, 0.5, 0.1, 0.9, 0.9, 0.9, 0.9]);
+        let expected = Matrix::from_vec(2, 4, vec![1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]);
+        metrics.add(&output, &expected);
+        assert!(close(metrics.auc(0).unwrap(), 0.75));
+        assert!(metrics.auc(1).is_none());
+        assert!(close(metrics.mean_auc().unwrap(), 0.75));
+
+        //Empates contam metade
+        let mut metrics = MultiLabelMetrics::new(1, 0.5);
+        metrics.add(
+            &Matrix::from_vec(1, 2, vec![0.3, 0.3]),
+            &Matrix::from_vec(1, 2, vec![1.0, 0.0]),
+        );
+        assert!(close(metrics.auc(0).unwrap(), 0.5));
+    }
+}
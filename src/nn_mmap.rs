@@ -0,0 +1,199 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://man7.org/linux/man-pages/man2/mmap.2.html
+use std::ops::Deref;
+
+/**
+ * Arquivo mapeado na memória, somente leitura.
+ * As páginas são carregadas pelo sistema operacional sob demanda e podem ser descartadas
+ * quando falta memória, o que permite acessar arquivos maiores que a RAM como um slice de bytes.
+ * Só está disponível em unix 64 bits; nas demais plataformas open devolve um erro Unsupported
+ * (para esses casos, leia o arquivo para a memória).
+ */
+pub struct Mmap {
+    mapping: Mapping,
+}
+
+impl Mmap {
+    /**
+     * Mapeia o arquivo inteiro.
+     *
+     * O arquivo não pode ser truncado nem modificado enquanto o Mmap existir, nem por este processo
+     * nem por outro. Com MAP_PRIVATE, o que acontece com as páginas ainda não lidas é indefinido:
+     * alterações no arquivo podem aparecer no slice, que o Rust supõe imutável, e acessar uma página
+     * além do fim de um arquivo truncado encerra o processo com SIGBUS.
+     * Use apenas em arquivos de dados que nenhum outro programa está escrevendo.
+     */
+    pub fn open(file_name: &str) -> std::io::Result<Mmap> {
+        Ok(Mmap {
+            mapping: Mapping::open(file_name)?,
+        })
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.mapping.bytes()
+    }
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+mod sys {
+    use std::ffi::{c_int, c_void};
+
+    //Valores iguais no Linux e no macOS
+    pub const PROT_READ: c_int = 1;
+    pub const MAP_PRIVATE: c_int = 2;
+
+    unsafe extern "C" {
+        pub fn mmap(
+            addr: *mut c_void,
+            len: usize,
+            prot: c_int,
+            flags: c_int,
+            fd: c_int,
+            offset: i64,
+        ) -> *mut c_void;
+        pub fn munmap(addr: *mut c_void, len: usize) -> c_int;
+    }
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+struct Mapping {
+    ptr: *const u8,
+    len: usize,
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+impl Mapping {
+    fn open(file_name: &str) -> std::io::Result<Mapping> {
+        use std::os::fd::AsRawFd;
+
+        let file = std::fs::File::open(file_name)?;
+        let len = file.metadata()?.len() as usize;
+        //mmap não aceita tamanho 0
+        if len == 0 {
+            return Ok(Mapping {
+                ptr: std::ptr::NonNull::dangling().as_ptr(),
+                len,
+            });
+        }
+        // SAFETY: endereço nulo deixa o sistema escolher onde mapear, sem sobrescrever outro
+        // mapeamento; len > 0 e o descritor é de um arquivo aberto para leitura, compatível com PROT_READ.
+        let ptr = unsafe {
+            sys::mmap(
+                std::ptr::null_mut(),
+                len,
+                sys::PROT_READ,
+                sys::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        //MAP_FAILED é (void*)-1. O mapeamento continua válido depois que o arquivo é fechado.
+        if ptr as isize == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Mapping {
+            ptr: ptr as *const u8,
+            len,
+        })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: ptr aponta para len bytes legíveis, mapeados até o Drop (ou é um ponteiro alinhado
+        // não nulo com len 0). O conteúdo só não muda se o arquivo não for modificado (ver Mmap::open).
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: ptr e len são os devolvidos por mmap, e nenhum slice de bytes() sobrevive ao Mapping
+            unsafe {
+                sys::munmap(self.ptr as *mut std::ffi::c_void, self.len);
+            }
+        }
+    }
+}
+
+// SAFETY: o Mapping é dono exclusivo do mapeamento, e munmap pode ser chamado de qualquer thread
+#[cfg(all(unix, target_pointer_width = "64"))]
+unsafe impl Send for Mapping {}
+// SAFETY: o mapeamento é somente leitura (PROT_READ) e o Mapping não tem mutabilidade interior,
+// então leituras simultâneas de várias threads não causam condição de corrida
+#[cfg(all(unix, target_pointer_width = "64"))]
+unsafe impl Sync for Mapping {}
+
+//Sem mmap não há como mapear: ler o arquivo inteiro esconderia o uso de memória de quem escolheu o Mmap
+#[cfg(not(all(unix, target_pointer_width = "64")))]
+enum Mapping {}
+
+#[cfg(not(all(unix, target_pointer_width = "64")))]
+impl Mapping {
+    fn open(_file_name: &str) -> std::io::Result<Mapping> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Mapeamento de arquivos disponível apenas em unix 64 bits",
+        ))
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match *self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+
+    #[test]
+    #[cfg(all(unix, target_pointer_width = "64"))]
+    fn test_mmap() {
+        let dir = std::env::temp_dir();
+        let file_name = dir.join("nn_mmap_test.bin");
+        let data: Vec<u8> = (0..10000).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&file_name, &data).unwrap();
+        let map = Mmap::open(file_name.to_str().unwrap()).unwrap();
+        assert!(map.len() == data.len());
+        assert!(map[..] == data[..]);
+        assert!(map[9999] == (9999 % 251) as u8);
+
+        let empty_name = dir.join("nn_mmap_test_empty.bin");
+        std::fs::write(&empty_name, []).unwrap();
+        assert!(Mmap::open(empty_name.to_str().unwrap()).unwrap().is_empty());
+
+        std::fs::remove_file(file_name).unwrap();
+        std::fs::remove_file(empty_name).unwrap();
+        assert!(Mmap::open(dir.join("nn_mmap_missing.bin").to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    #[cfg(not(all(unix, target_pointer_width = "64")))]
+    fn test_unsupported() {
+        let file_name = std::env::temp_dir().join("nn_mmap_test_unsupported.bin");
+        std::fs::write(&file_name, [1, 2, 3]).unwrap();
+        let error = Mmap::open(file_name.to_str().unwrap()).err().unwrap();
+        assert!(error.kind() == std::io::ErrorKind::Unsupported);
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
@@ -1,6 +1,10 @@
+use crate::nn_clipping::{GradientClipping, global_norm};
 use crate::nn_layer::Gradient;
-use crate::nn_layer::Layer;
+use crate::nn_layer::NetworkLayer;
+use crate::nn_loss::{Loss, MeanSquaredError};
 use crate::nn_matrix::Matrix;
+use crate::nn_preprocessing::{LabelEncoder, Preprocessor};
+use crate::nn_regularization::Regularization;
 /**
  *  Copyright 2025 Eric Zancanaro
  *    
@@ -18,9 +22,20 @@ use crate::nn_matrix::Matrix;
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 use std::collections::VecDeque;
+use std::io::{Read, Write};
+
+//Identificador dos arquivos de checkpoint ("NNCK")
+const CHECKPOINT_MAGIC: u32 = 0x4E4E434B;
+
 pub struct NeuralNetwork {
-    layers: Vec<Layer>,
+    layers: Vec<Box<dyn NetworkLayer>>,
     learning_rate: f64,
+    loss: Box<dyn Loss>,
+    regularizations: Vec<Option<Regularization>>, //Uma por camada
+    gradient_clipping: Option<GradientClipping>,
+    last_gradient_norm: f64, //Norma global dos gradientes do último ajuste, antes da limitação
+    preprocessors: Vec<Preprocessor>, //Aplicados em sequência às entradas em predict
+    label_encoder: Option<LabelEncoder>,
 }
 
 impl NeuralNetwork {
@@ -28,25 +43,102 @@ impl NeuralNetwork {
         NeuralNetwork {
             layers: Vec::with_capacity(num_layers),
             learning_rate: _learning_rate,
+            loss: Box::new(MeanSquaredError {}),
+            regularizations: Vec::with_capacity(num_layers),
+            gradient_clipping: None,
+            last_gradient_norm: 0.0,
+            preprocessors: Vec::new(),
+            label_encoder: None,
         }
     }
 
     pub fn num_layers(&self) -> usize {
         self.layers.len()
     }
-    pub fn add_layer(&mut self, layer: Layer) {
-        self.layers.push(layer);
+    pub fn add_layer(&mut self, layer: impl NetworkLayer + 'static) {
+        self.layers.push(Box::new(layer));
+        self.regularizations.push(None);
+    }
+
+    pub fn borrow_layer(&self, layer: usize) -> &dyn NetworkLayer {
+        assert!(layer > 0 && layer <= self.layers.len());
+        self.layers[layer - 1].as_ref()
     }
 
-    pub fn borrow_layer(&self, layer: usize) -> &Layer {
+    pub fn borrow_layer_mut(&mut self, layer: usize) -> &mut dyn NetworkLayer {
         assert!(layer > 0 && layer <= self.layers.len());
-        &self.layers[layer - 1]
+        self.layers[layer - 1].as_mut()
     }
 
     pub fn cost_derivative_mse(x: f64, y: f64) -> f64 {
         2.0 * (x - y)
     }
 
+    /**
+     * Função de custo usada na retropropagação (MSE por padrão)
+     */
+    pub fn set_loss(&mut self, loss: impl Loss + 'static) {
+        self.loss = Box::new(loss);
+    }
+
+    pub fn loss(&self) -> &dyn Loss {
+        self.loss.as_ref()
+    }
+
+    /**
+     * Regularização dos parâmetros da camada (numerada a partir de 1, como em borrow_layer)
+     */
+    pub fn set_regularization(&mut self, layer: usize, regularization: Regularization) {
+        assert!(layer > 0 && layer <= self.layers.len());
+        self.regularizations[layer - 1] = Some(regularization);
+    }
+
+    /**
+     * Mesma regularização para todas as camadas já adicionadas
+     */
+    pub fn set_regularization_all(&mut self, regularization: Regularization) {
+        self.regularizations
+            .iter_mut()
+            .for_each(|layer_regularization| *layer_regularization = Some(regularization));
+    }
+
+    /**
+     * Limitação aplicada aos gradientes em adjust_parameters (nenhuma por padrão)
+     */
+    pub fn set_gradient_clipping(&mut self, gradient_clipping: Option<GradientClipping>) {
+        self.gradient_clipping = gradient_clipping;
+    }
+
+    /**
+     * Norma global dos gradientes do último ajuste de parâmetros, antes da limitação.
+     * Útil para diagnosticar a explosão de gradientes.
+     */
+    pub fn last_gradient_norm(&self) -> f64 {
+        self.last_gradient_norm
+    }
+
+    /**
+     * Soma das penalidades L1/L2 de todas as camadas
+     */
+    pub fn regularization_penalty(&self) -> f64 {
+        self.layers
+            .iter()
+            .zip(&self.regularizations)
+            .filter_map(|(layer, regularization)| {
+                regularization.map(|regularization| regularization.penalty(layer.as_ref()))
+            })
+            .sum()
+    }
+
+    /**
+     * Custo da saída da última propagação em relação à saída esperada,
+     * incluindo as penalidades de regularização
+     */
+    pub fn cost(&self, expected_output: &Matrix) -> f64 {
+        let output = self.layers.last().expect("FAILED TO TAKE LAST LAYER");
+        self.loss.cost(output.neurons(), expected_output) + self.regularization_penalty()
+    }
+
     /* Original, com problema por conta do borrow checker.
     pub fn train(&mut self, input: Matrix) {
     assert!(!self.layers.is_empty());
@@ -66,7 +158,7 @@ impl NeuralNetwork {
      * e ajusta os parâmetros com os gradientes
      */
     pub fn train(&mut self, input: Matrix, expected_output: Matrix) {
-        self.classify(&input);
+        self.forward(&input, true);
         let mut gradients = self.generate_gradients(input, expected_output);
         self.adjust_parameters(&mut gradients);
     }
@@ -77,7 +169,7 @@ impl NeuralNetwork {
         expected_output: Matrix,
         cur_gradients: &mut VecDeque<Gradient>,
     ) {
-        self.classify(&input);
+        self.forward(&input, true);
         let gradients = self.generate_gradients(input, expected_output);
 
         if cur_gradients.is_empty() {
@@ -86,27 +178,81 @@ impl NeuralNetwork {
             cur_gradients
                 .iter_mut()
                 .zip(gradients)
-                .for_each(|(cur_gradient, gradient)| {
-                    cur_gradient.weight += &gradient.weight;
-                    cur_gradient.delta += &gradient.delta;
-                });
+                .for_each(|(cur_gradient, gradient)| cur_gradient.accumulate(&gradient));
         }
     }
 
     pub fn adjust_parameters(&mut self, gradients: &mut VecDeque<Gradient>) {
-        assert!(gradients.len() == self.layers.len() - 1);
+        assert!(gradients.len() == self.layers.len());
+        self.last_gradient_norm = match &self.gradient_clipping {
+            Some(gradient_clipping) => gradient_clipping.clip(gradients),
+            None => global_norm(gradients),
+        };
         //zip: agrupa 2 iteradores. O laço é finalizado quanto um deles chega ao fim.
         //No nosso caso, ambos terão o mesmo tamanho, dado o assert! acima.
-        for (layer, gradient) in self.layers.iter_mut().skip(1).zip(gradients) {
+        for ((layer, gradient), regularization) in self
+            .layers
+            .iter_mut()
+            .zip(gradients)
+            .zip(&self.regularizations)
+        {
+            if let Some(regularization) = regularization {
+                regularization.decay(layer.as_mut(), self.learning_rate);
+            }
             layer.adjust_parameters(gradient, self.learning_rate);
         }
-        
-   
     }
 
-
     pub fn classify(&mut self, input: &Matrix) -> &Matrix {
+        self.forward(input, false)
+    }
+
+    /**
+     * Pré-processamento ajustado aos dados de treino, salvo no checkpoint junto com os parâmetros.
+     * train e classify recebem as entradas já pré-processadas (ver preprocess); predict aplica o pré-processamento.
+     */
+    pub fn add_preprocessor(&mut self, preprocessor: Preprocessor) {
+        self.preprocessors.push(preprocessor);
+    }
+
+    pub fn preprocessors(&self) -> &[Preprocessor] {
+        &self.preprocessors
+    }
+
+    pub fn preprocess(&self, input: &Matrix) -> Matrix {
+        self.preprocessors
+            .iter()
+            .fold(input.clone(), |data, preprocessor| preprocessor.transform(&data))
+    }
+
+    /**
+     * Classificação de entradas sem pré-processamento (dados brutos)
+     */
+    pub fn predict(&mut self, input: &Matrix) -> &Matrix {
+        let preprocessed = self.preprocess(input);
+        self.forward(&preprocessed, false)
+    }
+
+    /**
+     * Rótulos das classes de saída, salvos no checkpoint
+     */
+    pub fn set_label_encoder(&mut self, label_encoder: Option<LabelEncoder>) {
+        self.label_encoder = label_encoder;
+    }
+
+    pub fn label_encoder(&self) -> Option<&LabelEncoder> {
+        self.label_encoder.as_ref()
+    }
+
+    /**
+     * Propaga a entrada por toda a rede. O modo de treinamento é repassado às camadas
+     * antes da propagação, pois algumas (ex: BatchNorm) se comportam de forma diferente na inferência.
+     */
+    pub(crate) fn forward(&mut self, input: &Matrix, training: bool) -> &Matrix {
         assert!(!self.layers.is_empty());
+        self.layers
+            .iter_mut()
+            .for_each(|layer| layer.set_training(training));
         //Propaga a primeira camada (considera que a primeira camada é uma camada oculta)
         self.layers[0].propagate(input);
         //Propaga as camadas remanescentes
@@ -120,28 +266,30 @@ impl NeuralNetwork {
         output.expect("FAILED TO TAKE LAST LAYER").neurons()
     }
 
+    /**
+     * Retropropagação por toda a rede. Cada camada recebe ∂C/∂a da camada seguinte
+     * e devolve o ∂C/∂a da anterior, de forma que a rede não precisa conhecer o tipo das camadas.
+     * Com um lote de amostras (uma por coluna), o custo é a média do custo das amostras.
+     */
     pub fn generate_gradients(
         &mut self,
         input: Matrix,
         expected_output: Matrix,
     ) -> VecDeque<Gradient> {
-        let last_layer_index = self.layers.len() - 1;
         let mut gradients: VecDeque<Gradient> = VecDeque::with_capacity(self.layers.len());
-        {
-            let (hidden_layers, output_layers) = self.layers.split_at_mut(last_layer_index);
-            let gradient = output_layers[0].backpropagate_output_layer(
-                &expected_output,
-                hidden_layers[last_layer_index - 1].neurons(),
-                &NeuralNetwork::cost_derivative_mse,
-            );
-            // println!("LL Gradients are 0 ? Weights: {}, Biases: {}", gradient.weight.is_zero(),gradient.delta.is_zero());
-            gradients.push_front(gradient);
-        }
+        let output = self
+            .layers
+            .last()
+            .expect("FAILED TO TAKE LAST LAYER")
+            .neurons();
+        assert!(output.rows() == expected_output.rows() && output.cols() == expected_output.cols());
+
+        //∂C/∂a da camada de saída
+        let mut output_gradient = self.loss.gradient(output, &expected_output);
 
-        for i in (1..last_layer_index).rev() {
-            //slices [0..i) e [i..len()-1) (Novamente lidando com borrow checker)
+        for i in (0..self.layers.len()).rev() {
+            //slices [0..i) e [i..len()) (Novamente lidando com borrow checker)
             let (initial_layers, current_and_done_layers) = self.layers.split_at_mut(i);
-            let (current_layer, done_layers) = current_and_done_layers.split_at_mut(1);
 
             //Para a primeira camada oculta, a ativação prévia é a entrada
             let prev_activations = if i == 0 {
@@ -150,22 +298,135 @@ impl NeuralNetwork {
                 initial_layers[i - 1].neurons()
             };
 
-            let gradient = current_layer[0].backpropagate_hidden_layer(
-                &done_layers[0].weights(),
-                &gradients[0].delta,
-                prev_activations,
-            );
+            let (mut gradient, prev_output_gradient) =
+                current_and_done_layers[0].backpropagate(&output_gradient, prev_activations);
+            if let Some(regularization) = &self.regularizations[i] {
+                regularization.add_gradient(current_and_done_layers[0].as_ref(), &mut gradient);
+            }
             // println!("La Gradients are 0 ? Weights: {}, Biases: {}", gradient.weight.is_zero(),gradient.delta.is_zero());
             gradients.push_front(gradient);
+            output_gradient = prev_output_gradient;
         }
         gradients
     }
+
+    /**
+     * Salva os parâmetros (pesos, viéses e estado das camadas) em um arquivo binário.
+     * A arquitetura não é salva: o checkpoint deve ser carregado em uma rede montada com as mesmas camadas.
+     * Formato (big-endian): magic, número de camadas e, para cada camada,
+     * o número de matrizes seguido das matrizes (ver Matrix::write_to).
+     * Depois das camadas vêm o número de pré-processamentos e cada um deles (ver Preprocessor::write_to)
+     * e um u32 indicando se há LabelEncoder, seguido dele. Checkpoints sem essa parte continuam válidos.
+     */
+    pub fn save_checkpoint(&self, file_name: &str) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(file_name)?);
+        file.write_all(&CHECKPOINT_MAGIC.to_be_bytes())?;
+        file.write_all(&(self.layers.len() as u32).to_be_bytes())?;
+        for layer in &self.layers {
+            let state = layer.state();
+            file.write_all(&(2 + state.len() as u32).to_be_bytes())?;
+            layer.weights().write_to(&mut file)?;
+            layer.biases().write_to(&mut file)?;
+            for matrix in &state {
+                matrix.write_to(&mut file)?;
+            }
+        }
+        file.write_all(&(self.preprocessors.len() as u32).to_be_bytes())?;
+        for preprocessor in &self.preprocessors {
+            preprocessor.write_to(&mut file)?;
+        }
+        match &self.label_encoder {
+            Some(label_encoder) => {
+                file.write_all(&1u32.to_be_bytes())?;
+                label_encoder.write_to(&mut file)?;
+            }
+            None => file.write_all(&0u32.to_be_bytes())?,
+        }
+        file.flush()
+    }
+
+    pub fn load_checkpoint(&mut self, file_name: &str) -> std::io::Result<()> {
+        let mut file = std::io::BufReader::new(std::fs::File::open(file_name)?);
+        let mut u32_buffer = [0u8; 4];
+        file.read_exact(&mut u32_buffer)?;
+        if u32::from_be_bytes(u32_buffer) != CHECKPOINT_MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Arquivo não é um checkpoint",
+            ));
+        }
+        file.read_exact(&mut u32_buffer)?;
+        if u32::from_be_bytes(u32_buffer) as usize != self.layers.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Número de camadas do checkpoint difere da rede",
+            ));
+        }
+        for layer in self.layers.iter_mut() {
+            file.read_exact(&mut u32_buffer)?;
+            let num_matrices = u32::from_be_bytes(u32_buffer) as usize;
+            if num_matrices < 2 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Camada sem pesos e viéses no checkpoint",
+                ));
+            }
+            let weights = Matrix::read_from(&mut file)?;
+            let biases = Matrix::read_from(&mut file)?;
+            if !same_shape(&weights, layer.weights()) || !same_shape(&biases, layer.biases()) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Dimensões dos parâmetros do checkpoint diferem da rede",
+                ));
+            }
+            layer.fix_weights(weights);
+            layer.fix_bias(biases);
+            let mut state = Vec::with_capacity(num_matrices - 2);
+            for _ in 2..num_matrices {
+                state.push(Matrix::read_from(&mut file)?);
+            }
+            layer.fix_state(state);
+        }
+
+        //Checkpoints antigos terminam depois das camadas
+        match file.read_exact(&mut u32_buffer) {
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+                self.preprocessors.clear();
+                self.label_encoder = None;
+                return Ok(());
+            }
+            result => result?,
+        }
+        let num_preprocessors = u32::from_be_bytes(u32_buffer);
+        let mut preprocessors = Vec::new();
+        for _ in 0..num_preprocessors {
+            preprocessors.push(Preprocessor::read_from(&mut file)?);
+        }
+        file.read_exact(&mut u32_buffer)?;
+        self.label_encoder = match u32::from_be_bytes(u32_buffer) {
+            0 => None,
+            _ => Some(LabelEncoder::read_from(&mut file)?),
+        };
+        self.preprocessors = preprocessors;
+        Ok(())
+    }
+}
+
+fn same_shape(a: &Matrix, b: &Matrix) -> bool {
+    a.rows() == b.rows() && a.cols() == b.cols()
 }
 
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
     use super::*;
+    use crate::nn_clipping::GradientClipping;
+    use crate::nn_layer::{Identity, Layer, Relu, Sigmoid, Tanh};
+    use crate::nn_loss::{BinaryCrossEntropy, Huber, MeanAbsoluteError};
+    use crate::nn_metrics::{MultiLabelMetrics, RegressionMetrics};
+    use crate::nn_normalization::{BatchNorm, LayerNorm};
+    use crate::nn_preprocessing::{MinMaxScaler, Standardizer};
+    use crate::nn_regularization::Regularization;
     #[test]
     fn test_train() {
         // let mut network = NeuralNetwork::new(2, 0.4);
@@ -177,4 +438,300 @@ mod tests {
         // // let output_layer = Layer::new::<Sigmoid>(128, 10);
         // let output_layer = Layer::new::<Relu>(56, 10);
     }
+
+    #[test]
+    fn test_checkpoint() {
+        let build_network = || {
+            let mut network = NeuralNetwork::new(3, 0.1);
+            network.add_layer(Layer::new::<Relu>(4, 3));
+            network.add_layer(BatchNorm::new_1d(3));
+            network.add_layer(Layer::new::<Sigmoid>(3, 2));
+            network
+        };
+        let mut network = build_network();
+        let input = Matrix::from_vec(4, 2, vec![0.1, 0.9, 0.4, 0.3, 0.7, 0.2, 0.5, 0.6]);
+        network.train(
+            input.clone(),
+            Matrix::from_vec(2, 2, vec![1.0, 0.0, 0.0, 1.0]),
+        );
+
+        let file_name = std::env::temp_dir().join("nn_checkpoint_test.bin");
+        let file_name = file_name.to_str().unwrap();
+        network.save_checkpoint(file_name).unwrap();
+
+        let mut restored = build_network();
+        restored.load_checkpoint(file_name).unwrap();
+        std::fs::remove_file(file_name).unwrap();
+
+        for i in 1..=3 {
+            assert!(network.borrow_layer(i).weights() == restored.borrow_layer(i).weights());
+            assert!(network.borrow_layer(i).biases() == restored.borrow_layer(i).biases());
+        }
+        //Estatísticas acumuladas da BatchNorm também são restauradas
+        assert!(network.borrow_layer(2).state()[0] == restored.borrow_layer(2).state()[0]);
+        assert!(network.classify(&input) == restored.classify(&input));
+    }
+
+    #[test]
+    fn test_checkpoint_preprocessing() {
+        let build_network = || {
+            let mut network = NeuralNetwork::new(1, 0.1);
+            network.add_layer(Layer::new::<Sigmoid>(2, 2));
+            network
+        };
+        let raw = [Matrix::from_vec(2, 3, vec![10.0, 20.0, 30.0, 0.1, 0.2, 0.6])];
+        //Cada pré-processamento é ajustado à saída do anterior
+        let scaler = MinMaxScaler::fit(&raw);
+        let scaled = [scaler.transform(&raw[0])];
+        let mut network = build_network();
+        network.add_preprocessor(Preprocessor::MinMax(scaler));
+        network.add_preprocessor(Preprocessor::Standard(Standardizer::fit_global(&scaled)));
+        network.set_label_encoder(Some(LabelEncoder::fit(&["gato", "cachorro"])));
+        let input = Matrix::from_vec(2, 1, vec![25.0, 0.3]);
+        let preprocessed = network.preprocess(&input);
+        assert!(preprocessed != input);
+        let output = network.predict(&input).clone();
+        assert!(output == *network.classify(&preprocessed));
+
+        let file_name = std::env::temp_dir().join("nn_checkpoint_preprocessing_test.bin");
+        let file_name = file_name.to_str().unwrap();
+        network.save_checkpoint(file_name).unwrap();
+        let mut restored = build_network();
+        restored.load_checkpoint(file_name).unwrap();
+        assert!(restored.preprocessors().len() == 2);
+        assert!(restored.preprocess(&input) == preprocessed);
+        assert!(*restored.predict(&input) == output);
+        assert!(restored.label_encoder().unwrap().decode(1) == "cachorro");
+
+        //Checkpoint sem a parte de pré-processamento (formato anterior)
+        build_network().save_checkpoint(file_name).unwrap();
+        let bytes = std::fs::read(file_name).unwrap();
+        std::fs::write(file_name, &bytes[..bytes.len() - 8]).unwrap();
+        restored.load_checkpoint(file_name).unwrap();
+        assert!(restored.preprocessors().is_empty() && restored.label_encoder().is_none());
+        std::fs::remove_file(file_name).unwrap();
+    }
+
+    #[test]
+    fn test_train_with_layernorm() {
+        let mut network = NeuralNetwork::new(3, 0.2);
+        network.add_layer(Layer::new::<Sigmoid>(3, 8));
+        network.add_layer(LayerNorm::new(8));
+        network.add_layer(Layer::new::<Sigmoid>(8, 2));
+        let input = Matrix::from_vec(3, 1, vec![0.2, 0.7, 0.1]);
+        let expected = Matrix::from_vec(2, 1, vec![1.0, 0.0]);
+        let squared_error = |output: &Matrix| -> f64 {
+            (0..2).map(|i| (output[i][0] - expected[i][0]).powi(2)).sum()
+        };
+
+        let initial_error = squared_error(network.classify(&input));
+        //Treinamento amostra a amostra, sem lotes
+        for _ in 0..50 {
+            network.train(input.clone(), expected.clone());
+        }
+        let final_error = squared_error(network.classify(&input));
+        println!("Error: {} -> {}", initial_error, final_error);
+        assert!(final_error < initial_error);
+    }
+
+    #[test]
+    fn test_train_regression() {
+        //y = 30x0 - 20x1 + 100, com as saídas padronizadas
+        let inputs: Vec<Matrix> = (0..20)
+            .map(|i| {
+                let x = i as f64 / 20.0;
+                Matrix::from_vec(2, 1, vec![x, (x * 7.0).sin()])
+            })
+            .collect();
+        let targets: Vec<Matrix> = inputs
+            .iter()
+            .map(|x| Matrix::from_vec(1, 1, vec![30.0 * x[0][0] - 20.0 * x[1][0] + 100.0]))
+            .collect();
+        let standardizer = Standardizer::fit(&targets);
+
+        let evaluate = |network: &mut NeuralNetwork| -> RegressionMetrics {
+            let mut metrics = RegressionMetrics::new(1);
+            for (input, target) in inputs.iter().zip(&targets) {
+                let prediction = standardizer.inverse_transform(network.classify(input));
+                metrics.add(&prediction, target);
+            }
+            metrics
+        };
+
+        for huber in [true, false] {
+            let mut network = NeuralNetwork::new(2, 0.05);
+            network.add_layer(Layer::new::<Tanh>(2, 8));
+            network.add_layer(Layer::new::<Identity>(8, 1));
+            if huber {
+                network.set_loss(Huber::new(1.0));
+            } else {
+                network.set_loss(MeanAbsoluteError {});
+            }
+            let initial_rmse = evaluate(&mut network).rmse();
+            for _ in 0..300 {
+                for (input, target) in inputs.iter().zip(&targets) {
+                    network.train(input.clone(), standardizer.transform(target));
+                }
+            }
+            let metrics = evaluate(&mut network);
+            print!("{}", metrics.report());
+            assert!(metrics.rmse() < initial_rmse);
+            assert!(metrics.r2() > 0.9);
+        }
+    }
+
+    #[test]
+    fn test_train_multi_label() {
+        //Rótulos independentes: x0 > 0.5, x1 > 0.5 e (x0 + x1) > 1; uma amostra pode ter os três
+        let samples: Vec<(Matrix, Matrix)> = (0..25)
+            .map(|i| {
+                let x0 = (i % 5) as f64 / 4.0;
+                let x1 = (i / 5) as f64 / 4.0;
+                let label = |condition: bool| if condition { 1.0 } else { 0.0 };
+                (
+                    Matrix::from_vec(2, 1, vec![x0, x1]),
+                    Matrix::from_vec(
+                        3,
+                        1,
+                        vec![label(x0 > 0.5), label(x1 > 0.5), label(x0 + x1 > 1.0)],
+                    ),
+                )
+            })
+            .collect();
+
+        let mut network = NeuralNetwork::new(2, 0.5);
+        network.add_layer(Layer::new::<Tanh>(2, 8));
+        network.add_layer(Layer::new::<Sigmoid>(8, 3));
+        network.set_loss(BinaryCrossEntropy {});
+        for _ in 0..500 {
+            for (input, target) in &samples {
+                network.train(input.clone(), target.clone());
+            }
+        }
+
+        let mut metrics = MultiLabelMetrics::new(3, 0.5);
+        for (input, target) in &samples {
+            metrics.add(network.classify(input), target);
+        }
+        print!("{}", metrics.report());
+        assert!(metrics.hamming_loss() < 0.1);
+        assert!(metrics.mean_auc().unwrap() > 0.95);
+    }
+
+    #[test]
+    fn test_regularization() {
+        let inputs: Vec<Matrix> = (0..8)
+            .map(|i| Matrix::from_vec(3, 1, vec![i as f64 / 8.0, (i % 3) as f64 / 3.0, 0.5]))
+            .collect();
+        let weights_norm = |network: &NeuralNetwork| -> f64 {
+            (1..=network.num_layers())
+                .map(|layer| {
+                    let weights = network.borrow_layer(layer).weights().data();
+                    weights.iter().map(|w| w * w).sum::<f64>()
+                })
+                .sum()
+        };
+        //Mesmos pesos iniciais (grandes) em todas as redes
+        let initial_weights = [
+            Matrix::new_random_glorot(6, 3).scalar_product(3.0),
+            Matrix::new_random_glorot(1, 6).scalar_product(3.0),
+        ];
+        let train = |regularization: Option<Regularization>| -> NeuralNetwork {
+            let mut network = NeuralNetwork::new(2, 0.2);
+            network.add_layer(Layer::new::<Tanh>(3, 6));
+            network.add_layer(Layer::new::<Sigmoid>(6, 1));
+            for (layer, weights) in initial_weights.iter().enumerate() {
+                network
+                    .borrow_layer_mut(layer + 1)
+                    .fix_weights(weights.clone());
+            }
+            if let Some(regularization) = regularization {
+                network.set_regularization_all(regularization);
+            }
+            for _ in 0..200 {
+                for input in &inputs {
+                    network.train(input.clone(), Matrix::from_vec(1, 1, vec![input[0][0]]));
+                }
+            }
+            network
+        };
+
+        let mut network = train(None);
+        let unregularized_norm = weights_norm(&network);
+        let expected = Matrix::from_vec(1, 1, vec![0.5]);
+        network.classify(&inputs[4]);
+        let unregularized_cost = network.cost(&expected);
+        network.set_regularization_all(Regularization::l2(0.1));
+        //A penalidade é somada ao custo reportado
+        assert!(network.regularization_penalty() > 0.0);
+        assert!(
+            (network.cost(&expected) - unregularized_cost - network.regularization_penalty()).abs()
+                < 1e-12
+        );
+
+        for regularization in [
+            Regularization::l1(0.01),
+            Regularization::l2(0.05),
+            Regularization::elastic_net(0.005, 0.02),
+            Regularization::weight_decay(0.05),
+        ] {
+            let network = train(Some(regularization));
+            println!(
+                "{:?}: {} (sem regularização: {})",
+                regularization,
+                weights_norm(&network),
+                unregularized_norm
+            );
+            assert!(weights_norm(&network) < unregularized_norm);
+            //Viéses não são regularizados por padrão, mas continuam sendo treinados
+            assert!(!network.borrow_layer(2).biases().is_zero());
+        }
+    }
+
+    #[test]
+    fn test_gradient_clipping() {
+        //Entradas grandes e saída linear: gradientes muito maiores que o limite
+        let input = Matrix::from_vec(3, 1, vec![50.0, -40.0, 30.0]);
+        let expected = Matrix::from_vec(2, 1, vec![100.0, -100.0]);
+        for clipping in [
+            GradientClipping::Value(0.5),
+            GradientClipping::LayerNorm(1.0),
+            GradientClipping::GlobalNorm(1.0),
+        ] {
+            let mut network = NeuralNetwork::new(1, 0.1);
+            network.add_layer(Layer::new::<Identity>(3, 2));
+            network.set_gradient_clipping(Some(clipping));
+            let weights = network.borrow_layer(1).weights().clone();
+            let biases = network.borrow_layer(1).biases().clone();
+            network.train(input.clone(), expected.clone());
+
+            //Variação dos parâmetros = taxa de aprendizado * gradiente limitado
+            let layer = network.borrow_layer(1);
+            let changes: Vec<f64> = weights
+                .data()
+                .iter()
+                .zip(layer.weights().data())
+                .chain(biases.data().iter().zip(layer.biases().data()))
+                .map(|(before, after)| (before - after) / 0.1)
+                .collect();
+            let norm = changes
+                .iter()
+                .map(|change| change * change)
+                .sum::<f64>()
+                .sqrt();
+            println!(
+                "{:?}: norma antes {}, depois {}",
+                clipping,
+                network.last_gradient_norm(),
+                norm
+            );
+            assert!(network.last_gradient_norm() > 100.0);
+            match clipping {
+                GradientClipping::Value(limit) => {
+                    assert!(changes.iter().all(|change| change.abs() <= limit + 1e-9))
+                }
+                _ => assert!((norm - 1.0).abs() < 1e-9),
+            }
+        }
+    }
 }
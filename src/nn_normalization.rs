@@ -0,0 +1,514 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://arxiv.org/abs/1502.03167
+use crate::nn_layer::{Gradient, NetworkLayer};
+use crate::nn_matrix::Matrix;
+
+const NORM_EPSILON: f64 = 1e-5; //Evita divisão por zero quando a variância é nula
+const DEFAULT_MOMENTUM: f64 = 0.1;
+
+/**
+ * Normalização em lote (Batch Normalization).
+ * Cada linha da entrada é uma característica e cada coluna uma amostra do lote.
+ *
+ * Versão 1d: cada característica é normalizada com a média e variância do lote.
+ * Versão 2d: a entrada é um mapa de características achatado por canal
+ *   (linhas = canais * altura * largura, com os pixels de cada canal contíguos),
+ *   e a normalização usa as estatísticas de todos os pixels do canal em todo o lote.
+ *
+ * y = gamma * (x - média) / sqrt(variância + ε) + beta
+ *
+ * Durante o treinamento, as médias e variâncias acumuladas são atualizadas com momentum,
+ * e são usadas no lugar das estatísticas do lote durante a inferência.
+ * Com lotes de uma única amostra (NeuralNetwork::train com uma coluna) a variância é nula
+ * e a saída é sempre beta: para esse caso a LayerNorm é mais adequada.
+ */
+pub struct BatchNorm {
+    channels: usize,
+    spatial_size: usize, //Pixels por canal. 1 para a versão 1d
+    gamma: Matrix,
+    beta: Matrix,
+    running_mean: Matrix,
+    running_var: Matrix,
+    momentum: f64,
+    training: bool,
+    neurons: Matrix,
+    //Valores guardados para a retropropagação
+    normalized: Matrix,
+    inv_std: Matrix,
+}
+
+impl BatchNorm {
+    pub fn new_1d(features: usize) -> BatchNorm {
+        BatchNorm::new_2d(features, 1, 1)
+    }
+
+    pub fn new_2d(channels: usize, height: usize, width: usize) -> BatchNorm {
+        let mut gamma = Matrix::new(channels, 1);
+        gamma.mut_map(|_| 1.0);
+        let mut running_var = Matrix::new(channels, 1);
+        running_var.mut_map(|_| 1.0);
+        BatchNorm {
+            channels,
+            spatial_size: height * width,
+            gamma,
+            beta: Matrix::new(channels, 1),
+            running_mean: Matrix::new(channels, 1),
+            running_var,
+            momentum: DEFAULT_MOMENTUM,
+            training: false,
+            neurons: Matrix::new(channels * height * width, 1),
+            normalized: Matrix::new(channels * height * width, 1),
+            inv_std: Matrix::new(channels, 1),
+        }
+    }
+
+    /**
+     * Fração das estatísticas do lote incorporada às estatísticas acumuladas a cada passo de treinamento.
+     */
+    pub fn set_momentum(&mut self, momentum: f64) {
+        assert!((0.0..=1.0).contains(&momentum));
+        self.momentum = momentum;
+    }
+
+    pub fn running_mean(&self) -> &Matrix {
+        &self.running_mean
+    }
+    pub fn running_var(&self) -> &Matrix {
+        &self.running_var
+    }
+
+    //Linhas da entrada que pertencem ao canal
+    fn channel_rows(&self, channel: usize) -> std::ops::Range<usize> {
+        channel * self.spatial_size..(channel + 1) * self.spatial_size
+    }
+
+    //Soma de f(valor) de todos os elementos do canal, em todas as amostras
+    fn channel_sum(&self, matrix: &Matrix, channel: usize, f: impl Fn(f64) -> f64) -> f64 {
+        self.channel_rows(channel)
+            .map(|i| matrix[i].iter().map(|&v| f(v)).sum::<f64>())
+            .sum()
+    }
+}
+
+impl NetworkLayer for BatchNorm {
+    fn propagate(&mut self, input: &Matrix) {
+        assert!(input.rows() == self.channels * self.spatial_size);
+        let count = (self.spatial_size * input.cols()) as f64;
+        self.normalized = Matrix::new(input.rows(), input.cols());
+        self.neurons = Matrix::new(input.rows(), input.cols());
+
+        for c in 0..self.channels {
+            let (mean, var) = if self.training {
+                let mean = self.channel_sum(input, c, |v| v) / count;
+                let var = self.channel_sum(input, c, |v| (v - mean).powi(2)) / count;
+                //Estatísticas acumuladas usam a variância não enviesada (n-1)
+                let unbiased_var = if count > 1.0 {
+                    var * count / (count - 1.0)
+                } else {
+                    var
+                };
+                self.running_mean[c][0] =
+                    (1.0 - self.momentum) * self.running_mean[c][0] + self.momentum * mean;
+                self.running_var[c][0] =
+                    (1.0 - self.momentum) * self.running_var[c][0] + self.momentum * unbiased_var;
+                (mean, var)
+            } else {
+                (self.running_mean[c][0], self.running_var[c][0])
+            };
+            let inv_std = 1.0 / (var + NORM_EPSILON).sqrt();
+            self.inv_std[c][0] = inv_std;
+
+            for i in self.channel_rows(c) {
+                for j in 0..input.cols() {
+                    let x_hat = (input[i][j] - mean) * inv_std;
+                    self.normalized[i][j] = x_hat;
+                    self.neurons[i][j] = self.gamma[c][0] * x_hat + self.beta[c][0];
+                }
+            }
+        }
+    }
+
+    fn neurons(&self) -> &Matrix {
+        &self.neurons
+    }
+    //Os pesos da BatchNorm são os fatores de escala gamma
+    fn weights(&self) -> &Matrix {
+        &self.gamma
+    }
+    //Os viéses da BatchNorm são os deslocamentos beta
+    fn biases(&self) -> &Matrix {
+        &self.beta
+    }
+    fn fix_weights(&mut self, weights: Matrix) {
+        assert!(weights.rows() == self.channels && weights.cols() == 1);
+        self.gamma = weights;
+    }
+    fn fix_bias(&mut self, biases: Matrix) {
+        assert!(biases.rows() == self.channels && biases.cols() == 1);
+        self.beta = biases;
+    }
+
+    /**
+     * Com x̂ = (x - μ) / σ e m elementos por canal:
+     * ∂C/∂gamma = sum(∂C/∂y * x̂)
+     * ∂C/∂beta = sum(∂C/∂y)
+     * ∂C/∂x̂ = ∂C/∂y * gamma
+     * ∂C/∂x = 1/(m*σ) * (m * ∂C/∂x̂ - sum(∂C/∂x̂) - x̂ * sum(∂C/∂x̂ * x̂))
+     * Os dois últimos termos vêm da dependência de μ e σ em relação a x. Na inferência, μ e σ são constantes
+     * e ∂C/∂x = ∂C/∂x̂ / σ
+     */
+    fn backpropagate(
+        &mut self,
+        output_gradient: &Matrix,
+        _prev_activations: &Matrix,
+    ) -> (Gradient, Matrix) {
+        assert!(
+            output_gradient.rows() == self.normalized.rows()
+                && output_gradient.cols() == self.normalized.cols()
+        );
+        let count = (self.spatial_size * output_gradient.cols()) as f64;
+        let mut gamma_gradient = Matrix::new(self.channels, 1);
+        let mut beta_gradient = Matrix::new(self.channels, 1);
+        let mut input_gradient = Matrix::new(output_gradient.rows(), output_gradient.cols());
+
+        for c in 0..self.channels {
+            let mut sum_dy = 0.0;
+            let mut sum_dy_x_hat = 0.0;
+            for i in self.channel_rows(c) {
+                for j in 0..output_gradient.cols() {
+                    sum_dy += output_gradient[i][j];
+                    sum_dy_x_hat += output_gradient[i][j] * self.normalized[i][j];
+                }
+            }
+            gamma_gradient[c][0] = sum_dy_x_hat;
+            beta_gradient[c][0] = sum_dy;
+
+            let gamma = self.gamma[c][0];
+            let inv_std = self.inv_std[c][0];
+            for i in self.channel_rows(c) {
+                for j in 0..output_gradient.cols() {
+                    input_gradient[i][j] = if self.training {
+                        gamma * inv_std / count
+                            * (count * output_gradient[i][j]
+                                - sum_dy
+                                - self.normalized[i][j] * sum_dy_x_hat)
+                    } else {
+                        gamma * inv_std * output_gradient[i][j]
+                    };
+                }
+            }
+        }
+        (
+            Gradient {
+                weight: gamma_gradient,
+                delta: beta_gradient,
+                rows: None,
+            },
+            input_gradient,
+        )
+    }
+
+    fn adjust_parameters(&mut self, gradients: &mut Gradient, learning_rate: f64) {
+        self.gamma -= gradients.weight.mut_scalar_product(learning_rate);
+        self.beta -= gradients.delta.mut_scalar_product(learning_rate);
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    fn state(&self) -> Vec<Matrix> {
+        vec![self.running_mean.clone(), self.running_var.clone()]
+    }
+
+    fn fix_state(&mut self, state: Vec<Matrix>) {
+        assert!(state.len() == 2);
+        let mut state = state.into_iter();
+        let running_mean = state.next().unwrap();
+        let running_var = state.next().unwrap();
+        assert!(running_mean.rows() == self.channels && running_var.rows() == self.channels);
+        self.running_mean = running_mean;
+        self.running_var = running_var;
+    }
+}
+
+/**
+ * Normalização por camada (Layer Normalization).
+ * Cada amostra (coluna) é normalizada com a média e a variância das suas próprias características,
+ * de forma que o resultado independe do tamanho do lote e funciona com o treinamento amostra a amostra.
+ *
+ * y = gamma * (x - média) / sqrt(variância + ε) + beta
+ *
+ * gamma e beta possuem um valor por característica. Não há estatísticas acumuladas:
+ * treinamento e inferência se comportam da mesma forma.
+ */
+pub struct LayerNorm {
+    features: usize,
+    gamma: Matrix,
+    beta: Matrix,
+    neurons: Matrix,
+    //Valores guardados para a retropropagação
+    normalized: Matrix,
+    inv_std: Matrix, //Um valor por amostra (1 x colunas)
+}
+
+impl LayerNorm {
+    pub fn new(features: usize) -> LayerNorm {
+        let mut gamma = Matrix::new(features, 1);
+        gamma.mut_map(|_| 1.0);
+        LayerNorm {
+            features,
+            gamma,
+            beta: Matrix::new(features, 1),
+            neurons: Matrix::new(features, 1),
+            normalized: Matrix::new(features, 1),
+            inv_std: Matrix::new(1, 1),
+        }
+    }
+}
+
+impl NetworkLayer for LayerNorm {
+    fn propagate(&mut self, input: &Matrix) {
+        assert!(input.rows() == self.features);
+        let count = self.features as f64;
+        self.normalized = Matrix::new(input.rows(), input.cols());
+        self.neurons = Matrix::new(input.rows(), input.cols());
+        self.inv_std = Matrix::new(1, input.cols());
+
+        for j in 0..input.cols() {
+            let mean = (0..self.features).map(|i| input[i][j]).sum::<f64>() / count;
+            let var = (0..self.features)
+                .map(|i| (input[i][j] - mean).powi(2))
+                .sum::<f64>()
+                / count;
+            let inv_std = 1.0 / (var + NORM_EPSILON).sqrt();
+            self.inv_std[0][j] = inv_std;
+            for i in 0..self.features {
+                let x_hat = (input[i][j] - mean) * inv_std;
+                self.normalized[i][j] = x_hat;
+                self.neurons[i][j] = self.gamma[i][0] * x_hat + self.beta[i][0];
+            }
+        }
+    }
+
+    fn neurons(&self) -> &Matrix {
+        &self.neurons
+    }
+    //Assim como na BatchNorm, os pesos são os fatores de escala gamma e os viéses os deslocamentos beta
+    fn weights(&self) -> &Matrix {
+        &self.gamma
+    }
+    fn biases(&self) -> &Matrix {
+        &self.beta
+    }
+    fn fix_weights(&mut self, weights: Matrix) {
+        assert!(weights.rows() == self.features && weights.cols() == 1);
+        self.gamma = weights;
+    }
+    fn fix_bias(&mut self, biases: Matrix) {
+        assert!(biases.rows() == self.features && biases.cols() == 1);
+        self.beta = biases;
+    }
+
+    /**
+     * Mesmas fórmulas da BatchNorm, mas as somas percorrem as n características de cada amostra:
+     * ∂C/∂x̂ = ∂C/∂y * gamma
+     * ∂C/∂x = 1/(n*σ) * (n * ∂C/∂x̂ - sum(∂C/∂x̂) - x̂ * sum(∂C/∂x̂ * x̂))
+     * ∂C/∂gamma e ∂C/∂beta acumulam as contribuições de todas as amostras do lote.
+     */
+    fn backpropagate(
+        &mut self,
+        output_gradient: &Matrix,
+        _prev_activations: &Matrix,
+    ) -> (Gradient, Matrix) {
+        assert!(
+            output_gradient.rows() == self.normalized.rows()
+                && output_gradient.cols() == self.normalized.cols()
+        );
+        let count = self.features as f64;
+        let mut gamma_gradient = Matrix::new(self.features, 1);
+        let mut input_gradient = Matrix::new(output_gradient.rows(), output_gradient.cols());
+
+        for j in 0..output_gradient.cols() {
+            let mut sum_dx_hat = 0.0;
+            let mut sum_dx_hat_x_hat = 0.0;
+            for i in 0..self.features {
+                let dx_hat = output_gradient[i][j] * self.gamma[i][0];
+                sum_dx_hat += dx_hat;
+                sum_dx_hat_x_hat += dx_hat * self.normalized[i][j];
+                gamma_gradient[i][0] += output_gradient[i][j] * self.normalized[i][j];
+            }
+            let inv_std = self.inv_std[0][j];
+            for i in 0..self.features {
+                let dx_hat = output_gradient[i][j] * self.gamma[i][0];
+                input_gradient[i][j] = inv_std / count
+                    * (count * dx_hat - sum_dx_hat - self.normalized[i][j] * sum_dx_hat_x_hat);
+            }
+        }
+        (
+            Gradient {
+                weight: gamma_gradient,
+                delta: output_gradient.sum_columns(),
+                rows: None,
+            },
+            input_gradient,
+        )
+    }
+
+    fn adjust_parameters(&mut self, gradients: &mut Gradient, learning_rate: f64) {
+        self.gamma -= gradients.weight.mut_scalar_product(learning_rate);
+        self.beta -= gradients.delta.mut_scalar_product(learning_rate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_gradient_check::check_layer_gradients;
+
+    #[test]
+    fn test_batchnorm_1d_normalizes_batch() {
+        let mut batch_norm = BatchNorm::new_1d(2);
+        batch_norm.set_training(true);
+        //2 características, 4 amostras
+        let input = Matrix::from_vec(2, 4, vec![1.0, 2.0, 3.0, 4.0, 10.0, 10.0, 30.0, 30.0]);
+        batch_norm.propagate(&input);
+        println!("Normalized: {}", batch_norm.neurons());
+
+        let output = batch_norm.neurons();
+        for i in 0..2 {
+            let mean: f64 = output[i].iter().sum::<f64>() / 4.0;
+            let var: f64 = output[i].iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 4.0;
+            assert!(mean.abs() < 1e-9);
+            assert!((var - 1.0).abs() < 1e-3);
+        }
+
+        //Momentum 0.1 partindo de média 0 e variância 1
+        //Variância não enviesada: [1.25 * 4/3, 100 * 4/3]
+        let expected_mean = Matrix::from_vec(2, 1, vec![0.25, 2.0]);
+        let expected_var =
+            Matrix::from_vec(2, 1, vec![0.9 + 0.1 * 5.0 / 3.0, 0.9 + 0.1 * 400.0 / 3.0]);
+        assert!(*batch_norm.running_mean() == expected_mean);
+        assert!(*batch_norm.running_var() == expected_var);
+    }
+
+    #[test]
+    fn test_batchnorm_inference_uses_running_stats() {
+        let mut batch_norm = BatchNorm::new_1d(2);
+        batch_norm.fix_state(vec![
+            Matrix::from_vec(2, 1, vec![1.0, -2.0]),
+            Matrix::from_vec(2, 1, vec![4.0, 0.25]),
+        ]);
+        batch_norm.fix_weights(Matrix::from_vec(2, 1, vec![2.0, 1.0]));
+        batch_norm.fix_bias(Matrix::from_vec(2, 1, vec![0.5, 0.0]));
+        batch_norm.set_training(false);
+        batch_norm.propagate(&Matrix::from_vec(2, 1, vec![3.0, -1.0]));
+
+        //[2 * (3-1)/2 + 0.5, (-1+2)/0.5]
+        let expected = Matrix::from_vec(2, 1, vec![2.5, 2.0]);
+        let output = batch_norm.neurons();
+        println!("Output: {}", output);
+        assert!((output[0][0] - expected[0][0]).abs() < 1e-5);
+        assert!((output[1][0] - expected[1][0]).abs() < 1e-4);
+        //Inferência não altera as estatísticas
+        assert!(batch_norm.running_mean()[0][0] == 1.0);
+    }
+
+    #[test]
+    fn test_batchnorm_2d_channel_statistics() {
+        //2 canais de 2x1 pixels, 2 amostras
+        let mut batch_norm = BatchNorm::new_2d(2, 2, 1);
+        batch_norm.set_training(true);
+        let input = Matrix::from_vec(4, 2, vec![1.0, 3.0, 5.0, 7.0, 100.0, 100.0, 100.0, 100.0]);
+        batch_norm.propagate(&input);
+        println!("Normalized: {}", batch_norm.neurons());
+        //Média do primeiro canal é 4, do segundo 100 (com variância nula a saída é beta)
+        assert!(*batch_norm.running_mean() == Matrix::from_vec(2, 1, vec![0.4, 10.0]));
+        assert!(batch_norm.neurons()[2].iter().all(|v| *v == 0.0));
+        assert!(batch_norm.neurons()[0][0] < 0.0 && batch_norm.neurons()[1][1] > 0.0);
+    }
+
+    #[test]
+    fn test_batchnorm_backpropagate() {
+        let mut batch_norm = BatchNorm::new_2d(2, 2, 1);
+        batch_norm.fix_weights(Matrix::from_vec(2, 1, vec![1.5, -0.5]));
+        batch_norm.fix_bias(Matrix::from_vec(2, 1, vec![0.1, 0.2]));
+        batch_norm.set_training(true);
+        let input = Matrix::from_vec(
+            4,
+            3,
+            vec![
+                0.5, -1.0, 2.0, 1.5, 0.3, -0.7, 3.0, 2.5, -1.5, 0.8, 0.1, 0.4,
+            ],
+        );
+        let cost_weights = Matrix::from_vec(
+            4,
+            3,
+            vec![
+                0.3, -0.2, 0.9, 1.1, 0.4, -0.6, 0.2, 0.7, -1.3, 0.5, 0.05, 0.8,
+            ],
+        );
+
+        //Compara com diferenças finitas centrais
+        check_layer_gradients(&mut batch_norm, &input, &cost_weights, 1e-5);
+        batch_norm.propagate(&input);
+        let (gradient, _) = batch_norm.backpropagate(&cost_weights, &input);
+        //∂C/∂beta = soma dos pesos do custo em cada canal
+        assert!(gradient.delta == Matrix::from_vec(2, 1, vec![1.9, 0.95]));
+    }
+
+    #[test]
+    fn test_layernorm_normalizes_each_sample() {
+        let mut layer_norm = LayerNorm::new(4);
+        //Cada coluna é normalizada independentemente, mesmo com uma única amostra
+        let input = Matrix::from_vec(4, 2, vec![1.0, -3.0, 2.0, 0.0, 3.0, 3.0, 4.0, 6.0]);
+        layer_norm.propagate(&input);
+        println!("Normalized: {}", layer_norm.neurons());
+
+        for j in 0..2 {
+            let column = layer_norm.neurons().column(j);
+            let mean: f64 = column.data().iter().sum::<f64>() / 4.0;
+            let var: f64 = column
+                .data()
+                .iter()
+                .map(|v| (v - mean).powi(2))
+                .sum::<f64>()
+                / 4.0;
+            assert!(mean.abs() < 1e-9);
+            assert!((var - 1.0).abs() < 1e-3);
+        }
+
+        layer_norm.propagate(&input.column(0));
+        assert!((layer_norm.neurons()[0][0] + 1.3416).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_layernorm_backpropagate() {
+        let mut layer_norm = LayerNorm::new(3);
+        layer_norm.fix_weights(Matrix::from_vec(3, 1, vec![1.5, -0.5, 0.8]));
+        layer_norm.fix_bias(Matrix::from_vec(3, 1, vec![0.1, 0.2, -0.3]));
+        let input = Matrix::from_vec(3, 2, vec![0.5, -1.0, 2.0, 1.5, 0.3, -0.7]);
+        let cost_weights = Matrix::from_vec(3, 2, vec![0.3, -0.2, 0.9, 1.1, 0.4, -0.6]);
+
+        check_layer_gradients(&mut layer_norm, &input, &cost_weights, 1e-5);
+        layer_norm.propagate(&input);
+        let (gradient, _) = layer_norm.backpropagate(&cost_weights, &input);
+        assert!(gradient.delta == Matrix::from_vec(3, 1, vec![0.1, 2.0, -0.2]));
+    }
+}
@@ -0,0 +1,289 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::thread::JoinHandle;
+
+use crate::nn_dataset::{DataLoader, SharedDataset, stack};
+use crate::nn_trainer::Sample;
+
+/**
+ * Prepara os próximos lotes em threads enquanto a rede treina o lote atual.
+ * Cada thread lê as amostras do conjunto compartilhado, aplica a transformação (normalização,
+ * aumento de dados, etc) a cada amostra e une o lote. Conjuntos em memória são lidos em paralelo;
+ * um Dataset que lê do arquivo pode ser compartilhado em um Mutex (ver SharedDataset).
+ *
+ * Os lotes são distribuídos entre as threads em rodízio, cada uma com seu próprio canal limitado,
+ * então saem na mesma ordem dos índices e cada thread fica no máximo capacity lotes à frente do treino.
+ */
+pub struct Prefetcher {
+    receivers: Vec<Receiver<Sample>>,
+    workers: Vec<Option<JoinHandle<()>>>,
+    next_batch: usize,
+    num_batches: usize,
+}
+
+impl Prefetcher {
+    pub fn new<D, F>(
+        dataset: Arc<D>,
+        batches: Vec<Vec<usize>>,
+        transform: F,
+        num_workers: usize,
+        capacity: usize,
+    ) -> Prefetcher
+    where
+        D: SharedDataset + 'static,
+        F: Fn(Sample) -> Sample + Send + Sync + 'static,
+    {
+        assert!(num_workers > 0);
+        let num_batches = batches.len();
+        let num_workers = num_workers.min(num_batches.max(1));
+        let transform = Arc::new(transform);
+
+        //Lote i fica com a thread i % num_workers
+        let mut worker_batches = vec![Vec::new(); num_workers];
+        for (i, batch) in batches.into_iter().enumerate() {
+            worker_batches[i % num_workers].push(batch);
+        }
+
+        let mut receivers = Vec::with_capacity(num_workers);
+        let mut workers = Vec::with_capacity(num_workers);
+        for batches in worker_batches {
+            let (sender, receiver) = sync_channel(capacity);
+            let dataset = Arc::clone(&dataset);
+            let transform = Arc::clone(&transform);
+            workers.push(Some(std::thread::spawn(move || {
+                prefetch(dataset.as_ref(), batches, transform.as_ref(), sender)
+            })));
+            receivers.push(receiver);
+        }
+        Prefetcher {
+            receivers,
+            workers,
+            next_batch: 0,
+            num_batches,
+        }
+    }
+
+    /**
+     * Lotes de uma passagem do DataLoader (com o embaralhamento dele)
+     */
+    pub fn from_loader<D, F>(
+        loader: &mut DataLoader,
+        dataset: Arc<D>,
+        transform: F,
+        num_workers: usize,
+        capacity: usize,
+    ) -> Prefetcher
+    where
+        D: SharedDataset + 'static,
+        F: Fn(Sample) -> Sample + Send + Sync + 'static,
+    {
+        Prefetcher::new(
+            dataset,
+            loader.batch_indices(),
+            transform,
+            num_workers,
+            capacity,
+        )
+    }
+
+    /**
+     * Uma thread por núcleo, deixando um para o treinamento
+     */
+    pub fn default_workers() -> usize {
+        std::thread::available_parallelism()
+            .map(|cores| cores.get().saturating_sub(1).max(1))
+            .unwrap_or(1)
+    }
+
+    pub fn num_batches(&self) -> usize {
+        self.num_batches
+    }
+}
+
+fn prefetch<D: SharedDataset>(
+    dataset: &D,
+    batches: Vec<Vec<usize>>,
+    transform: &(dyn Fn(Sample) -> Sample + Sync),
+    sender: SyncSender<Sample>,
+) {
+    for indices in batches {
+        let samples: Vec<Sample> = indices
+            .iter()
+            .map(|&index| transform(dataset.sample(index)))
+            .collect();
+        //Erro no envio: o Prefetcher foi descartado antes do fim
+        if sender.send(stack(&samples)).is_err() {
+            return;
+        }
+    }
+}
+
+impl Iterator for Prefetcher {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        if self.next_batch == self.num_batches {
+            return None;
+        }
+        let worker = self.next_batch % self.receivers.len();
+        self.next_batch += 1;
+        match self.receivers[worker].recv() {
+            Ok(batch) => Some(batch),
+            //A thread terminou sem enviar o lote: só acontece em pânico, que é repassado
+            Err(_) => {
+                let result = self.workers[worker].take().map(|handle| handle.join());
+                if let Some(Err(panic)) = result {
+                    std::panic::resume_unwind(panic);
+                }
+                panic!("Thread de leitura encerrada antes do fim dos lotes");
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.num_batches - self.next_batch;
+        (remaining, Some(remaining))
+    }
+}
+
+impl Drop for Prefetcher {
+    fn drop(&mut self) {
+        //Fechar os canais faz as threads pararem no próximo envio
+        self.receivers.clear();
+        for handle in self.workers.iter_mut().filter_map(|worker| worker.take()) {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_dataset::Dataset;
+    use crate::nn_matrix::Matrix;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    //Amostra i: entrada [i], saída [2i]. Conta as leituras.
+    struct Counting {
+        len: usize,
+        reads: usize,
+    }
+
+    impl Dataset for Counting {
+        fn len(&self) -> usize {
+            self.len
+        }
+
+        fn get(&mut self, index: usize) -> Sample {
+            assert!(index < self.len, "Índice fora do conjunto");
+            self.reads += 1;
+            (
+                Matrix::from_vec(1, 1, vec![index as f64]),
+                Matrix::from_vec(1, 1, vec![2.0 * index as f64]),
+            )
+        }
+    }
+
+    fn counting(len: usize) -> Arc<Mutex<Counting>> {
+        Arc::new(Mutex::new(Counting { len, reads: 0 }))
+    }
+
+    fn double_input((input, target): Sample) -> Sample {
+        (input.scalar_product(2.0), target)
+    }
+
+    #[test]
+    fn test_prefetch_order() {
+        let dataset = counting(50);
+        let mut loader = DataLoader::new((0..50).collect(), 4);
+        loader.set_shuffle(true);
+        loader.set_seed(9);
+        let expected = loader.batch_indices();
+        loader.set_seed(9);
+        let prefetcher = Prefetcher::from_loader(&mut loader, dataset.clone(), double_input, 3, 2);
+        assert!(prefetcher.num_batches() == 13);
+        let batches: Vec<Sample> = prefetcher.collect();
+        assert!(batches.len() == expected.len());
+        for (batch, indices) in batches.iter().zip(&expected) {
+            assert!(batch.0.cols() == indices.len());
+            for (column, &index) in indices.iter().enumerate() {
+                assert!(batch.0[0][column] == 2.0 * index as f64);
+                assert!(batch.1[0][column] == 2.0 * index as f64);
+            }
+        }
+        assert!(dataset.lock().unwrap().reads == 50);
+    }
+
+    #[test]
+    fn test_prefetch_bounded() {
+        let dataset = counting(100);
+        let batches: Vec<Vec<usize>> = (0..100).map(|i| vec![i]).collect();
+        let mut prefetcher = Prefetcher::new(dataset.clone(), batches, |sample| sample, 1, 2);
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        //2 lotes no canal e 1 esperando para ser enviado
+        assert!(dataset.lock().unwrap().reads <= 3);
+        assert!(prefetcher.next().unwrap().0[0][0] == 0.0);
+        //Descartar antes do fim encerra a thread
+        drop(prefetcher);
+        assert!(dataset.lock().unwrap().reads < 100);
+    }
+
+    //Conta as leituras em andamento ao mesmo tempo
+    #[derive(Default)]
+    struct Concurrent {
+        active: AtomicUsize,
+        max_active: AtomicUsize,
+    }
+
+    impl SharedDataset for Concurrent {
+        fn sample(&self, index: usize) -> Sample {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            (
+                Matrix::from_vec(1, 1, vec![index as f64]),
+                Matrix::new(1, 1),
+            )
+        }
+    }
+
+    #[test]
+    fn test_prefetch_parallel_reads() {
+        let dataset = Arc::new(Concurrent::default());
+        let batches: Vec<Vec<usize>> = (0..8).map(|i| vec![i]).collect();
+        let inputs: Vec<f64> = Prefetcher::new(dataset.clone(), batches, |sample| sample, 4, 2)
+            .map(|batch| batch.0[0][0])
+            .collect();
+        assert!(inputs == (0..8).map(|i| i as f64).collect::<Vec<f64>>());
+        //As threads leem sem travar o conjunto
+        assert!(dataset.max_active.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Índice fora do conjunto")]
+    fn test_prefetch_panic() {
+        let dataset = counting(5);
+        let batches = vec![vec![0, 1], vec![4, 5]];
+        let prefetcher = Prefetcher::new(dataset, batches, |sample| sample, 2, 1);
+        prefetcher.for_each(drop);
+    }
+}
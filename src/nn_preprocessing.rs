@@ -0,0 +1,665 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::io::{Read, Write};
+
+use crate::nn_matrix::Matrix;
+use crate::nn_metrics::argmax;
+use crate::nn_trainer::Sample;
+
+/**
+ * Padronização por linha (feature ou saída): (x - média) / desvio padrão.
+ * As estatísticas são calculadas sobre todas as colunas (amostras) das matrizes usadas em fit.
+ * Linhas constantes (desvio padrão 0) são apenas centralizadas.
+ */
+#[derive(Debug, Clone)]
+pub struct Standardizer {
+    mean: Matrix,
+    std: Matrix,
+}
+
+impl Standardizer {
+    pub fn fit(data: &[Matrix]) -> Standardizer {
+        assert!(!data.is_empty());
+        let rows = data[0].rows();
+        assert!(data.iter().all(|matrix| matrix.rows() == rows));
+        let count: usize = data.iter().map(|matrix| matrix.cols()).sum();
+        let mut mean = Matrix::new(rows, 1);
+        for matrix in data {
+            mean += &matrix.sum_columns();
+        }
+        mean.mut_scalar_product(1.0 / count as f64);
+
+        let mut std = Matrix::new(rows, 1);
+        for matrix in data {
+            for i in 0..rows {
+                for j in 0..matrix.cols() {
+                    std[i][0] += (matrix[i][j] - mean[i][0]).powi(2);
+                }
+            }
+        }
+        for i in 0..rows {
+            std[i][0] = (std[i][0] / count as f64).sqrt();
+            if std[i][0] == 0.0 {
+                std[i][0] = 1.0;
+            }
+        }
+        Standardizer { mean, std }
+    }
+
+    /**
+     * Média e desvio padrão únicos, de todos os elementos (ex: pixels de uma imagem)
+     */
+    pub fn fit_global(data: &[Matrix]) -> Standardizer {
+        assert!(!data.is_empty());
+        let rows = data[0].rows();
+        assert!(data.iter().all(|matrix| matrix.rows() == rows));
+        let count: usize = data.iter().map(|matrix| matrix.num_elements()).sum();
+        let mean = data
+            .iter()
+            .map(|matrix| matrix.data().iter().sum::<f64>())
+            .sum::<f64>()
+            / count as f64;
+        let variance = data
+            .iter()
+            .map(|matrix| {
+                matrix
+                    .data()
+                    .iter()
+                    .map(|v| (v - mean).powi(2))
+                    .sum::<f64>()
+            })
+            .sum::<f64>()
+            / count as f64;
+        let std = if variance == 0.0 {
+            1.0
+        } else {
+            variance.sqrt()
+        };
+        Standardizer {
+            mean: Matrix::from_vec(rows, 1, vec![mean; rows]),
+            std: Matrix::from_vec(rows, 1, vec![std; rows]),
+        }
+    }
+
+    pub fn mean(&self) -> &Matrix {
+        &self.mean
+    }
+
+    pub fn std(&self) -> &Matrix {
+        &self.std
+    }
+
+    pub fn transform(&self, data: &Matrix) -> Matrix {
+        assert!(data.rows() == self.mean.rows());
+        let mut transformed = data.clone();
+        for i in 0..data.rows() {
+            for j in 0..data.cols() {
+                transformed[i][j] = (data[i][j] - self.mean[i][0]) / self.std[i][0];
+            }
+        }
+        transformed
+    }
+
+    /**
+     * Volta à escala original (ex: previsões de uma rede treinada com saídas padronizadas)
+     */
+    pub fn inverse_transform(&self, data: &Matrix) -> Matrix {
+        assert!(data.rows() == self.mean.rows());
+        let mut restored = data.clone();
+        for i in 0..data.rows() {
+            for j in 0..data.cols() {
+                restored[i][j] = data[i][j] * self.std[i][0] + self.mean[i][0];
+            }
+        }
+        restored
+    }
+}
+
+/**
+ * Escala cada linha (feature) para o intervalo 0..1, com o mínimo e o máximo vistos em fit.
+ * Linhas constantes são apenas deslocadas para 0.
+ */
+#[derive(Debug, Clone)]
+pub struct MinMaxScaler {
+    min: Matrix,
+    range: Matrix,
+}
+
+impl MinMaxScaler {
+    pub fn fit(data: &[Matrix]) -> MinMaxScaler {
+        assert!(!data.is_empty());
+        let rows = data[0].rows();
+        assert!(data.iter().all(|matrix| matrix.rows() == rows));
+        let mut min = Matrix::from_vec(rows, 1, vec![f64::INFINITY; rows]);
+        let mut max = Matrix::from_vec(rows, 1, vec![f64::NEG_INFINITY; rows]);
+        for matrix in data {
+            for i in 0..rows {
+                for j in 0..matrix.cols() {
+                    min[i][0] = min[i][0].min(matrix[i][j]);
+                    max[i][0] = max[i][0].max(matrix[i][j]);
+                }
+            }
+        }
+        let mut range = Matrix::new(rows, 1);
+        for i in 0..rows {
+            range[i][0] = max[i][0] - min[i][0];
+            if range[i][0] == 0.0 {
+                range[i][0] = 1.0;
+            }
+        }
+        MinMaxScaler { min, range }
+    }
+
+    pub fn min(&self) -> &Matrix {
+        &self.min
+    }
+
+    pub fn range(&self) -> &Matrix {
+        &self.range
+    }
+
+    pub fn transform(&self, data: &Matrix) -> Matrix {
+        assert!(data.rows() == self.min.rows());
+        let mut transformed = data.clone();
+        for i in 0..data.rows() {
+            for j in 0..data.cols() {
+                transformed[i][j] = (data[i][j] - self.min[i][0]) / self.range[i][0];
+            }
+        }
+        transformed
+    }
+
+    pub fn inverse_transform(&self, data: &Matrix) -> Matrix {
+        assert!(data.rows() == self.min.rows());
+        let mut restored = data.clone();
+        for i in 0..data.rows() {
+            for j in 0..data.cols() {
+                restored[i][j] = data[i][j] * self.range[i][0] + self.min[i][0];
+            }
+        }
+        restored
+    }
+}
+
+/**
+ * Branqueamento por PCA: projeta os dados centralizados nos num_components autovetores da covariância
+ * de maior autovalor, dividindo cada componente pela raiz do autovalor (+ epsilon).
+ * Os componentes resultantes têm média 0, variância 1 e não são correlacionados.
+ *
+ * A covariância é n x n (n features) e a decomposição é feita pelo método de Jacobi, então para entradas grandes
+ * (ex: imagens 28x28) o ajuste deve ser feito em uma amostra do conjunto.
+ */
+#[derive(Debug, Clone)]
+pub struct PcaWhitening {
+    mean: Matrix,
+    projection: Matrix, //num_components x n, já dividida pelas raízes dos autovalores
+}
+
+impl PcaWhitening {
+    pub fn fit(data: &[Matrix], num_components: usize, epsilon: f64) -> PcaWhitening {
+        assert!(!data.is_empty());
+        let rows = data[0].rows();
+        assert!(num_components > 0 && num_components <= rows);
+        let mean = Standardizer::fit(data).mean;
+        let count: usize = data.iter().map(|matrix| matrix.cols()).sum();
+
+        let mut covariance = Matrix::new(rows, rows);
+        let mut centered = vec![0.0; rows];
+        for matrix in data {
+            for j in 0..matrix.cols() {
+                for i in 0..rows {
+                    centered[i] = matrix[i][j] - mean[i][0];
+                }
+                for a in 0..rows {
+                    for b in a..rows {
+                        covariance[a][b] += centered[a] * centered[b];
+                    }
+                }
+            }
+        }
+        for a in 0..rows {
+            for b in a..rows {
+                covariance[a][b] /= count as f64;
+                covariance[b][a] = covariance[a][b];
+            }
+        }
+
+        let (eigenvalues, eigenvectors) = symmetric_eigen(&covariance);
+        let mut projection = Matrix::new(num_components, rows);
+        for k in 0..num_components {
+            let scale = 1.0 / (eigenvalues[k].max(0.0) + epsilon).sqrt();
+            for i in 0..rows {
+                projection[k][i] = eigenvectors[i][k] * scale;
+            }
+        }
+        PcaWhitening { mean, projection }
+    }
+
+    pub fn num_components(&self) -> usize {
+        self.projection.rows()
+    }
+
+    pub fn transform(&self, data: &Matrix) -> Matrix {
+        assert!(data.rows() == self.mean.rows());
+        let mut centered = data.clone();
+        for i in 0..data.rows() {
+            for j in 0..data.cols() {
+                centered[i][j] -= self.mean[i][0];
+            }
+        }
+        self.projection.multiply(&centered)
+    }
+}
+
+/**
+ * Autovalores (em ordem decrescente) e autovetores (colunas) de uma matriz simétrica, pelo método de Jacobi cíclico
+ */
+fn symmetric_eigen(matrix: &Matrix) -> (Vec<f64>, Matrix) {
+    let n = matrix.rows();
+    assert!(matrix.cols() == n);
+    let mut a = matrix.clone();
+    let mut vectors = Matrix::new(n, n);
+    for i in 0..n {
+        vectors[i][i] = 1.0;
+    }
+    let total: f64 = a.data().iter().map(|v| v * v).sum();
+    for _ in 0..100 {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|p| (0..n).filter(move |&q| q != p).map(move |q| (p, q)))
+            .map(|(p, q)| a[p][q] * a[p][q])
+            .sum();
+        if off_diagonal <= 1e-22 * total.max(f64::MIN_POSITIVE) {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                //Rotação que zera a[p][q]
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[k][p], a[k][q]);
+                    a[k][p] = c * akp - s * akq;
+                    a[k][q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p][k], a[q][k]);
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (vectors[k][p], vectors[k][q]);
+                    vectors[k][p] = c * vkp - s * vkq;
+                    vectors[k][q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| a[j][j].total_cmp(&a[i][i]));
+    let eigenvalues = order.iter().map(|&i| a[i][i]).collect();
+    let mut sorted_vectors = Matrix::new(n, n);
+    for (k, &i) in order.iter().enumerate() {
+        sorted_vectors.set_column(k, &vectors.column(i));
+    }
+    (eigenvalues, sorted_vectors)
+}
+
+/**
+ * Pré-processamento das entradas ajustado aos dados de treino.
+ * Os parâmetros são salvos no checkpoint da rede (ver NeuralNetwork::add_preprocessor),
+ * para que a inferência use exatamente a mesma transformação.
+ */
+#[derive(Debug, Clone)]
+pub enum Preprocessor {
+    Standard(Standardizer),
+    MinMax(MinMaxScaler),
+    Pca(PcaWhitening),
+}
+
+impl Preprocessor {
+    pub fn transform(&self, data: &Matrix) -> Matrix {
+        match self {
+            Preprocessor::Standard(standardizer) => standardizer.transform(data),
+            Preprocessor::MinMax(scaler) => scaler.transform(data),
+            Preprocessor::Pca(pca) => pca.transform(data),
+        }
+    }
+
+    /**
+     * Formato: tipo (u32) seguido das matrizes de parâmetros (ver Matrix::write_to)
+     */
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let (kind, parameters) = match self {
+            Preprocessor::Standard(standardizer) => (0u32, [&standardizer.mean, &standardizer.std]),
+            Preprocessor::MinMax(scaler) => (1, [&scaler.min, &scaler.range]),
+            Preprocessor::Pca(pca) => (2, [&pca.mean, &pca.projection]),
+        };
+        writer.write_all(&kind.to_be_bytes())?;
+        for matrix in parameters {
+            matrix.write_to(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> std::io::Result<Preprocessor> {
+        let mut u32_buffer = [0u8; 4];
+        reader.read_exact(&mut u32_buffer)?;
+        let kind = u32::from_be_bytes(u32_buffer);
+        if kind > 2 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Tipo de pré-processamento desconhecido: {}", kind),
+            ));
+        }
+        let first = Matrix::read_from(reader)?;
+        let second = Matrix::read_from(reader)?;
+        Ok(match kind {
+            0 => Preprocessor::Standard(Standardizer {
+                mean: first,
+                std: second,
+            }),
+            1 => Preprocessor::MinMax(MinMaxScaler {
+                min: first,
+                range: second,
+            }),
+            _ => Preprocessor::Pca(PcaWhitening {
+                mean: first,
+                projection: second,
+            }),
+        })
+    }
+}
+
+/**
+ * Codificação de classes (0..num_classes) em saídas esperadas com uma posição por classe, e o caminho inverso
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct OneHotEncoder {
+    num_classes: usize,
+}
+
+impl OneHotEncoder {
+    pub fn new(num_classes: usize) -> OneHotEncoder {
+        assert!(num_classes > 0);
+        OneHotEncoder { num_classes }
+    }
+
+    pub fn num_classes(&self) -> usize {
+        self.num_classes
+    }
+
+    pub fn encode(&self, class: usize) -> Matrix {
+        assert!(class < self.num_classes);
+        let mut encoded = Matrix::new(self.num_classes, 1);
+        encoded[class][0] = 1.0;
+        encoded
+    }
+
+    /**
+     * Uma coluna por classe
+     */
+    pub fn encode_batch(&self, classes: &[usize]) -> Matrix {
+        let mut encoded = Matrix::new(self.num_classes, classes.len());
+        for (j, &class) in classes.iter().enumerate() {
+            assert!(class < self.num_classes);
+            encoded[class][j] = 1.0;
+        }
+        encoded
+    }
+
+    /**
+     * Classe de maior valor na saída (matriz coluna)
+     */
+    pub fn decode(&self, output: &Matrix) -> usize {
+        assert!(output.rows() == self.num_classes && output.cols() == 1);
+        argmax(output)
+    }
+}
+
+/**
+ * Associa rótulos (ex: "setosa", "A") aos índices das classes, na ordem em que aparecem em fit.
+ * É salvo no checkpoint da rede para que as previsões possam ser convertidas de volta aos rótulos.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelEncoder {
+    labels: Vec<String>,
+}
+
+impl LabelEncoder {
+    pub fn fit<S: AsRef<str>>(labels: &[S]) -> LabelEncoder {
+        let mut unique: Vec<String> = Vec::new();
+        for label in labels {
+            if !unique.iter().any(|known| known == label.as_ref()) {
+                unique.push(label.as_ref().to_string());
+            }
+        }
+        LabelEncoder { labels: unique }
+    }
+
+    pub fn num_classes(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn encode(&self, label: &str) -> Option<usize> {
+        self.labels.iter().position(|known| known == label)
+    }
+
+    pub fn decode(&self, class: usize) -> &str {
+        &self.labels[class]
+    }
+
+    pub fn one_hot(&self) -> OneHotEncoder {
+        OneHotEncoder::new(self.num_classes())
+    }
+
+    /**
+     * Formato: número de rótulos (u32) e, para cada um, o tamanho em bytes (u32) seguido do texto em UTF-8
+     */
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(&(self.labels.len() as u32).to_be_bytes())?;
+        for label in &self.labels {
+            writer.write_all(&(label.len() as u32).to_be_bytes())?;
+            writer.write_all(label.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Os tamanhos vêm do arquivo: nada é reservado a partir deles
+     */
+    pub fn read_from(reader: &mut impl Read) -> std::io::Result<LabelEncoder> {
+        let mut u32_buffer = [0u8; 4];
+        reader.read_exact(&mut u32_buffer)?;
+        let count = u32::from_be_bytes(u32_buffer);
+        let mut labels = Vec::new();
+        for _ in 0..count {
+            reader.read_exact(&mut u32_buffer)?;
+            let len = u32::from_be_bytes(u32_buffer) as u64;
+            let mut bytes = Vec::new();
+            reader.by_ref().take(len).read_to_end(&mut bytes)?;
+            if (bytes.len() as u64) < len {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+            }
+            let label = String::from_utf8(bytes)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+            labels.push(label);
+        }
+        Ok(LabelEncoder { labels })
+    }
+}
+
+/**
+ * Padroniza as saídas esperadas das amostras e devolve o Standardizer usado,
+ * necessário para converter as previsões da rede de volta à escala original.
+ */
+pub fn standardize_targets(samples: &mut [Sample]) -> Standardizer {
+    let targets: Vec<Matrix> = samples.iter().map(|(_, target)| target.clone()).collect();
+    let standardizer = Standardizer::fit(&targets);
+    for (_, target) in samples.iter_mut() {
+        *target = standardizer.transform(target);
+    }
+    standardizer
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+
+    #[test]
+    fn test_standardizer() {
+        let data = [
+            Matrix::from_vec(2, 2, vec![1.0, 3.0, 10.0, 10.0]),
+            Matrix::from_vec(2, 1, vec![5.0, 10.0]),
+        ];
+        let standardizer = Standardizer::fit(&data);
+        assert!(*standardizer.mean() == Matrix::from_vec(2, 1, vec![3.0, 10.0]));
+        //Desvio padrão populacional de [1, 3, 5]; a linha constante fica com 1
+        let expected_std = (8.0f64 / 3.0).sqrt();
+        assert!(*standardizer.std() == Matrix::from_vec(2, 1, vec![expected_std, 1.0]));
+
+        let transformed = standardizer.transform(&data[0]);
+        print!("Padronizado: {}", transformed);
+        assert!(transformed == Matrix::from_vec(2, 2, vec![-2.0 / expected_std, 0.0, 0.0, 0.0]));
+        assert!(standardizer.inverse_transform(&transformed) == data[0]);
+    }
+
+    #[test]
+    fn test_standardize_targets() {
+        let mut samples: Vec<Sample> = [100.0, 200.0, 300.0]
+            .iter()
+            .map(|&y| (Matrix::new(1, 1), Matrix::from_vec(1, 1, vec![y])))
+            .collect();
+        let standardizer = standardize_targets(&mut samples);
+        let sum: f64 = samples.iter().map(|(_, target)| target[0][0]).sum();
+        assert!(sum.abs() < 1e-12);
+        assert!((standardizer.inverse_transform(&samples[2].1)[0][0] - 300.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_global_and_min_max() {
+        let data = [Matrix::from_vec(2, 2, vec![1.0, 3.0, 5.0, 7.0])];
+        let standardizer = Standardizer::fit_global(&data);
+        assert!(standardizer.mean()[1][0] == 4.0);
+        let transformed = standardizer.transform(&data[0]);
+        assert!(transformed.data().iter().sum::<f64>().abs() < 1e-12);
+        assert!((transformed[1][1] - 3.0 / 5.0f64.sqrt()).abs() < 1e-12);
+
+        let data = [
+            Matrix::from_vec(2, 2, vec![2.0, 4.0, 1.0, 1.0]),
+            Matrix::from_vec(2, 1, vec![6.0, 1.0]),
+        ];
+        let scaler = MinMaxScaler::fit(&data);
+        let transformed = scaler.transform(&data[1]);
+        assert!(transformed == Matrix::from_vec(2, 1, vec![1.0, 0.0]));
+        assert!(scaler.transform(&data[0])[0][1] == 0.5);
+        assert!(scaler.inverse_transform(&transformed) == data[1]);
+    }
+
+    #[test]
+    fn test_pca_whitening() {
+        //Pontos correlacionados: y = 2x + ruído pequeno
+        let mut data = Matrix::new(3, 200);
+        for j in 0..200 {
+            let x = (j as f64 * 0.37).sin() * 3.0;
+            data[0][j] = x;
+            data[1][j] = 2.0 * x + (j as f64 * 1.3).cos() * 0.1 + 5.0;
+            data[2][j] = (j as f64 * 0.71).cos();
+        }
+        let pca = PcaWhitening::fit(std::slice::from_ref(&data), 3, 0.0);
+        assert!(pca.num_components() == 3);
+        let whitened = pca.transform(&data);
+        //Covariância dos componentes deve ser a identidade
+        for a in 0..3 {
+            for b in 0..3 {
+                let mean_a = whitened[a].iter().sum::<f64>() / 200.0;
+                let mean_b = whitened[b].iter().sum::<f64>() / 200.0;
+                let covariance: f64 = (0..200)
+                    .map(|j| (whitened[a][j] - mean_a) * (whitened[b][j] - mean_b))
+                    .sum::<f64>()
+                    / 200.0;
+                let expected = if a == b { 1.0 } else { 0.0 };
+                assert!((covariance - expected).abs() < 1e-6);
+            }
+        }
+        //O primeiro componente é a direção de maior variância (1, 2)/√5
+        let reduced = PcaWhitening::fit(&[data], 1, 1e-5);
+        let direction = reduced.projection[0][1] / reduced.projection[0][0];
+        assert!((direction - 2.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_symmetric_eigen() {
+        let matrix = Matrix::from_vec(2, 2, vec![2.0, 1.0, 1.0, 2.0]);
+        let (values, vectors) = symmetric_eigen(&matrix);
+        assert!((values[0] - 3.0).abs() < 1e-12 && (values[1] - 1.0).abs() < 1e-12);
+        assert!((vectors[0][0].abs() - 0.5f64.sqrt()).abs() < 1e-12);
+        assert!((vectors[0][0] - vectors[1][0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let data = [Matrix::from_vec(2, 3, vec![1.0, 2.0, 4.0, 0.5, 0.1, 0.3])];
+        let input = Matrix::from_vec(2, 1, vec![3.0, 0.2]);
+        for preprocessor in [
+            Preprocessor::Standard(Standardizer::fit(&data)),
+            Preprocessor::MinMax(MinMaxScaler::fit(&data)),
+            Preprocessor::Pca(PcaWhitening::fit(&data, 2, 1e-5)),
+        ] {
+            let mut buffer: Vec<u8> = Vec::new();
+            preprocessor.write_to(&mut buffer).unwrap();
+            let read = Preprocessor::read_from(&mut buffer.as_slice()).unwrap();
+            assert!(read.transform(&input) == preprocessor.transform(&input));
+        }
+        let invalid = [0u8, 0, 0, 9];
+        let error = Preprocessor::read_from(&mut invalid.as_slice()).unwrap_err();
+        assert!(error.kind() == std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_label_encoders() {
+        let encoder = LabelEncoder::fit(&["setosa", "versicolor", "setosa", "virginica"]);
+        assert!(encoder.num_classes() == 3);
+        assert!(encoder.encode("virginica") == Some(2) && encoder.encode("rosa").is_none());
+        assert!(encoder.decode(1) == "versicolor");
+
+        let one_hot = encoder.one_hot();
+        assert!(one_hot.encode(1) == Matrix::from_vec(3, 1, vec![0.0, 1.0, 0.0]));
+        let batch = one_hot.encode_batch(&[2, 0]);
+        assert!(batch == Matrix::from_vec(3, 2, vec![0.0, 1.0, 0.0, 0.0, 1.0, 0.0]));
+        assert!(one_hot.decode(&Matrix::from_vec(3, 1, vec![0.1, 0.3, 0.8])) == 2);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        encoder.write_to(&mut buffer).unwrap();
+        assert!(LabelEncoder::read_from(&mut buffer.as_slice()).unwrap() == encoder);
+
+        //Tamanhos corrompidos: erro ao faltarem os dados
+        let corrupted = [u32::MAX.to_be_bytes(), u32::MAX.to_be_bytes()].concat();
+        let error = LabelEncoder::read_from(&mut corrupted.as_slice()).unwrap_err();
+        assert!(error.kind() == std::io::ErrorKind::UnexpectedEof);
+    }
+}
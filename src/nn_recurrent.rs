@@ -0,0 +1,668 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://colah.github.io/posts/2015-08-Understanding-LSTMs/
+//https://arxiv.org/abs/1406.1078 (GRU)
+use crate::nn_layer::{ActivationFunction, Gradient, NetworkLayer, Sigmoid, Tanh};
+use crate::nn_matrix::Matrix;
+
+/**
+ * Valores de um passo de tempo, guardados para a retropropagação através do tempo (BPTT)
+ */
+pub struct CellStep {
+    h: Matrix,
+    c: Matrix,     //Estado da célula (apenas LSTM)
+    gates: Matrix, //Valores das portas (ou a soma ponderada, na RNN simples)
+    ah: Matrix,    //Contribuição do estado anterior, W_h * h_(t-1) (necessária na GRU)
+}
+
+/**
+ * Gradientes de um passo de tempo em relação às somas ponderadas das portas.
+ * dax multiplica a entrada x_t e dah o estado anterior h_(t-1). Diferem apenas na GRU.
+ */
+pub struct CellGradient {
+    dax: Matrix,
+    dah: Matrix,
+    dh_prev: Matrix, //Contribuição para ∂C/∂h_(t-1) que não passa pelos pesos
+    dc_prev: Matrix,
+}
+
+/**
+ * Célula recorrente. Os pesos ficam na camada Recurrent, empacotados em uma única matriz
+ * [W_x | W_h] com um bloco de linhas por porta, de modo que cada célula só implementa as fórmulas de um passo.
+ */
+pub trait RecurrentCell {
+    fn gates(&self) -> usize;
+    fn step(
+        &self,
+        ax: Matrix,
+        ah: Matrix,
+        biases: &Matrix,
+        h_prev: &Matrix,
+        c_prev: &Matrix,
+    ) -> CellStep;
+    fn backward_step(
+        &self,
+        step: &CellStep,
+        dh: &Matrix,
+        dc: &Matrix,
+        h_prev: &Matrix,
+        c_prev: &Matrix,
+    ) -> CellGradient;
+    fn init_biases(&self, _biases: &mut Matrix) {}
+}
+
+/**
+ * RNN simples: h_t = f(W_x * x_t + W_h * h_(t-1) + b)
+ */
+pub struct RnnCell {
+    activation_function: fn(f64, &[f64]) -> f64,
+    activation_derivative: fn(f64) -> f64,
+}
+
+impl RecurrentCell for RnnCell {
+    fn gates(&self) -> usize {
+        1
+    }
+
+    fn step(
+        &self,
+        ax: Matrix,
+        ah: Matrix,
+        biases: &Matrix,
+        h_prev: &Matrix,
+        _c_prev: &Matrix,
+    ) -> CellStep {
+        let zed = ax + &ah + biases;
+        let h = Matrix::from_vec(
+            zed.rows(),
+            1,
+            zed.data()
+                .iter()
+                .map(|&v| (self.activation_function)(v, zed.data()))
+                .collect(),
+        );
+        CellStep {
+            h,
+            c: Matrix::new(h_prev.rows(), 1),
+            gates: zed,
+            ah,
+        }
+    }
+
+    //δ = ∂C/∂h_t * f'(z)
+    fn backward_step(
+        &self,
+        step: &CellStep,
+        dh: &Matrix,
+        _dc: &Matrix,
+        h_prev: &Matrix,
+        _c_prev: &Matrix,
+    ) -> CellGradient {
+        let delta = step
+            .gates
+            .clone()
+            .map(self.activation_derivative)
+            .hadamard_product(dh);
+        CellGradient {
+            dax: delta.clone(),
+            dah: delta,
+            dh_prev: Matrix::new(h_prev.rows(), 1),
+            dc_prev: Matrix::new(h_prev.rows(), 1),
+        }
+    }
+}
+
+/**
+ * LSTM com portas na ordem entrada (i), esquecimento (f), candidato (g) e saída (o):
+ * i = σ(a_i), f = σ(a_f), g = tanh(a_g), o = σ(a_o)
+ * c_t = f ⊙ c_(t-1) + i ⊙ g
+ * h_t = o ⊙ tanh(c_t)
+ */
+pub struct LstmCell {}
+
+impl RecurrentCell for LstmCell {
+    fn gates(&self) -> usize {
+        4
+    }
+
+    fn step(
+        &self,
+        ax: Matrix,
+        ah: Matrix,
+        biases: &Matrix,
+        _h_prev: &Matrix,
+        c_prev: &Matrix,
+    ) -> CellStep {
+        let hidden = c_prev.rows();
+        let mut gates = ax + &ah + biases;
+        for i in 0..4 * hidden {
+            gates[i][0] = if i / hidden == 2 {
+                Tanh::activate(gates[i][0], &[])
+            } else {
+                Sigmoid::activate(gates[i][0], &[])
+            };
+        }
+        let mut c = Matrix::new(hidden, 1);
+        let mut h = Matrix::new(hidden, 1);
+        for k in 0..hidden {
+            let (i, f, g, o) = gate_values(&gates, hidden, k);
+            c[k][0] = f * c_prev[k][0] + i * g;
+            h[k][0] = o * c[k][0].tanh();
+        }
+        CellStep { h, c, gates, ah }
+    }
+
+    /**
+     * ∂C/∂o = ∂C/∂h ⊙ tanh(c_t)
+     * ∂C/∂c_t = ∂C/∂c_(t+1) ⊙ f_(t+1) + ∂C/∂h ⊙ o ⊙ (1 - tanh²(c_t))
+     * ∂C/∂i = ∂C/∂c_t ⊙ g, ∂C/∂g = ∂C/∂c_t ⊙ i, ∂C/∂f = ∂C/∂c_t ⊙ c_(t-1)
+     * e cada porta é multiplicada pela derivada da sua ativação.
+     */
+    fn backward_step(
+        &self,
+        step: &CellStep,
+        dh: &Matrix,
+        dc: &Matrix,
+        _h_prev: &Matrix,
+        c_prev: &Matrix,
+    ) -> CellGradient {
+        let hidden = dh.rows();
+        let mut dgates = Matrix::new(4 * hidden, 1);
+        let mut dc_prev = Matrix::new(hidden, 1);
+        for k in 0..hidden {
+            let (i, f, g, o) = gate_values(&step.gates, hidden, k);
+            let tanh_c = step.c[k][0].tanh();
+            let dc_total = dc[k][0] + dh[k][0] * o * (1.0 - tanh_c * tanh_c);
+            dgates[k][0] = dc_total * g * i * (1.0 - i);
+            dgates[hidden + k][0] = dc_total * c_prev[k][0] * f * (1.0 - f);
+            dgates[2 * hidden + k][0] = dc_total * i * (1.0 - g * g);
+            dgates[3 * hidden + k][0] = dh[k][0] * tanh_c * o * (1.0 - o);
+            dc_prev[k][0] = dc_total * f;
+        }
+        CellGradient {
+            dax: dgates.clone(),
+            dah: dgates,
+            dh_prev: Matrix::new(hidden, 1),
+            dc_prev,
+        }
+    }
+
+    //Viés da porta de esquecimento iniciado em 1 para que a célula preserve o estado no início do treinamento
+    fn init_biases(&self, biases: &mut Matrix) {
+        let hidden = biases.rows() / 4;
+        for k in hidden..2 * hidden {
+            biases[k][0] = 1.0;
+        }
+    }
+}
+
+fn gate_values(gates: &Matrix, hidden: usize, k: usize) -> (f64, f64, f64, f64) {
+    (
+        gates[k][0],
+        gates[hidden + k][0],
+        gates[2 * hidden + k][0],
+        gates[3 * hidden + k][0],
+    )
+}
+
+/**
+ * GRU com portas na ordem reset (r), atualização (z) e candidato (n):
+ * r = σ(ax_r + ah_r + b_r), z = σ(ax_z + ah_z + b_z)
+ * n = tanh(ax_n + r ⊙ ah_n + b_n)
+ * h_t = (1 - z) ⊙ n + z ⊙ h_(t-1)
+ */
+pub struct GruCell {}
+
+impl RecurrentCell for GruCell {
+    fn gates(&self) -> usize {
+        3
+    }
+
+    fn step(
+        &self,
+        ax: Matrix,
+        ah: Matrix,
+        biases: &Matrix,
+        h_prev: &Matrix,
+        _c_prev: &Matrix,
+    ) -> CellStep {
+        let hidden = h_prev.rows();
+        let mut gates = Matrix::new(3 * hidden, 1);
+        let mut h = Matrix::new(hidden, 1);
+        for k in 0..hidden {
+            let r = Sigmoid::activate(ax[k][0] + ah[k][0] + biases[k][0], &[]);
+            let z = Sigmoid::activate(
+                ax[hidden + k][0] + ah[hidden + k][0] + biases[hidden + k][0],
+                &[],
+            );
+            let n = (ax[2 * hidden + k][0] + r * ah[2 * hidden + k][0] + biases[2 * hidden + k][0])
+                .tanh();
+            gates[k][0] = r;
+            gates[hidden + k][0] = z;
+            gates[2 * hidden + k][0] = n;
+            h[k][0] = (1.0 - z) * n + z * h_prev[k][0];
+        }
+        CellStep {
+            h,
+            c: Matrix::new(hidden, 1),
+            gates,
+            ah,
+        }
+    }
+
+    /**
+     * ∂C/∂n = ∂C/∂h ⊙ (1 - z), ∂C/∂z = ∂C/∂h ⊙ (h_(t-1) - n)
+     * ∂C/∂r = ∂C/∂a_n ⊙ ah_n
+     * A soma ponderada de n recebe W_h * h_(t-1) multiplicada por r, por isso dah_n = dax_n ⊙ r.
+     */
+    fn backward_step(
+        &self,
+        step: &CellStep,
+        dh: &Matrix,
+        _dc: &Matrix,
+        h_prev: &Matrix,
+        _c_prev: &Matrix,
+    ) -> CellGradient {
+        let hidden = dh.rows();
+        let mut dax = Matrix::new(3 * hidden, 1);
+        let mut dah = Matrix::new(3 * hidden, 1);
+        let mut dh_prev = Matrix::new(hidden, 1);
+        for k in 0..hidden {
+            let r = step.gates[k][0];
+            let z = step.gates[hidden + k][0];
+            let n = step.gates[2 * hidden + k][0];
+            let da_n = dh[k][0] * (1.0 - z) * (1.0 - n * n);
+            let da_z = dh[k][0] * (h_prev[k][0] - n) * z * (1.0 - z);
+            let da_r = da_n * step.ah[2 * hidden + k][0] * r * (1.0 - r);
+
+            dax[k][0] = da_r;
+            dax[hidden + k][0] = da_z;
+            dax[2 * hidden + k][0] = da_n;
+            dah[k][0] = da_r;
+            dah[hidden + k][0] = da_z;
+            dah[2 * hidden + k][0] = da_n * r;
+            dh_prev[k][0] = dh[k][0] * z;
+        }
+        CellGradient {
+            dax,
+            dah,
+            dh_prev,
+            dc_prev: Matrix::new(hidden, 1),
+        }
+    }
+}
+
+/**
+ * Camada recorrente. A entrada é uma sequência em que cada coluna é um passo de tempo (entradas x passos).
+ * A saída é o último estado oculto (ocultos x 1), que pode alimentar diretamente uma camada densa,
+ * ou a sequência completa de estados (ocultos x passos) com set_return_sequences(true).
+ *
+ * Cada chamada processa uma única sequência: as colunas são passos de tempo, não amostras.
+ * Com a sequência completa na saída da rede, o custo (ver Loss) divide pelo número de colunas
+ * e fica sendo a média sobre os passos de tempo da sequência, assim como o gradiente.
+ * Assim, o passo de aprendizado efetivo de cada passo de tempo cai com o comprimento da sequência;
+ * para um custo somado por sequência, multiplique a taxa de aprendizado pelo número de passos.
+ *
+ * Pesos: [W_x | W_h], com (portas * ocultos) linhas e (entradas + ocultos) colunas.
+ * Viéses: (portas * ocultos) x 1.
+ */
+pub struct Recurrent<C: RecurrentCell> {
+    cell: C,
+    input_size: usize,
+    hidden_size: usize,
+    weights: Matrix,
+    biases: Matrix,
+    return_sequences: bool,
+    truncation: Option<usize>,
+    neurons: Matrix,
+    //Valores guardados para a retropropagação através do tempo
+    hidden_states: Vec<Matrix>, //h_0 (zeros) .. h_T
+    cell_states: Vec<Matrix>,
+    steps: Vec<CellStep>,
+}
+
+pub type Rnn = Recurrent<RnnCell>;
+pub type Lstm = Recurrent<LstmCell>;
+pub type Gru = Recurrent<GruCell>;
+
+impl Recurrent<RnnCell> {
+    pub fn new<F: ActivationFunction>(input_size: usize, hidden_size: usize) -> Rnn {
+        Recurrent::with_cell(
+            RnnCell {
+                activation_function: F::activate,
+                activation_derivative: F::derivative,
+            },
+            input_size,
+            hidden_size,
+        )
+    }
+}
+
+impl Recurrent<LstmCell> {
+    pub fn new(input_size: usize, hidden_size: usize) -> Lstm {
+        Recurrent::with_cell(LstmCell {}, input_size, hidden_size)
+    }
+}
+
+impl Recurrent<GruCell> {
+    pub fn new(input_size: usize, hidden_size: usize) -> Gru {
+        Recurrent::with_cell(GruCell {}, input_size, hidden_size)
+    }
+}
+
+impl<C: RecurrentCell> Recurrent<C> {
+    pub fn with_cell(cell: C, input_size: usize, hidden_size: usize) -> Recurrent<C> {
+        let rows = cell.gates() * hidden_size;
+        let mut biases = Matrix::new(rows, 1);
+        cell.init_biases(&mut biases);
+        Recurrent {
+            //Glorot, já que as portas usam ativações sigmoid e tanh
+            weights: Matrix::new_random_glorot(rows, input_size + hidden_size),
+            biases,
+            cell,
+            input_size,
+            hidden_size,
+            return_sequences: false,
+            truncation: None,
+            neurons: Matrix::new(hidden_size, 1),
+            hidden_states: Vec::new(),
+            cell_states: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /**
+     * Retorna um estado oculto por passo de tempo. Ver a convenção do custo na documentação da camada.
+     */
+    pub fn set_return_sequences(&mut self, return_sequences: bool) {
+        self.return_sequences = return_sequences;
+    }
+
+    /**
+     * Retropropagação truncada: a sequência é dividida em blocos de `steps` passos
+     * e o gradiente não atravessa a fronteira entre blocos (o estado oculto continua sendo propagado).
+     */
+    pub fn set_truncation(&mut self, steps: Option<usize>) {
+        assert!(steps != Some(0));
+        self.truncation = steps;
+    }
+
+    //Produto de um bloco de colunas dos pesos (W_x ou W_h) por um vetor coluna
+    fn block_product(&self, first_col: usize, vector: &Matrix) -> Matrix {
+        let mut product = Matrix::new(self.weights.rows(), 1);
+        for i in 0..self.weights.rows() {
+            let row = &self.weights[i][first_col..first_col + vector.rows()];
+            product[i][0] = row.iter().zip(vector.data()).map(|(w, v)| w * v).sum();
+        }
+        product
+    }
+
+    //Produto da transposta de um bloco de colunas dos pesos por um vetor coluna
+    fn block_transpose_product(&self, first_col: usize, size: usize, vector: &Matrix) -> Matrix {
+        let mut product = Matrix::new(size, 1);
+        for i in 0..self.weights.rows() {
+            let row = &self.weights[i][first_col..first_col + size];
+            for (j, w) in row.iter().enumerate() {
+                product[j][0] += w * vector[i][0];
+            }
+        }
+        product
+    }
+}
+
+impl<C: RecurrentCell> NetworkLayer for Recurrent<C> {
+    fn propagate(&mut self, input: &Matrix) {
+        assert!(input.rows() == self.input_size);
+        let time_steps = input.cols();
+        self.hidden_states = vec![Matrix::new(self.hidden_size, 1)];
+        self.cell_states = vec![Matrix::new(self.hidden_size, 1)];
+        self.steps = Vec::with_capacity(time_steps);
+
+        for t in 0..time_steps {
+            let h_prev = &self.hidden_states[t];
+            let ax = self.block_product(0, &input.column(t));
+            let ah = self.block_product(self.input_size, h_prev);
+            let step = self
+                .cell
+                .step(ax, ah, &self.biases, h_prev, &self.cell_states[t]);
+            self.hidden_states.push(step.h.clone());
+            self.cell_states.push(step.c.clone());
+            self.steps.push(step);
+        }
+
+        self.neurons = if self.return_sequences {
+            let mut sequence = Matrix::new(self.hidden_size, time_steps);
+            for t in 0..time_steps {
+                sequence.set_column(t, &self.hidden_states[t + 1]);
+            }
+            sequence
+        } else {
+            self.hidden_states[time_steps].clone()
+        };
+    }
+
+    fn neurons(&self) -> &Matrix {
+        &self.neurons
+    }
+    fn weights(&self) -> &Matrix {
+        &self.weights
+    }
+    fn biases(&self) -> &Matrix {
+        &self.biases
+    }
+    fn fix_weights(&mut self, weights: Matrix) {
+        assert!(weights.rows() == self.weights.rows() && weights.cols() == self.weights.cols());
+        self.weights = weights;
+    }
+    fn fix_bias(&mut self, biases: Matrix) {
+        assert!(biases.rows() == self.biases.rows() && biases.cols() == self.biases.cols());
+        self.biases = biases;
+    }
+
+    /**
+     * Retropropagação através do tempo (BPTT). Percorre a sequência do último ao primeiro passo:
+     * ∂C/∂h_t = ∂C/∂y_t (se a sequência é retornada) + ∂C/∂h_t vindo do passo t+1
+     * ∂C/∂W_x += dax * x_t^T, ∂C/∂W_h += dah * h_(t-1)^T, ∂C/∂b += dax
+     * ∂C/∂x_t = W_x^T * dax, ∂C/∂h_(t-1) = W_h^T * dah (+ termos diretos da célula)
+     */
+    fn backpropagate(
+        &mut self,
+        output_gradient: &Matrix,
+        prev_activations: &Matrix,
+    ) -> (Gradient, Matrix) {
+        let time_steps = self.steps.len();
+        assert!(prev_activations.cols() == time_steps && time_steps > 0);
+        assert!(output_gradient.rows() == self.hidden_size);
+        assert!(output_gradient.cols() == if self.return_sequences { time_steps } else { 1 });
+
+        let mut weight_gradient = Matrix::new(self.weights.rows(), self.weights.cols());
+        let mut bias_gradient = Matrix::new(self.biases.rows(), 1);
+        let mut input_gradient = Matrix::new(self.input_size, time_steps);
+        let mut dh_next = Matrix::new(self.hidden_size, 1);
+        let mut dc_next = Matrix::new(self.hidden_size, 1);
+
+        for t in (0..time_steps).rev() {
+            let mut dh = dh_next;
+            if self.return_sequences {
+                dh += &output_gradient.column(t);
+            } else if t == time_steps - 1 {
+                dh += output_gradient;
+            }
+            let h_prev = &self.hidden_states[t];
+            let gradient = self.cell.backward_step(
+                &self.steps[t],
+                &dh,
+                &dc_next,
+                h_prev,
+                &self.cell_states[t],
+            );
+
+            let x_t = prev_activations.column(t);
+            for i in 0..self.weights.rows() {
+                for j in 0..self.input_size {
+                    weight_gradient[i][j] += gradient.dax[i][0] * x_t[j][0];
+                }
+                for j in 0..self.hidden_size {
+                    weight_gradient[i][self.input_size + j] += gradient.dah[i][0] * h_prev[j][0];
+                }
+            }
+            bias_gradient += &gradient.dax;
+            input_gradient.set_column(
+                t,
+                &self.block_transpose_product(0, self.input_size, &gradient.dax),
+            );
+
+            dh_next =
+                self.block_transpose_product(self.input_size, self.hidden_size, &gradient.dah);
+            dh_next += &gradient.dh_prev;
+            dc_next = gradient.dc_prev;
+            //Início de um bloco da retropropagação truncada: o gradiente não passa para o bloco anterior
+            if self.truncation.is_some_and(|steps| t % steps == 0) {
+                dh_next.zero();
+                dc_next.zero();
+            }
+        }
+        (
+            Gradient {
+                weight: weight_gradient,
+                delta: bias_gradient,
+                rows: None,
+            },
+            input_gradient,
+        )
+    }
+
+    fn adjust_parameters(&mut self, gradients: &mut Gradient, learning_rate: f64) {
+        self.weights -= gradients.weight.mut_scalar_product(learning_rate);
+        self.biases -= gradients.delta.mut_scalar_product(learning_rate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_gradient_check::check_layer_gradients;
+    use crate::nn_layer::Layer;
+    use crate::nn_network::NeuralNetwork;
+
+    fn sequence() -> Matrix {
+        //2 entradas, 4 passos de tempo
+        Matrix::from_vec(2, 4, vec![0.5, -0.3, 0.8, 0.1, -0.6, 0.4, 0.2, 0.9])
+    }
+
+    #[test]
+    fn test_rnn_gradients() {
+        let mut rnn = Rnn::new::<Tanh>(2, 3);
+        check_layer_gradients(
+            &mut rnn,
+            &sequence(),
+            &Matrix::from_vec(3, 1, vec![0.7, -0.4, 1.2]),
+            1e-6,
+        );
+
+        rnn.set_return_sequences(true);
+        let cost_weights =
+            Matrix::from_vec(3, 4, (0..12).map(|i| (i as f64 * 0.37).sin()).collect());
+        check_layer_gradients(&mut rnn, &sequence(), &cost_weights, 1e-6);
+    }
+
+    #[test]
+    fn test_lstm_gradients() {
+        let mut lstm = Lstm::new(2, 3);
+        check_layer_gradients(
+            &mut lstm,
+            &sequence(),
+            &Matrix::from_vec(3, 1, vec![0.7, -0.4, 1.2]),
+            1e-6,
+        );
+
+        lstm.set_return_sequences(true);
+        let cost_weights =
+            Matrix::from_vec(3, 4, (0..12).map(|i| (i as f64 * 0.37).sin()).collect());
+        check_layer_gradients(&mut lstm, &sequence(), &cost_weights, 1e-6);
+    }
+
+    #[test]
+    fn test_gru_gradients() {
+        let mut gru = Gru::new(2, 3);
+        check_layer_gradients(
+            &mut gru,
+            &sequence(),
+            &Matrix::from_vec(3, 1, vec![0.7, -0.4, 1.2]),
+            1e-6,
+        );
+
+        gru.set_return_sequences(true);
+        let cost_weights =
+            Matrix::from_vec(3, 4, (0..12).map(|i| (i as f64 * 0.37).sin()).collect());
+        check_layer_gradients(&mut gru, &sequence(), &cost_weights, 1e-6);
+    }
+
+    #[test]
+    fn test_truncated_bptt() {
+        let mut lstm = Lstm::new(2, 3);
+        lstm.set_truncation(Some(2));
+        let input = sequence();
+        lstm.propagate(&input);
+        println!("Last hidden state: {}", lstm.neurons());
+        let (_, input_gradient) =
+            lstm.backpropagate(&Matrix::from_vec(3, 1, vec![1.0, 1.0, 1.0]), &input);
+        println!("Input gradient: {}", input_gradient);
+        //Apenas o último bloco (passos 2 e 3) recebe gradiente
+        assert!(input_gradient.column(0).is_zero() && input_gradient.column(1).is_zero());
+        assert!(!input_gradient.column(2).is_zero() && !input_gradient.column(3).is_zero());
+    }
+
+    #[test]
+    fn test_sequence_classification() {
+        //Classifica se a soma da sequência é positiva com LSTM + camada densa de saída
+        let mut network = NeuralNetwork::new(2, 0.3);
+        network.add_layer(Lstm::new(1, 4));
+        network.add_layer(Layer::new::<Sigmoid>(4, 1));
+        let samples: Vec<(Matrix, Matrix)> = (0..8)
+            .map(|s| {
+                let values: Vec<f64> = (0..5).map(|t| ((s * 5 + t) as f64 * 1.7).sin()).collect();
+                let label = if values.iter().sum::<f64>() > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                };
+                (
+                    Matrix::from_vec(1, 5, values),
+                    Matrix::from_vec(1, 1, vec![label]),
+                )
+            })
+            .collect();
+        let total_error = |network: &mut NeuralNetwork| -> f64 {
+            samples
+                .iter()
+                .map(|(input, expected)| (network.classify(input)[0][0] - expected[0][0]).powi(2))
+                .sum()
+        };
+
+        let initial_error = total_error(&mut network);
+        for _ in 0..100 {
+            for (input, expected) in &samples {
+                network.train(input.clone(), expected.clone());
+            }
+        }
+        let final_error = total_error(&mut network);
+        println!("Error: {} -> {}", initial_error, final_error);
+        assert!(final_error < initial_error);
+    }
+}
@@ -0,0 +1,208 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//http://neuralnetworksanddeeplearning.com/chap3.html#regularization
+//https://arxiv.org/abs/1711.05101 (Decoupled Weight Decay Regularization)
+use crate::nn_layer::{Gradient, NetworkLayer};
+use crate::nn_matrix::Matrix;
+
+/**
+ * Regularização dos parâmetros de uma camada.
+ * L1 e L2 são penalidades somadas ao custo: l1 * Σ|w| + 0.5 * l2 * Σw², com gradiente l1 * sinal(w) + l2 * w.
+ * Elastic-net é a combinação das duas.
+ * O weight decay desacoplado não altera o custo nem o gradiente: a cada ajuste os pesos
+ * são reduzidos diretamente, w = w - taxa de aprendizado * decay * w.
+ * Por padrão apenas os pesos são regularizados; os viéses podem ser incluídos com set_include_biases.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Regularization {
+    l1: f64,
+    l2: f64,
+    weight_decay: f64,
+    include_biases: bool,
+}
+
+impl Regularization {
+    pub fn new(l1: f64, l2: f64, weight_decay: f64) -> Regularization {
+        assert!(l1 >= 0.0 && l2 >= 0.0 && weight_decay >= 0.0);
+        Regularization {
+            l1,
+            l2,
+            weight_decay,
+            include_biases: false,
+        }
+    }
+
+    pub fn l1(lambda: f64) -> Regularization {
+        Regularization::new(lambda, 0.0, 0.0)
+    }
+
+    pub fn l2(lambda: f64) -> Regularization {
+        Regularization::new(0.0, lambda, 0.0)
+    }
+
+    pub fn elastic_net(l1: f64, l2: f64) -> Regularization {
+        Regularization::new(l1, l2, 0.0)
+    }
+
+    pub fn weight_decay(decay: f64) -> Regularization {
+        Regularization::new(0.0, 0.0, decay)
+    }
+
+    pub fn set_include_biases(&mut self, include_biases: bool) {
+        self.include_biases = include_biases;
+    }
+
+    /**
+     * Penalidade L1/L2 dos parâmetros da camada, somada ao custo
+     */
+    pub fn penalty(&self, layer: &dyn NetworkLayer) -> f64 {
+        let mut penalty = self.matrix_penalty(layer.weights());
+        if self.include_biases {
+            penalty += self.matrix_penalty(layer.biases());
+        }
+        penalty
+    }
+
+    fn matrix_penalty(&self, parameters: &Matrix) -> f64 {
+        parameters
+            .data()
+            .iter()
+            .map(|&w| self.l1 * w.abs() + 0.5 * self.l2 * w * w)
+            .sum()
+    }
+
+    /**
+     * Soma o gradiente da penalidade L1/L2 ao gradiente da camada.
+     * Em gradientes esparsos (ver Gradient), apenas as linhas presentes recebem o gradiente da penalidade.
+     */
+    pub fn add_gradient(&self, layer: &dyn NetworkLayer, gradient: &mut Gradient) {
+        let weights = layer.weights();
+        match &gradient.rows {
+            Some(rows) => self.add_rows_gradient(weights, rows, &mut gradient.weight),
+            None => {
+                let rows: Vec<usize> = (0..weights.rows()).collect();
+                self.add_rows_gradient(weights, &rows, &mut gradient.weight);
+            }
+        }
+        if self.include_biases {
+            let biases = layer.biases();
+            let rows: Vec<usize> = (0..biases.rows()).collect();
+            self.add_rows_gradient(biases, &rows, &mut gradient.delta);
+        }
+    }
+
+    //Linha i do gradiente corresponde à linha rows[i] dos parâmetros
+    fn add_rows_gradient(&self, parameters: &Matrix, rows: &[usize], gradient: &mut Matrix) {
+        if self.l1 == 0.0 && self.l2 == 0.0 {
+            return;
+        }
+        assert!(rows.len() == gradient.rows() && parameters.cols() == gradient.cols());
+        for (i, &row) in rows.iter().enumerate() {
+            for j in 0..parameters.cols() {
+                let w = parameters[row][j];
+                //A derivada de |w| em w = 0 é considerada 0
+                let l1_derivative = if w == 0.0 { 0.0 } else { w.signum() };
+                gradient[i][j] += self.l1 * l1_derivative + self.l2 * w;
+            }
+        }
+    }
+
+    /**
+     * Weight decay desacoplado, aplicado antes do ajuste dos parâmetros pelo gradiente
+     */
+    pub fn decay(&self, layer: &mut dyn NetworkLayer, learning_rate: f64) {
+        if self.weight_decay == 0.0 {
+            return;
+        }
+        let factor = 1.0 - learning_rate * self.weight_decay;
+        let weights = layer.weights().clone().scalar_product(factor);
+        layer.fix_weights(weights);
+        if self.include_biases {
+            let biases = layer.biases().clone().scalar_product(factor);
+            layer.fix_bias(biases);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_layer::{Layer, Sigmoid};
+
+    fn layer() -> Layer {
+        let mut layer = Layer::new::<Sigmoid>(2, 2);
+        layer.fix_weights(Matrix::from_vec(2, 2, vec![1.0, -2.0, 0.0, 3.0]));
+        layer.fix_bias(Matrix::from_vec(2, 1, vec![2.0, -1.0]));
+        layer
+    }
+
+    fn zero_gradient() -> Gradient {
+        Gradient {
+            weight: Matrix::new(2, 2),
+            delta: Matrix::new(2, 1),
+            rows: None,
+        }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn test_penalty() {
+        let layer = layer();
+        //Σ|w| = 6, Σw² = 14
+        assert!(close(Regularization::l1(0.1).penalty(&layer), 0.6));
+        assert!(close(Regularization::l2(0.1).penalty(&layer), 0.7));
+        assert!(close(
+            Regularization::elastic_net(0.1, 0.2).penalty(&layer),
+            0.6 + 1.4
+        ));
+        assert!(Regularization::weight_decay(0.1).penalty(&layer) == 0.0);
+
+        let mut with_biases = Regularization::l1(1.0);
+        with_biases.set_include_biases(true);
+        assert!(close(with_biases.penalty(&layer), 9.0));
+    }
+
+    #[test]
+    fn test_gradient() {
+        let layer = layer();
+        let mut gradient = zero_gradient();
+        Regularization::elastic_net(0.5, 0.1).add_gradient(&layer, &mut gradient);
+        assert!(gradient.weight == Matrix::from_vec(2, 2, vec![0.6, -0.7, 0.0, 0.8]));
+        assert!(gradient.delta == Matrix::new(2, 1));
+
+        let mut with_biases = Regularization::l2(1.0);
+        with_biases.set_include_biases(true);
+        let mut gradient = zero_gradient();
+        with_biases.add_gradient(&layer, &mut gradient);
+        assert!(gradient.delta == *layer.biases());
+    }
+
+    #[test]
+    fn test_weight_decay() {
+        let mut layer = layer();
+        Regularization::weight_decay(0.5).decay(&mut layer, 0.1);
+        assert!(*layer.weights() == Matrix::from_vec(2, 2, vec![0.95, -1.9, 0.0, 2.85]));
+        assert!(*layer.biases() == Matrix::from_vec(2, 1, vec![2.0, -1.0]));
+        //L1/L2 não alteram os pesos diretamente
+        Regularization::elastic_net(0.5, 0.5).decay(&mut layer, 0.1);
+        assert!(*layer.weights() == Matrix::from_vec(2, 2, vec![0.95, -1.9, 0.0, 2.85]));
+    }
+}
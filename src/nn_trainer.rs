@@ -0,0 +1,724 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::time::Instant;
+
+use crate::nn_dataset::{DataLoader, Dataset, collate};
+use crate::nn_matrix::Matrix;
+use crate::nn_metrics::argmax;
+use crate::nn_network::NeuralNetwork;
+
+/**
+ * Amostra de treinamento: (entrada, saída esperada)
+ */
+pub type Sample = (Matrix, Matrix);
+
+//Limiar das saídas na acurácia multi-rótulo de evaluate
+const MULTI_LABEL_THRESHOLD: f64 = 0.5;
+
+/**
+ * Resumo de um lote, repassado a Callback::on_batch_end
+ */
+#[derive(Debug, Clone)]
+pub struct BatchSummary {
+    pub epoch: usize,
+    pub batch: usize,
+    pub samples_seen: usize, //Amostras treinadas na época até o fim deste lote
+    pub loss: f64,           //Custo médio das amostras do lote
+    pub gradient_norm: f64,  //Norma global dos gradientes do lote, antes da limitação
+}
+
+/**
+ * Resumo de uma época, repassado a Callback::on_epoch_end e devolvido por Trainer::fit
+ */
+#[derive(Debug, Clone)]
+pub struct EpochSummary {
+    pub epoch: usize,
+    pub train_loss: f64,
+    pub validation_loss: Option<f64>,
+    pub validation_accuracy: Option<f64>, //Fração de amostras classificadas corretamente (ver evaluate)
+}
+
+/**
+ * Decisão de um callback ao fim de cada época
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingControl {
+    Continue,
+    Stop,
+}
+
+/**
+ * Ganchos chamados durante o treinamento (log, checkpoints, parada antecipada...).
+ * Recebem a rede para poder inspecionar, salvar ou restaurar os parâmetros.
+ * on_train_end é chamado uma vez, ao fim do treinamento (inclusive após uma parada antecipada).
+ */
+pub trait Callback {
+    fn on_batch_end(&mut self, _network: &mut NeuralNetwork, _batch: &BatchSummary) {}
+    fn on_epoch_end(
+        &mut self,
+        _network: &mut NeuralNetwork,
+        _epoch: &EpochSummary,
+    ) -> TrainingControl {
+        TrainingControl::Continue
+    }
+    fn on_train_end(&mut self, _network: &mut NeuralNetwork) {}
+}
+
+/**
+ * Laço de treinamento reutilizável: percorre um Dataset por várias épocas nos lotes de um DataLoader
+ * (tamanho do lote, embaralhamento e semente são configurados nele), avalia o conjunto de validação
+ * e chama os callbacks.
+ *
+ * As amostras (matrizes coluna) de um lote são unidas em uma matriz, uma por coluna, e treinadas
+ * em uma única propagação com train: o custo é a média do lote e camadas como a BatchNorm veem o lote inteiro.
+ * Com lotes de tamanho 1, a amostra é treinada como está (ex: sequências, com um passo de tempo por coluna).
+ */
+pub struct Trainer {
+    epochs: usize,
+    callbacks: Vec<Box<dyn Callback>>,
+}
+
+impl Trainer {
+    pub fn new(epochs: usize) -> Trainer {
+        Trainer {
+            epochs,
+            callbacks: Vec::new(),
+        }
+    }
+
+    pub fn add_callback(&mut self, callback: impl Callback + 'static) {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /**
+     * Treina a rede com os lotes que o loader forma sobre train e devolve o resumo de cada época executada.
+     * O treinamento termina antes das épocas configuradas se algum callback devolver Stop.
+     */
+    pub fn fit(
+        &mut self,
+        network: &mut NeuralNetwork,
+        train: &mut dyn Dataset,
+        loader: &mut DataLoader,
+        mut validation: Option<&mut dyn Dataset>,
+    ) -> Vec<EpochSummary> {
+        assert!(loader.num_batches() > 0);
+        let mut history = Vec::with_capacity(self.epochs);
+        for epoch in 0..self.epochs {
+            let mut epoch_loss = 0.0;
+            let mut samples_seen = 0;
+            for (batch, indices) in loader.batch_indices().into_iter().enumerate() {
+                let batch_loss = train_on_batch(network, collate(train, &indices));
+                epoch_loss += batch_loss * indices.len() as f64;
+                samples_seen += indices.len();
+                let summary = BatchSummary {
+                    epoch,
+                    batch,
+                    samples_seen,
+                    loss: batch_loss,
+                    gradient_norm: network.last_gradient_norm(),
+                };
+                for callback in self.callbacks.iter_mut() {
+                    callback.on_batch_end(network, &summary);
+                }
+            }
+
+            let (validation_loss, validation_accuracy) = match validation.as_deref_mut() {
+                Some(dataset) if !dataset.is_empty() => {
+                    let (loss, accuracy) = evaluate(network, dataset);
+                    (Some(loss), Some(accuracy))
+                }
+                _ => (None, None),
+            };
+            let summary = EpochSummary {
+                epoch,
+                train_loss: epoch_loss / samples_seen as f64,
+                validation_loss,
+                validation_accuracy,
+            };
+            //Todos os callbacks são chamados, mesmo que um deles peça a parada
+            let mut control = TrainingControl::Continue;
+            for callback in self.callbacks.iter_mut() {
+                if callback.on_epoch_end(network, &summary) == TrainingControl::Stop {
+                    control = TrainingControl::Stop;
+                }
+            }
+            history.push(summary);
+            if control == TrainingControl::Stop {
+                break;
+            }
+        }
+        for callback in self.callbacks.iter_mut() {
+            callback.on_train_end(network);
+        }
+        history
+    }
+}
+
+/**
+ * Treina um lote e devolve o custo médio das amostras, calculado na propagação do treinamento
+ * (as camadas mantêm a saída até a próxima propagação)
+ */
+fn train_on_batch(network: &mut NeuralNetwork, (input, expected): Sample) -> f64 {
+    network.train(input, expected.clone());
+    network.cost(&expected)
+}
+
+/**
+ * Custo médio e acurácia em modo de inferência, por coluna.
+ * Uma amostra está correta se a maior saída coincide com a maior saída esperada ou,
+ * com um custo multi-rótulo (ex: BinaryCrossEntropy), se todos os rótulos previstos com o limiar
+ * MULTI_LABEL_THRESHOLD coincidem com os esperados (acurácia de subconjunto, como em MultiLabelMetrics).
+ */
+pub fn evaluate<D: Dataset + ?Sized>(network: &mut NeuralNetwork, dataset: &mut D) -> (f64, f64) {
+    let multi_label = network.loss().multi_label();
+    let mut total_loss = 0.0;
+    let mut right = 0;
+    let mut total = 0;
+    for index in 0..dataset.len() {
+        let (input, expected) = dataset.get(index);
+        network.classify(&input);
+        total_loss += network.cost(&expected);
+        let output = network.borrow_layer(network.num_layers()).neurons();
+        for j in 0..output.cols() {
+            let correct = if multi_label {
+                (0..output.rows())
+                    .all(|i| (output[i][j] >= MULTI_LABEL_THRESHOLD) == (expected[i][j] >= 0.5))
+            } else {
+                argmax(&output.column(j)) == argmax(&expected.column(j))
+            };
+            if correct {
+                right += 1;
+            }
+            total += 1;
+        }
+    }
+    (
+        total_loss / dataset.len() as f64,
+        right as f64 / total as f64,
+    )
+}
+
+/**
+ * Imprime o progresso a cada tantos lotes e o resumo de cada época
+ */
+pub struct ProgressLogger {
+    every_batches: usize,
+    start: Instant,
+}
+
+impl ProgressLogger {
+    pub fn new(every_batches: usize) -> ProgressLogger {
+        ProgressLogger {
+            every_batches,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Callback for ProgressLogger {
+    fn on_batch_end(&mut self, _network: &mut NeuralNetwork, batch: &BatchSummary) {
+        if self.every_batches > 0 && (batch.batch + 1).is_multiple_of(self.every_batches) {
+            println!(
+                "\tEpoch {}: trained on {} samples. Loss: {:.6}. Gradient norm: {:.4}. Training Time is: {:?}.",
+                batch.epoch,
+                batch.samples_seen,
+                batch.loss,
+                batch.gradient_norm,
+                self.start.elapsed()
+            );
+        }
+    }
+
+    fn on_epoch_end(
+        &mut self,
+        _network: &mut NeuralNetwork,
+        epoch: &EpochSummary,
+    ) -> TrainingControl {
+        print!(
+            "Epoch {}. Train loss: {:.6}.",
+            epoch.epoch, epoch.train_loss
+        );
+        if let (Some(loss), Some(accuracy)) = (epoch.validation_loss, epoch.validation_accuracy) {
+            print!(
+                " Validation loss: {:.6}. Accuracy: {:.2}%.",
+                loss,
+                accuracy * 100.0
+            );
+        }
+        println!(" Training Time is: {:?}", self.start.elapsed());
+        TrainingControl::Continue
+    }
+}
+
+/**
+ * Salva um checkpoint da rede ao fim de cada época (o arquivo é sobrescrito)
+ */
+pub struct CheckpointSaver {
+    file_name: String,
+}
+
+impl CheckpointSaver {
+    pub fn new(file_name: &str) -> CheckpointSaver {
+        CheckpointSaver {
+            file_name: file_name.to_string(),
+        }
+    }
+}
+
+impl Callback for CheckpointSaver {
+    fn on_epoch_end(
+        &mut self,
+        network: &mut NeuralNetwork,
+        epoch: &EpochSummary,
+    ) -> TrainingControl {
+        if let Err(error) = network.save_checkpoint(&self.file_name) {
+            println!(
+                "Falha ao salvar checkpoint da época {}: {}",
+                epoch.epoch, error
+            );
+        }
+        TrainingControl::Continue
+    }
+}
+
+/**
+ * Métrica acompanhada pela parada antecipada
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Monitor {
+    TrainLoss,
+    ValidationLoss,
+    ValidationAccuracy,
+}
+
+impl Monitor {
+    fn value(&self, epoch: &EpochSummary) -> Option<f64> {
+        match self {
+            Monitor::TrainLoss => Some(epoch.train_loss),
+            Monitor::ValidationLoss => epoch.validation_loss,
+            Monitor::ValidationAccuracy => epoch.validation_accuracy,
+        }
+    }
+
+    //Custos melhoram diminuindo; a acurácia, aumentando
+    fn improvement(&self, value: f64, best: f64) -> f64 {
+        match self {
+            Monitor::TrainLoss | Monitor::ValidationLoss => best - value,
+            Monitor::ValidationAccuracy => value - best,
+        }
+    }
+}
+
+/**
+ * Parada antecipada: interrompe o treinamento quando a métrica não melhora mais que min_delta
+ * por patience épocas seguidas. Guarda uma cópia dos parâmetros (pesos, viéses e estado das camadas)
+ * da melhor época e, se restore_best_weights estiver ativo, restaura essa cópia ao fim do treinamento.
+ */
+pub struct EarlyStopping {
+    monitor: Monitor,
+    patience: usize,
+    min_delta: f64,
+    restore_best_weights: bool,
+    best_value: Option<f64>,
+    best_epoch: Option<usize>,
+    best_parameters: Vec<Vec<Matrix>>,
+    epochs_without_improvement: usize,
+    stopped_epoch: Option<usize>,
+}
+
+impl EarlyStopping {
+    pub fn new(monitor: Monitor, patience: usize, min_delta: f64) -> EarlyStopping {
+        assert!(min_delta >= 0.0);
+        EarlyStopping {
+            monitor,
+            patience,
+            min_delta,
+            restore_best_weights: true,
+            best_value: None,
+            best_epoch: None,
+            best_parameters: Vec::new(),
+            epochs_without_improvement: 0,
+            stopped_epoch: None,
+        }
+    }
+
+    pub fn set_restore_best_weights(&mut self, restore: bool) {
+        self.restore_best_weights = restore;
+    }
+
+    pub fn best_value(&self) -> Option<f64> {
+        self.best_value
+    }
+
+    pub fn best_epoch(&self) -> Option<usize> {
+        self.best_epoch
+    }
+
+    /**
+     * Época em que o treinamento foi interrompido (None se todas as épocas foram executadas)
+     */
+    pub fn stopped_epoch(&self) -> Option<usize> {
+        self.stopped_epoch
+    }
+
+    fn snapshot(&mut self, network: &NeuralNetwork) {
+        self.best_parameters = (1..=network.num_layers())
+            .map(|i| {
+                let layer = network.borrow_layer(i);
+                let mut parameters = vec![layer.weights().clone(), layer.biases().clone()];
+                parameters.extend(layer.state());
+                parameters
+            })
+            .collect();
+    }
+
+    fn restore(&mut self, network: &mut NeuralNetwork) {
+        for (i, mut parameters) in std::mem::take(&mut self.best_parameters)
+            .into_iter()
+            .enumerate()
+        {
+            let layer = network.borrow_layer_mut(i + 1);
+            let state = parameters.split_off(2);
+            let biases = parameters.pop().unwrap();
+            let weights = parameters.pop().unwrap();
+            layer.fix_weights(weights);
+            layer.fix_bias(biases);
+            layer.fix_state(state);
+        }
+    }
+}
+
+impl Callback for EarlyStopping {
+    fn on_epoch_end(
+        &mut self,
+        network: &mut NeuralNetwork,
+        epoch: &EpochSummary,
+    ) -> TrainingControl {
+        let value = self
+            .monitor
+            .value(epoch)
+            .expect("Métrica monitorada indisponível (falta o conjunto de validação?)");
+        let improved = match self.best_value {
+            None => true,
+            Some(best) => self.monitor.improvement(value, best) > self.min_delta,
+        };
+        if improved {
+            self.best_value = Some(value);
+            self.best_epoch = Some(epoch.epoch);
+            self.epochs_without_improvement = 0;
+            if self.restore_best_weights {
+                self.snapshot(network);
+            }
+            return TrainingControl::Continue;
+        }
+        self.epochs_without_improvement += 1;
+        if self.epochs_without_improvement >= self.patience {
+            self.stopped_epoch = Some(epoch.epoch);
+            return TrainingControl::Stop;
+        }
+        TrainingControl::Continue
+    }
+
+    fn on_train_end(&mut self, network: &mut NeuralNetwork) {
+        if self.restore_best_weights && !self.best_parameters.is_empty() {
+            self.restore(network);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_layer::{Layer, NetworkLayer, Sigmoid, Tanh};
+    use crate::nn_loss::BinaryCrossEntropy;
+    use crate::nn_normalization::BatchNorm;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn xor_samples() -> Vec<Sample> {
+        [
+            (0.0, 0.0, 0.0),
+            (0.0, 1.0, 1.0),
+            (1.0, 0.0, 1.0),
+            (1.0, 1.0, 0.0),
+        ]
+        .iter()
+        .map(|&(a, b, y)| {
+            (
+                Matrix::from_vec(2, 1, vec![a, b]),
+                Matrix::from_vec(2, 1, vec![y, 1.0 - y]),
+            )
+        })
+        .collect()
+    }
+
+    fn xor_network() -> NeuralNetwork {
+        let mut network = NeuralNetwork::new(2, 0.5);
+        network.add_layer(Layer::new::<Tanh>(2, 6));
+        network.add_layer(Layer::new::<Sigmoid>(6, 2));
+        network
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        batches: usize,
+        epochs: usize,
+        stop_after: Option<usize>,
+        gradient_norms: Vec<f64>,
+    }
+
+    struct SharedCounter(Rc<RefCell<Counter>>);
+
+    impl Callback for SharedCounter {
+        fn on_batch_end(&mut self, _network: &mut NeuralNetwork, batch: &BatchSummary) {
+            let mut counter = self.0.borrow_mut();
+            counter.batches += 1;
+            counter.gradient_norms.push(batch.gradient_norm);
+        }
+        fn on_epoch_end(
+            &mut self,
+            _network: &mut NeuralNetwork,
+            _epoch: &EpochSummary,
+        ) -> TrainingControl {
+            let mut counter = self.0.borrow_mut();
+            counter.epochs += 1;
+            match counter.stop_after {
+                Some(epochs) if counter.epochs >= epochs => TrainingControl::Stop,
+                _ => TrainingControl::Continue,
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit() {
+        let mut samples = xor_samples();
+        let mut validation = xor_samples();
+        let mut network = xor_network();
+        let mut loader = DataLoader::for_dataset(&samples, 2);
+        loader.set_shuffle(true);
+        let mut trainer = Trainer::new(400);
+        let history = trainer.fit(
+            &mut network,
+            &mut samples,
+            &mut loader,
+            Some(&mut validation),
+        );
+        assert!(history.len() == 400);
+        let first = &history[0];
+        let last = &history[399];
+        println!("Loss: {} -> {}", first.train_loss, last.train_loss);
+        assert!(last.train_loss < first.train_loss);
+        assert!(last.validation_loss.unwrap() < first.validation_loss.unwrap());
+        assert!(last.validation_accuracy.is_some());
+    }
+
+    #[test]
+    fn test_callbacks() {
+        let mut samples = xor_samples();
+        let mut network = xor_network();
+        let counter = Rc::new(RefCell::new(Counter {
+            stop_after: Some(3),
+            ..Default::default()
+        }));
+        let mut loader = DataLoader::for_dataset(&samples, 3);
+        let mut trainer = Trainer::new(10);
+        trainer.add_callback(SharedCounter(counter.clone()));
+        let history = trainer.fit(&mut network, &mut samples, &mut loader, None);
+        //4 amostras em lotes de 3: 2 lotes por época, parando na terceira época
+        assert!(history.len() == 3);
+        assert!(counter.borrow().epochs == 3);
+        assert!(counter.borrow().batches == 6);
+        assert!(
+            counter
+                .borrow()
+                .gradient_norms
+                .iter()
+                .all(|&norm| norm > 0.0)
+        );
+        assert!(history[0].validation_loss.is_none());
+    }
+
+    //Colunas da saída da primeira camada ao fim de cada lote
+    struct BatchColumns(Rc<RefCell<Vec<usize>>>);
+
+    impl Callback for BatchColumns {
+        fn on_batch_end(&mut self, network: &mut NeuralNetwork, _batch: &BatchSummary) {
+            self.0
+                .borrow_mut()
+                .push(network.borrow_layer(1).neurons().cols());
+        }
+    }
+
+    #[test]
+    fn test_fit_mini_batches() {
+        let mut samples = xor_samples();
+        let mut network = NeuralNetwork::new(3, 0.5);
+        network.add_layer(Layer::new::<Tanh>(2, 6));
+        network.add_layer(BatchNorm::new_1d(6));
+        network.add_layer(Layer::new::<Sigmoid>(6, 2));
+        let columns = Rc::new(RefCell::new(Vec::new()));
+        let mut loader = DataLoader::for_dataset(&samples, 3);
+        loader.set_shuffle(true);
+        loader.set_seed(5);
+        let mut trainer = Trainer::new(2);
+        trainer.add_callback(BatchColumns(columns.clone()));
+        trainer.fit(&mut network, &mut samples, &mut loader, None);
+        //Uma única propagação por lote, com uma amostra por coluna
+        assert!(*columns.borrow() == [3, 1, 3, 1]);
+        //A BatchNorm normalizou lotes de 3 amostras: variância acumulada diferente de zero
+        assert!(!network.borrow_layer(2).state()[1].is_zero());
+
+        //O último lote incompleto é descartado pelo DataLoader
+        columns.borrow_mut().clear();
+        loader.set_drop_last(true);
+        let history = trainer.fit(&mut network, &mut samples, &mut loader, None);
+        assert!(*columns.borrow() == [3, 3]);
+        assert!(history[0].train_loss.is_finite());
+    }
+
+    #[test]
+    fn test_fit_seed() {
+        //Com a mesma semente no DataLoader, o treinamento é reproduzível
+        let train = |seed: u64| -> Matrix {
+            let mut samples = xor_samples();
+            let mut network = xor_network();
+            network
+                .borrow_layer_mut(1)
+                .fix_weights(Matrix::from_vec(6, 2, vec![0.1; 12]));
+            network
+                .borrow_layer_mut(2)
+                .fix_weights(Matrix::from_vec(2, 6, vec![0.2; 12]));
+            let mut loader = DataLoader::for_dataset(&samples, 1);
+            loader.set_shuffle(true);
+            loader.set_seed(seed);
+            Trainer::new(3).fit(&mut network, &mut samples, &mut loader, None);
+            network.borrow_layer(1).weights().clone()
+        };
+        assert!(train(3) == train(3));
+    }
+
+    #[test]
+    fn test_evaluate_multi_label() {
+        //Saídas fixas [0.62, 0.88] e rótulos esperados [0, 1]
+        let mut network = NeuralNetwork::new(1, 0.1);
+        let mut layer = Layer::new::<Sigmoid>(1, 2);
+        layer.fix_weights(Matrix::new(2, 1));
+        layer.fix_bias(Matrix::from_vec(2, 1, vec![0.5, 2.0]));
+        network.add_layer(layer);
+        let mut samples = vec![(Matrix::new(1, 1), Matrix::from_vec(2, 1, vec![0.0, 1.0]))];
+        //A maior saída está correta...
+        assert!(evaluate(&mut network, &mut samples).1 == 1.0);
+        //...mas o primeiro rótulo também é previsto
+        network.set_loss(BinaryCrossEntropy {});
+        assert!(evaluate(&mut network, &mut samples).1 == 0.0);
+    }
+
+    #[test]
+    fn test_checkpoint_saver() {
+        let mut samples = xor_samples();
+        let mut network = xor_network();
+        let file_name = std::env::temp_dir().join("nn_trainer_checkpoint_test.bin");
+        let file_name = file_name.to_str().unwrap();
+        let mut loader = DataLoader::for_dataset(&samples, 4);
+        let mut trainer = Trainer::new(2);
+        trainer.add_callback(CheckpointSaver::new(file_name));
+        trainer.fit(&mut network, &mut samples, &mut loader, None);
+
+        let mut restored = xor_network();
+        restored.load_checkpoint(file_name).unwrap();
+        std::fs::remove_file(file_name).unwrap();
+        assert!(network.borrow_layer(1).weights() == restored.borrow_layer(1).weights());
+    }
+
+    fn summary(epoch: usize, validation_loss: f64) -> EpochSummary {
+        EpochSummary {
+            epoch,
+            train_loss: 1.0,
+            validation_loss: Some(validation_loss),
+            validation_accuracy: Some(0.5),
+        }
+    }
+
+    #[test]
+    fn test_early_stopping() {
+        let mut network = xor_network();
+        let mut early_stopping = EarlyStopping::new(Monitor::ValidationLoss, 2, 0.01);
+        let continue_ = TrainingControl::Continue;
+        assert!(early_stopping.on_epoch_end(&mut network, &summary(0, 0.5)) == continue_);
+        let best_weights = network.borrow_layer(1).weights().clone();
+        assert!(early_stopping.on_epoch_end(&mut network, &summary(1, 0.3)) == continue_);
+        let best_weights_after = network.borrow_layer(1).weights().clone();
+        assert!(best_weights == best_weights_after);
+
+        //Melhora menor que min_delta não conta
+        network.train(xor_samples()[1].0.clone(), xor_samples()[1].1.clone());
+        assert!(early_stopping.on_epoch_end(&mut network, &summary(2, 0.295)) == continue_);
+        assert!(
+            early_stopping.on_epoch_end(&mut network, &summary(3, 0.4)) == TrainingControl::Stop
+        );
+        assert!(early_stopping.best_epoch() == Some(1));
+        assert!(early_stopping.best_value() == Some(0.3));
+        assert!(early_stopping.stopped_epoch() == Some(3));
+
+        assert!(*network.borrow_layer(1).weights() != best_weights);
+        early_stopping.on_train_end(&mut network);
+        assert!(*network.borrow_layer(1).weights() == best_weights);
+    }
+
+    #[test]
+    fn test_early_stopping_accuracy() {
+        let mut network = xor_network();
+        let mut early_stopping = EarlyStopping::new(Monitor::ValidationAccuracy, 1, 0.0);
+        let mut epoch = summary(0, 1.0);
+        assert!(early_stopping.on_epoch_end(&mut network, &epoch) == TrainingControl::Continue);
+        epoch.validation_accuracy = Some(0.75);
+        assert!(early_stopping.on_epoch_end(&mut network, &epoch) == TrainingControl::Continue);
+        epoch.validation_accuracy = Some(0.5);
+        assert!(early_stopping.on_epoch_end(&mut network, &epoch) == TrainingControl::Stop);
+        assert!(early_stopping.best_value() == Some(0.75));
+    }
+
+    #[test]
+    fn test_fit_restores_best_weights() {
+        let mut samples = xor_samples();
+        let mut validation = xor_samples();
+        let mut network = xor_network();
+        let mut loader = DataLoader::for_dataset(&samples, 1);
+        loader.set_shuffle(true);
+        let mut trainer = Trainer::new(200);
+        trainer.add_callback(EarlyStopping::new(Monitor::ValidationLoss, 5, 0.0));
+        let history = trainer.fit(
+            &mut network,
+            &mut samples,
+            &mut loader,
+            Some(&mut validation),
+        );
+        let best_loss = history
+            .iter()
+            .map(|epoch| epoch.validation_loss.unwrap())
+            .fold(f64::INFINITY, f64::min);
+        let (restored_loss, _) = evaluate(&mut network, &mut validation);
+        println!(
+            "Epochs: {}. Best loss: {}. Restored loss: {}",
+            history.len(),
+            best_loss,
+            restored_loss
+        );
+        assert!((restored_loss - best_loss).abs() < 1e-12);
+    }
+}
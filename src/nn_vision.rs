@@ -0,0 +1,421 @@
+/**
+ *  Copyright 2025 Eric Zancanaro
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Lesser General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//https://github.com/zalandoresearch/fashion-mnist
+//https://www.cs.toronto.edu/~kriz/cifar.html
+use std::io::Read;
+
+use crate::nn_dataset::{Dataset, SharedDataset};
+use crate::nn_emnist::check_headers;
+use crate::nn_gzip::DataFile;
+use crate::nn_idx::IdxHeader;
+use crate::nn_matrix::Matrix;
+use crate::nn_trainer::Sample;
+
+pub const FASHION_MNIST_CLASSES: [&str; 10] = [
+    "T-shirt/top",
+    "Trouser",
+    "Pullover",
+    "Dress",
+    "Coat",
+    "Sandal",
+    "Shirt",
+    "Sneaker",
+    "Bag",
+    "Ankle boot",
+];
+
+pub const CIFAR10_CLASSES: [&str; 10] = [
+    "airplane",
+    "automobile",
+    "bird",
+    "cat",
+    "deer",
+    "dog",
+    "frog",
+    "horse",
+    "ship",
+    "truck",
+];
+
+//Registro do CIFAR-10: 1 byte de rótulo seguido dos canais vermelho, verde e azul de 32x32
+const CIFAR10_SHAPE: ImageShape = ImageShape {
+    channels: 3,
+    height: 32,
+    width: 32,
+};
+
+/**
+ * Formato das imagens: as entradas das amostras são matrizes coluna com channels * height * width linhas,
+ * com os pixels de cada canal contíguos e percorridos linha a linha (mesma convenção da BatchNorm::new_2d).
+ * Camadas densas usam a coluna diretamente; camadas que precisam da forma da imagem usam channel.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageShape {
+    pub channels: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl ImageShape {
+    pub fn new(channels: usize, height: usize, width: usize) -> ImageShape {
+        ImageShape {
+            channels,
+            height,
+            width,
+        }
+    }
+
+    /**
+     * Número de elementos da imagem (linhas da entrada)
+     */
+    pub fn len(&self) -> usize {
+        self.channels * self.height * self.width
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /**
+     * Canal da entrada (matriz coluna) como uma matriz height x width
+     */
+    pub fn channel(&self, input: &Matrix, channel: usize) -> Matrix {
+        assert!(input.rows() == self.len() && input.cols() == 1 && channel < self.channels);
+        let spatial_size = self.height * self.width;
+        let start = channel * spatial_size;
+        Matrix::from_vec(
+            self.height,
+            self.width,
+            input.data()[start..start + spatial_size].to_vec(),
+        )
+    }
+
+    /**
+     * Caminho inverso de channel: une os canais (height x width) em uma matriz coluna
+     */
+    pub fn join_channels(&self, channels: &[Matrix]) -> Matrix {
+        assert!(channels.len() == self.channels);
+        let mut data = Vec::with_capacity(self.len());
+        for channel in channels {
+            assert!(channel.rows() == self.height && channel.cols() == self.width);
+            data.extend_from_slice(channel.data());
+        }
+        Matrix::from_vec(self.len(), 1, data)
+    }
+}
+
+/**
+ * Conjunto de imagens u8 carregado na memória, com os nomes das classes.
+ * As entradas têm os pixels normalizados para 0..1 no formato de ImageShape,
+ * e as saídas esperadas uma posição por classe.
+ */
+pub struct ImageDataset {
+    images: Vec<u8>,
+    labels: Vec<u8>,
+    shape: ImageShape,
+    class_names: Vec<String>,
+}
+
+impl ImageDataset {
+    pub fn new(
+        images: Vec<u8>,
+        labels: Vec<u8>,
+        shape: ImageShape,
+        class_names: Vec<String>,
+    ) -> ImageDataset {
+        assert!(images.len() == labels.len() * shape.len());
+        assert!(
+            labels
+                .iter()
+                .all(|&label| (label as usize) < class_names.len())
+        );
+        ImageDataset {
+            images,
+            labels,
+            shape,
+            class_names,
+        }
+    }
+
+    /**
+     * Arquivos IDX do Fashion-MNIST (mesmo formato do MNIST, 28x28 em tons de cinza).
+     * Diferente do EMNIST, as imagens já estão na orientação correta.
+     */
+    pub fn fashion_mnist(
+        label_file_name: &str,
+        image_file_name: &str,
+    ) -> std::io::Result<ImageDataset> {
+        let mut label_file = DataFile::open(label_file_name)?;
+        let mut image_file = DataFile::open(image_file_name)?;
+        let labels_header = IdxHeader::read_from(&mut label_file)?;
+        let images_header = IdxHeader::read_from(&mut image_file)?;
+        check_headers(
+            &labels_header,
+            &images_header,
+            label_file_name,
+            image_file_name,
+        )?;
+        let mut labels = vec![0; labels_header.num_items()];
+        label_file.read_exact(&mut labels)?;
+        let mut images = vec![0; images_header.num_items() * images_header.item_len()];
+        image_file.read_exact(&mut images)?;
+        let shape = ImageShape::new(1, images_header.dims()[1], images_header.dims()[2]);
+        let class_names = FASHION_MNIST_CLASSES.map(String::from).to_vec();
+        check_labels(&labels, class_names.len())?;
+        Ok(ImageDataset::new(images, labels, shape, class_names))
+    }
+
+    /**
+     * Arquivos de treino ou teste do Fashion-MNIST no diretório, com os nomes originais
+     * (ex: train-images-idx3-ubyte), descomprimidos ou .gz
+     */
+    pub fn open_fashion_mnist(dir: &str, train: bool) -> std::io::Result<ImageDataset> {
+        let prefix = if train { "train" } else { "t10k" };
+        let file_name = |kind: &str| {
+            let file_name = format!("{}/{}-{}", dir, prefix, kind);
+            let compressed = format!("{}.gz", file_name);
+            if !std::path::Path::new(&file_name).exists()
+                && std::path::Path::new(&compressed).exists()
+            {
+                compressed
+            } else {
+                file_name
+            }
+        };
+        ImageDataset::fashion_mnist(
+            &file_name("labels-idx1-ubyte"),
+            &file_name("images-idx3-ubyte"),
+        )
+    }
+
+    /**
+     * Arquivos binários do CIFAR-10 (ex: data_batch_1.bin), cada um com registros de 3073 bytes:
+     * o rótulo seguido dos canais vermelho, verde e azul de 32x32, já no formato de ImageShape.
+     */
+    pub fn cifar10(
+        file_names: &[String],
+        class_names: Vec<String>,
+    ) -> std::io::Result<ImageDataset> {
+        let record_len = 1 + CIFAR10_SHAPE.len();
+        let mut images = Vec::new();
+        let mut labels = Vec::new();
+        for file_name in file_names {
+            let mut data = Vec::new();
+            DataFile::open(file_name)?.read_to_end(&mut data)?;
+            if data.is_empty() || !data.len().is_multiple_of(record_len) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "{}: tamanho não é múltiplo de {} bytes",
+                        file_name, record_len
+                    ),
+                ));
+            }
+            for record in data.chunks(record_len) {
+                labels.push(record[0]);
+                images.extend_from_slice(&record[1..]);
+            }
+        }
+        check_labels(&labels, class_names.len())?;
+        Ok(ImageDataset::new(
+            images,
+            labels,
+            CIFAR10_SHAPE,
+            class_names,
+        ))
+    }
+
+    /**
+     * Lotes de treino (data_batch_1.bin a data_batch_5.bin) ou de teste (test_batch.bin) do diretório
+     * cifar-10-batches-bin. Os nomes das classes vêm de batches.meta.txt, se existir.
+     */
+    pub fn open_cifar10(dir: &str, train: bool) -> std::io::Result<ImageDataset> {
+        let file_names: Vec<String> = if train {
+            (1..=5)
+                .map(|batch| format!("{}/data_batch_{}.bin", dir, batch))
+                .collect()
+        } else {
+            vec![format!("{}/test_batch.bin", dir)]
+        };
+        let class_names = match std::fs::read_to_string(format!("{}/batches.meta.txt", dir)) {
+            Ok(meta) => meta
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect(),
+            Err(_) => CIFAR10_CLASSES.map(String::from).to_vec(),
+        };
+        ImageDataset::cifar10(&file_names, class_names)
+    }
+
+    pub fn shape(&self) -> ImageShape {
+        self.shape
+    }
+
+    pub fn num_classes(&self) -> usize {
+        self.class_names.len()
+    }
+
+    pub fn class_names(&self) -> &[String] {
+        &self.class_names
+    }
+
+    pub fn class_name(&self, class: usize) -> &str {
+        &self.class_names[class]
+    }
+
+    /**
+     * Pixels da imagem no formato de ImageShape, sem normalização
+     */
+    pub fn image(&self, index: usize) -> &[u8] {
+        let image_len = self.shape.len();
+        &self.images[index * image_len..(index + 1) * image_len]
+    }
+}
+
+impl Dataset for ImageDataset {
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn get(&mut self, index: usize) -> Sample {
+        self.sample(index)
+    }
+
+    fn label(&mut self, index: usize) -> usize {
+        self.labels[index] as usize
+    }
+}
+
+impl SharedDataset for ImageDataset {
+    fn sample(&self, index: usize) -> Sample {
+        let pixels = self
+            .image(index)
+            .iter()
+            .map(|&pixel| pixel as f64 / 255.0)
+            .collect();
+        let mut expected = Matrix::new(self.num_classes(), 1);
+        expected[self.labels[index] as usize][0] = 1.0;
+        (Matrix::from_vec(self.shape.len(), 1, pixels), expected)
+    }
+}
+
+fn check_labels(labels: &[u8], num_classes: usize) -> std::io::Result<()> {
+    match labels.iter().find(|&&label| label as usize >= num_classes) {
+        Some(label) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Rótulo {} fora das {} classes", label, num_classes),
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope. (Rust Book)
+    use super::*;
+    use crate::nn_idx::{IdxTensor, IdxType};
+    use crate::nn_layer::NetworkLayer;
+    use crate::nn_normalization::BatchNorm;
+
+    #[test]
+    fn test_image_shape() {
+        let shape = ImageShape::new(2, 2, 3);
+        assert!(shape.len() == 12);
+        let input = Matrix::from_vec(12, 1, (0..12).map(|v| v as f64).collect());
+        let second = shape.channel(&input, 1);
+        assert!(second == Matrix::from_vec(2, 3, vec![6.0, 7.0, 8.0, 9.0, 10.0, 11.0]));
+        let channels = [shape.channel(&input, 0), second];
+        assert!(shape.join_channels(&channels) == input);
+    }
+
+    #[test]
+    fn test_fashion_mnist() {
+        let dir = std::env::temp_dir().join("nn_vision_fashion");
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.to_str().unwrap();
+        IdxTensor::new(IdxType::U8, vec![2], vec![9.0, 0.0])
+            .write_file(&format!("{}/t10k-labels-idx1-ubyte", dir))
+            .unwrap();
+        let pixels: Vec<f64> = (0..12).map(|v| (v * 20) as f64).collect();
+        IdxTensor::new(IdxType::U8, vec![2, 2, 3], pixels)
+            .write_file(&format!("{}/t10k-images-idx3-ubyte", dir))
+            .unwrap();
+
+        let mut dataset = ImageDataset::open_fashion_mnist(dir, false).unwrap();
+        assert!(dataset.len() == 2 && dataset.shape() == ImageShape::new(1, 2, 3));
+        let label = dataset.label(0);
+        assert!(dataset.class_name(label) == "Ankle boot");
+        //Sem transposição: mesma ordem do arquivo
+        assert!(dataset.image(1) == [120, 140, 160, 180, 200, 220]);
+        let (input, expected) = dataset.get(0);
+        assert!(input.rows() == 6 && input[1][0] == 20.0 / 255.0);
+        assert!(expected.rows() == 10 && expected[9][0] == 1.0);
+        assert!(ImageDataset::open_fashion_mnist(dir, true).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cifar10() {
+        let dir = std::env::temp_dir().join("nn_vision_cifar");
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.to_str().unwrap();
+        //2 registros: rótulos 3 e 8, canal c com todos os pixels iguais a 10 * (c + 1) + registro
+        let mut data = Vec::new();
+        for (record, label) in [3u8, 8].iter().enumerate() {
+            data.push(*label);
+            for channel in 0..3 {
+                data.extend(std::iter::repeat_n(
+                    (10 * (channel + 1) + record) as u8,
+                    1024,
+                ));
+            }
+        }
+        std::fs::write(format!("{}/test_batch.bin", dir), &data).unwrap();
+
+        let mut dataset = ImageDataset::open_cifar10(dir, false).unwrap();
+        assert!(dataset.len() == 2 && dataset.shape() == ImageShape::new(3, 32, 32));
+        let label = dataset.label(0);
+        assert!(dataset.class_name(label) == "cat");
+        let label = dataset.label(1);
+        assert!(dataset.class_name(label) == "ship");
+        let (input, expected) = dataset.get(1);
+        let green = dataset.shape().channel(&input, 1);
+        assert!(green[31][31] == 21.0 / 255.0 && input[0][0] == 11.0 / 255.0);
+        assert!(expected[8][0] == 1.0);
+
+        //Entrada no formato da BatchNorm 2d
+        let mut batch_norm = BatchNorm::new_2d(3, 32, 32);
+        batch_norm.propagate(&input);
+        assert!(batch_norm.neurons().rows() == 3072);
+
+        //Nomes das classes do arquivo de metadados
+        std::fs::write(
+            format!("{}/batches.meta.txt", dir),
+            "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n\n",
+        )
+        .unwrap();
+        let dataset = ImageDataset::open_cifar10(dir, false).unwrap();
+        assert!(dataset.class_names().len() == 10 && dataset.class_name(3) == "d");
+
+        std::fs::write(format!("{}/test_batch.bin", dir), &data[..100]).unwrap();
+        assert!(ImageDataset::open_cifar10(dir, false).is_err());
+        assert!(ImageDataset::open_cifar10(dir, true).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}